@@ -0,0 +1,443 @@
+//! Point-in-time snapshots of a workspace's memory, zipped into `.neomemory/backups/`.
+//!
+//! Writes to memory schedule a snapshot at most once an hour, and old snapshots are
+//! thinned out to one per hour, day and week going back `KEEP_HOURLY` hours,
+//! `KEEP_DAILY` days and `KEEP_WEEKLY` weeks. The newest snapshot is always kept.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+use serde::Serialize;
+use tauri::{Manager, State};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::NeoError;
+use crate::lock::{self, WorkspaceLocks};
+use crate::memory::{self, BACKUPS_DIR, MEMORY_DIR, TRANSIENT_DIRS};
+use crate::workspaces::{self, WorkspaceGrants};
+
+const BACKUP_PREFIX: &str = "memory-";
+const BACKUP_EXTENSION: &str = ".zip";
+const HOUR_MILLIS: u64 = 60 * 60 * 1000;
+const DAY_MILLIS: u64 = 24 * HOUR_MILLIS;
+const WEEK_MILLIS: u64 = 7 * DAY_MILLIS;
+/// Minimum time between two scheduled snapshots of a workspace.
+const SNAPSHOT_INTERVAL_MILLIS: u64 = HOUR_MILLIS;
+const KEEP_HOURLY: usize = 24;
+const KEEP_DAILY: usize = 7;
+const KEEP_WEEKLY: usize = 4;
+
+/// Workspaces a scheduled snapshot is under way for, so a burst of writes takes just
+/// one per workspace.
+static SNAPSHOTTING: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryBackup {
+    /// Pass to `restore_memory_backup`.
+    pub id: String,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    /// Size of the zip in bytes.
+    pub size: u64,
+}
+
+fn backups_dir(workspace: &Path) -> PathBuf {
+    memory::memory_dir(workspace).join(BACKUPS_DIR)
+}
+
+/// The snapshot time encoded in a backup id, or `None` if it isn't one.
+fn parse_id(id: &str) -> Option<u64> {
+    let millis = id.strip_prefix(BACKUP_PREFIX)?;
+    if millis.is_empty() || !millis.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    millis.parse().ok()
+}
+
+/// A workspace's snapshots, newest first.
+fn list(workspace: &Path) -> Vec<MemoryBackup> {
+    let mut backups: Vec<MemoryBackup> = fs::read_dir(backups_dir(workspace))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let id = name.strip_suffix(BACKUP_EXTENSION)?;
+            let created_at = parse_id(id)?;
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some(MemoryBackup {
+                id: id.to_string(),
+                created_at,
+                size: metadata.len(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Files of `.neomemory/` that belong in a snapshot, relative to it: everything but
+/// logs, caches, the lock, earlier backups and temp files.
+fn snapshot_files(memory: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, relative: &Path, out: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            if memory::is_temp_file(&name_str)
+                || (relative.as_os_str().is_empty() && TRANSIENT_DIRS.contains(&name_str.as_ref()))
+            {
+                continue;
+            }
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => walk(&entry.path(), &relative.join(&name), out),
+                Ok(t) if t.is_file() => out.push(relative.join(&name)),
+                _ => {}
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(memory, Path::new(""), &mut files);
+    files
+}
+
+fn write_zip(memory: &Path, target: &Path) -> Result<(), NeoError> {
+    let file = File::create(target)
+        .map_err(|e| NeoError::Io(format!("Failed to create {}: {e}", target.display())))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for relative in snapshot_files(memory) {
+        // Zip entry names always use `/`.
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut source = File::open(memory.join(&relative))
+            .map_err(|e| NeoError::Io(format!("Failed to read {MEMORY_DIR}/{name}: {e}")))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| NeoError::Failed(format!("Failed to add {name} to backup: {e}")))?;
+        io::copy(&mut source, &mut zip)
+            .map_err(|e| NeoError::Failed(format!("Failed to add {name} to backup: {e}")))?;
+    }
+    let file = zip
+        .finish()
+        .map_err(|e| NeoError::Io(format!("Failed to write backup: {e}")))?;
+    file.sync_all()
+        .map_err(|e| NeoError::Io(format!("Failed to write backup: {e}")))
+}
+
+/// Zip the workspace's memory into a new snapshot, written to a temp file and
+/// renamed into place so a half-written zip is never listed.
+fn snapshot(workspace: &Path) -> Result<MemoryBackup, NeoError> {
+    let memory = memory::memory_dir(workspace);
+    let dir = backups_dir(workspace);
+    fs::create_dir_all(&dir)
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}/{BACKUPS_DIR}: {e}")))?;
+    let created_at = workspaces::now_millis();
+    let id = format!("{BACKUP_PREFIX}{created_at}");
+    let path = dir.join(format!("{id}{BACKUP_EXTENSION}"));
+    let tmp = dir.join(memory::temp_name(&format!("{id}{BACKUP_EXTENSION}")));
+    let written = write_zip(&memory, &tmp).and_then(|()| {
+        fs::rename(&tmp, &path)
+            .map_err(|e| NeoError::Io(format!("Failed to save backup {id}: {e}")))
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    Ok(MemoryBackup {
+        id,
+        created_at,
+        size,
+    })
+}
+
+/// Ids of the snapshots rotation keeps, given newest first: the newest one in each
+/// of the `KEEP_HOURLY` hours, `KEEP_DAILY` days and `KEEP_WEEKLY` weeks up to the
+/// newest snapshot, which is always kept.
+fn kept(backups: &[MemoryBackup]) -> HashSet<&str> {
+    let mut keep = HashSet::new();
+    let Some(newest) = backups.first() else {
+        return keep;
+    };
+    keep.insert(newest.id.as_str());
+    for (period, count) in [
+        (HOUR_MILLIS, KEEP_HOURLY),
+        (DAY_MILLIS, KEEP_DAILY),
+        (WEEK_MILLIS, KEEP_WEEKLY),
+    ] {
+        let oldest = (newest.created_at / period).saturating_sub(count as u64 - 1);
+        let mut last = None;
+        for backup in backups {
+            let bucket = backup.created_at / period;
+            if bucket < oldest {
+                break;
+            }
+            if last != Some(bucket) {
+                last = Some(bucket);
+                keep.insert(&backup.id);
+            }
+        }
+    }
+    keep
+}
+
+/// Delete the snapshots rotation no longer keeps. Best-effort.
+fn prune(workspace: &Path) {
+    let backups = list(workspace);
+    let keep = kept(&backups);
+    let dir = backups_dir(workspace);
+    for backup in &backups {
+        if !keep.contains(backup.id.as_str()) {
+            let _ = fs::remove_file(dir.join(format!("{}{BACKUP_EXTENSION}", backup.id)));
+        }
+    }
+}
+
+/// Take a snapshot in the background if the workspace's newest one is more than
+/// `SNAPSHOT_INTERVAL_MILLIS` old, then prune. Called after each successful write to
+/// memory; failures are ignored, since the write itself already succeeded. The write
+/// lock is held throughout, so the snapshot never catches a write half done and
+/// pruning can't race a restore.
+pub fn schedule(workspace: &Path) {
+    let due = list(workspace).first().is_none_or(|latest| {
+        workspaces::now_millis().saturating_sub(latest.created_at) >= SNAPSHOT_INTERVAL_MILLIS
+    });
+    if !due {
+        return;
+    }
+    let workspace = workspace.to_path_buf();
+    let started = SNAPSHOTTING
+        .lock()
+        .is_ok_and(|mut running| running.insert(workspace.clone()));
+    if !started {
+        return;
+    }
+    thread::spawn(move || {
+        let _ = lock::with_write_lock(&workspace, || {
+            if snapshot(&workspace).is_ok() {
+                prune(&workspace);
+            }
+        });
+        if let Ok(mut running) = SNAPSHOTTING.lock() {
+            running.remove(&workspace);
+        }
+    });
+}
+
+/// Unpack a snapshot into `dir`, refusing entries that would land outside it.
+fn extract(backup: &Path, dir: &Path) -> Result<(), NeoError> {
+    let file =
+        File::open(backup).map_err(|e| NeoError::Io(format!("Failed to open backup: {e}")))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| NeoError::Failed(format!("Corrupt backup: {e}")))?;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| NeoError::Failed(format!("Corrupt backup: {e}")))?;
+        let Some(relative) = entry.enclosed_name() else {
+            return Err(NeoError::Failed(format!(
+                "Backup entry escapes {MEMORY_DIR}: {}",
+                entry.name()
+            )));
+        };
+        let target = dir.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| NeoError::Io(format!("Failed to restore {}: {e}", entry.name())))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| NeoError::Io(format!("Failed to restore {}: {e}", entry.name())))?;
+        }
+        let mut out = File::create(&target)
+            .map_err(|e| NeoError::Io(format!("Failed to restore {}: {e}", entry.name())))?;
+        io::copy(&mut entry, &mut out)
+            .map_err(|e| NeoError::Io(format!("Failed to restore {}: {e}", entry.name())))?;
+    }
+    Ok(())
+}
+
+/// Replace the snapshotted part of `.neomemory/` with the content of `staging`.
+fn replace_contents(memory: &Path, staging: &Path) -> Result<(), NeoError> {
+    for entry in fs::read_dir(memory)
+        .map_err(|e| NeoError::Io(format!("Failed to read {MEMORY_DIR}: {e}")))?
+        .flatten()
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if TRANSIENT_DIRS.contains(&name.as_str()) || memory::is_temp_file(&name) {
+            continue;
+        }
+        let removed = match entry.file_type() {
+            Ok(t) if t.is_dir() => fs::remove_dir_all(entry.path()),
+            _ => fs::remove_file(entry.path()),
+        };
+        removed.map_err(|e| NeoError::Io(format!("Failed to remove {MEMORY_DIR}/{name}: {e}")))?;
+    }
+    for entry in fs::read_dir(staging)
+        .map_err(|e| NeoError::Io(format!("Failed to read restored backup: {e}")))?
+        .flatten()
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if TRANSIENT_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+        fs::rename(entry.path(), memory.join(&name))
+            .map_err(|e| NeoError::Io(format!("Failed to restore {MEMORY_DIR}/{name}: {e}")))?;
+    }
+    Ok(())
+}
+
+fn restore(workspace: &Path, backup_id: &str) -> Result<MemoryBackup, NeoError> {
+    if parse_id(backup_id).is_none() {
+        return Err(NeoError::InvalidInput(format!(
+            "Invalid backup id: {backup_id}"
+        )));
+    }
+    let dir = backups_dir(workspace);
+    let backup = dir.join(format!("{backup_id}{BACKUP_EXTENSION}"));
+    if !backup.is_file() {
+        return Err(NeoError::NotFound(format!("Backup not found: {backup_id}")));
+    }
+
+    let staging = dir.join(format!(".restore.tmp-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    if let Err(e) = extract(&backup, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    // Snapshot what is about to be replaced, so the restore can be undone.
+    let previous = snapshot(workspace).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&staging);
+    })?;
+    let replaced = replace_contents(&memory::memory_dir(workspace), &staging);
+    let _ = fs::remove_dir_all(&staging);
+    replaced.map_err(|e| {
+        let message = format!(
+            "{e}. The memory from before the restore is in backup {}",
+            previous.id
+        );
+        e.with_message(message)
+    })?;
+    prune(workspace);
+    Ok(previous)
+}
+
+/// A workspace's memory snapshots, newest first.
+#[tauri::command]
+pub fn list_memory_backups(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+) -> Result<Vec<MemoryBackup>, NeoError> {
+    Ok(list(&grants.require(&workspace)?))
+}
+
+/// Roll a workspace's memory back to a snapshot from `list_memory_backups`.
+///
+/// The current memory is snapshotted first and that snapshot is returned, so the
+/// restore can itself be undone. Logs and other backups are left alone.
+#[tauri::command]
+pub async fn restore_memory_backup(
+    app: tauri::AppHandle,
+    workspace: String,
+    backup_id: String,
+) -> Result<MemoryBackup, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace)?;
+        app.state::<WorkspaceLocks>().while_held(&root, || {
+            lock::with_write_lock(&root, || restore(&root, &backup_id))
+        })??
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to restore backup: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An hour-aligned time far from the epoch, so periods don't saturate.
+    const BASE: u64 = 2800 * WEEK_MILLIS;
+
+    fn backups(times: impl IntoIterator<Item = u64>) -> Vec<MemoryBackup> {
+        let mut backups: Vec<MemoryBackup> = times
+            .into_iter()
+            .map(|created_at| MemoryBackup {
+                id: format!("{BACKUP_PREFIX}{created_at}"),
+                created_at,
+                size: 0,
+            })
+            .collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        backups
+    }
+
+    fn kept_times(backups: &[MemoryBackup]) -> Vec<u64> {
+        let keep = kept(backups);
+        backups
+            .iter()
+            .filter(|b| keep.contains(b.id.as_str()))
+            .map(|b| b.created_at)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_snapshot_of_each_hour() {
+        let list = backups([BASE, BASE + 1, BASE + HOUR_MILLIS, BASE + HOUR_MILLIS + 5]);
+        assert_eq!(kept_times(&list), [BASE + HOUR_MILLIS + 5, BASE + 1]);
+    }
+
+    #[test]
+    fn thins_older_snapshots_to_days_then_weeks() {
+        // One snapshot every hour for five weeks.
+        let list = backups((0..35 * 24).map(|hour| BASE + hour * HOUR_MILLIS));
+        let newest = list[0].created_at;
+        let times = kept_times(&list);
+
+        let hourly = times.iter().filter(|&&t| newest - t < DAY_MILLIS).count();
+        assert_eq!(hourly, KEEP_HOURLY);
+        // The newest of each of the last seven days, the first of which is covered by
+        // the hourly ones, and of each of the last four weeks, the first of which is
+        // covered by the daily ones.
+        assert_eq!(times.len(), KEEP_HOURLY + KEEP_DAILY - 1 + KEEP_WEEKLY - 1);
+        assert!(times
+            .iter()
+            .all(|&t| newest - t < KEEP_WEEKLY as u64 * WEEK_MILLIS));
+    }
+
+    #[test]
+    fn counts_periods_back_from_the_newest_snapshot() {
+        // Daily snapshots don't stretch the hourly window over weeks.
+        let list = backups((0..30).map(|day| BASE + day * DAY_MILLIS));
+        let newest = list[0].created_at;
+        let times = kept_times(&list);
+        let daily: Vec<u64> = (0..KEEP_DAILY as u64)
+            .map(|day| newest - day * DAY_MILLIS)
+            .collect();
+        assert_eq!(times[..KEEP_DAILY], daily[..]);
+        assert!(times.len() < KEEP_HOURLY);
+    }
+
+    #[test]
+    fn keeps_a_lone_snapshot() {
+        assert_eq!(kept_times(&backups([5])), [5]);
+        assert!(kept(&[]).is_empty());
+    }
+
+    #[test]
+    fn parses_only_backup_ids() {
+        assert_eq!(parse_id("memory-1700000000000"), Some(1_700_000_000_000));
+        assert_eq!(parse_id("memory-"), None);
+        assert_eq!(parse_id("memory-12a"), None);
+        assert_eq!(parse_id("memory-+1"), None);
+        assert_eq!(parse_id("other-1"), None);
+    }
+}
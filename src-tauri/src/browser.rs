@@ -0,0 +1,30 @@
+//! Opening links in the user's browser.
+
+use tauri_plugin_opener::OpenerExt;
+use url::Url;
+
+use crate::error::NeoError;
+
+/// Schemes `open_url_in_browser` will hand to the system. Links come from AI-generated
+/// content, so anything that could reach local files or run script is refused.
+const ALLOWED_SCHEMES: &[&str] = &["https", "http"];
+
+/// Open a web link in the default browser.
+///
+/// Only `http` and `https` URLs are opened; `file:`, `javascript:` and every other
+/// scheme are rejected before the opener plugin sees them.
+#[tauri::command]
+pub fn open_url_in_browser(app: tauri::AppHandle, url: String) -> Result<(), NeoError> {
+    let parsed =
+        Url::parse(url.trim()).map_err(|e| NeoError::InvalidInput(format!("Invalid URL: {e}")))?;
+    if !ALLOWED_SCHEMES.contains(&parsed.scheme()) {
+        return Err(NeoError::InvalidInput(format!(
+            "URL scheme not allowed: {} (allowed: {})",
+            parsed.scheme(),
+            ALLOWED_SCHEMES.join(", ")
+        )));
+    }
+    app.opener()
+        .open_url(parsed.as_str(), None::<&str>)
+        .map_err(|e| NeoError::Io(format!("Failed to open URL: {e}")))
+}
@@ -0,0 +1,195 @@
+//! Export and import of workspace registrations, so a setup can be moved to another
+//! machine. Bundles never contain API keys.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::denylist;
+use crate::error::NeoError;
+use crate::memory::{self, ProviderConfig};
+use crate::workspaces::{self, WorkspaceEntry, WorkspaceGrants};
+
+/// Current version of the bundle schema.
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBundle {
+    pub version: u32,
+    /// Unix timestamp in milliseconds.
+    #[serde(default)]
+    pub exported_at: u64,
+    pub workspaces: Vec<BundledWorkspace>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledWorkspace {
+    pub path: PathBuf,
+    pub name: String,
+    #[serde(default = "denylist::default_patterns")]
+    pub denylist: Vec<String>,
+    #[serde(default = "default_recursive")]
+    pub recursive: bool,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// The workspace's `config.json` provider settings, if it had any.
+    #[serde(default)]
+    pub provider_config: Option<ProviderConfig>,
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+/// What happened to one workspace of an imported bundle.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportStatus {
+    /// Added, waiting for the user to grant the folder on this machine.
+    Pending,
+    /// Already registered here; its settings were replaced.
+    Updated,
+    /// The folder doesn't exist on this machine.
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOutcome {
+    pub path: String,
+    pub status: ImportStatus,
+    pub error: Option<String>,
+}
+
+impl ImportOutcome {
+    fn new(path: &Path, status: ImportStatus, error: Option<String>) -> Self {
+        ImportOutcome {
+            path: path.to_string_lossy().into_owned(),
+            status,
+            error,
+        }
+    }
+}
+
+/// Export every registered workspace with its deny-list and provider settings as a
+/// JSON bundle.
+#[tauri::command]
+pub fn export_workspace_config(app: tauri::AppHandle) -> Result<String, NeoError> {
+    let bundle = ConfigBundle {
+        version: BUNDLE_VERSION,
+        exported_at: workspaces::now_millis(),
+        workspaces: workspaces::load(&app)
+            .into_iter()
+            .map(|entry| BundledWorkspace {
+                provider_config: entry
+                    .pending_config
+                    .or_else(|| memory::load_provider_config(&entry.path)),
+                path: entry.path,
+                name: entry.name,
+                denylist: entry.denylist,
+                recursive: entry.recursive,
+                workspace_id: entry.workspace_id,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&bundle)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize bundle: {e}")))
+}
+
+/// Apply one bundled workspace to the registry.
+fn import_entry(
+    app: &tauri::AppHandle,
+    grants: &WorkspaceGrants,
+    entries: &mut Vec<WorkspaceEntry>,
+    bundled: BundledWorkspace,
+) -> ImportOutcome {
+    let path = bundled.path.as_path();
+    let fail = |e: NeoError| ImportOutcome::new(path, ImportStatus::Failed, Some(e.to_string()));
+
+    if let Err(e) = denylist::compile(&bundled.denylist) {
+        return fail(e);
+    }
+    if let Some(config) = &bundled.provider_config {
+        if let Err(e) = memory::validate_provider_config(config) {
+            return fail(e);
+        }
+    }
+    let canonical = match path.canonicalize() {
+        Ok(canonical) if canonical.is_dir() => canonical,
+        _ => {
+            return ImportOutcome::new(
+                path,
+                ImportStatus::Skipped,
+                Some("Folder does not exist on this machine".to_string()),
+            )
+        }
+    };
+
+    let Some(entry) = entries.iter_mut().find(|e| e.path == canonical) else {
+        entries.push(WorkspaceEntry {
+            path: canonical,
+            name: bundled.name,
+            last_opened: 0,
+            denylist: bundled.denylist,
+            recursive: bundled.recursive,
+            workspace_id: bundled.workspace_id,
+            pending: true,
+            pending_config: bundled.provider_config,
+            stale: false,
+        });
+        return ImportOutcome::new(path, ImportStatus::Pending, None);
+    };
+
+    entry.name = bundled.name;
+    entry.denylist = bundled.denylist;
+    if entry.pending {
+        entry.pending_config = bundled.provider_config;
+    } else if let Some(config) = &bundled.provider_config {
+        if let Err(e) = memory::save_provider_config(&canonical, config) {
+            return fail(e);
+        }
+    }
+    if grants.contains(&canonical) {
+        if let Err(e) = denylist::apply(app, &canonical, &entry.denylist) {
+            return fail(e);
+        }
+    }
+    ImportOutcome::new(path, ImportStatus::Updated, None)
+}
+
+/// Import a bundle produced by `export_workspace_config`.
+///
+/// Nothing is granted: new workspaces are registered as pending and only restored
+/// once the user grants each folder on this machine. Folders that don't exist here are
+/// skipped, and the result reports what happened to every entry.
+#[tauri::command]
+pub async fn import_workspace_config(
+    app: tauri::AppHandle,
+    json: String,
+) -> Result<Vec<ImportOutcome>, NeoError> {
+    let bundle: ConfigBundle = serde_json::from_str(&json)
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid config bundle: {e}")))?;
+    if bundle.version == 0 || bundle.version > BUNDLE_VERSION {
+        return Err(NeoError::InvalidInput(format!(
+            "Unsupported config bundle version {} (expected at most {BUNDLE_VERSION})",
+            bundle.version
+        )));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        workspaces::update(&app, |entries| {
+            Ok(bundle
+                .workspaces
+                .into_iter()
+                .map(|bundled| import_entry(&app, &grants, entries, bundled))
+                .collect())
+        })
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to import workspace config: {e}")))?
+}
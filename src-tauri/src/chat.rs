@@ -0,0 +1,206 @@
+//! Chat completions made from the backend, so provider API keys never reach the
+//! frontend.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::watch;
+
+use crate::conversations::Message;
+use crate::dotenv::WorkspaceEnv;
+use crate::error::NeoError;
+use crate::http::{self, RetryPolicy};
+use crate::providers::NETWORK_ERROR;
+use crate::sse::SseDecoder;
+
+/// Event emitted with each piece of a streamed reply.
+pub const TOKEN_EVENT: &str = "chat://token";
+const OPENROUTER_CHAT_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+/// `data:` of the event that ends an OpenAI-style stream.
+const DONE_SENTINEL: &str = "[DONE]";
+
+/// Cancel signals of the streams in flight, keyed by request id.
+#[derive(Default)]
+pub struct ChatStreams(Mutex<HashMap<String, watch::Sender<bool>>>);
+
+/// Removes a stream's cancel signal when it ends, however it ends.
+struct StreamGuard<'a> {
+    streams: &'a ChatStreams,
+    request_id: String,
+}
+
+impl Drop for StreamGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut streams) = self.streams.0.lock() {
+            streams.remove(&self.request_id);
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatToken {
+    pub request_id: String,
+    pub token: String,
+}
+
+/// The `error.message` of an OpenRouter error body, or the body itself.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|value| value["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Describe a failed response. Rate limits and server errors were retried, so they
+/// say how many attempts were made.
+fn status_error(status: reqwest::StatusCode, attempts: u32, body: &str) -> NeoError {
+    if http::is_retryable(status) {
+        NeoError::Network(format!(
+            "OpenRouter returned {status} after {}: {}",
+            http::attempts(attempts),
+            error_message(body)
+        ))
+    } else {
+        NeoError::Failed(format!(
+            "OpenRouter returned {status}: {}",
+            error_message(body)
+        ))
+    }
+}
+
+fn cancelled_error(request_id: &str) -> NeoError {
+    NeoError::Cancelled(format!("Chat request {request_id} was cancelled"))
+}
+
+fn network_error(e: reqwest::Error) -> NeoError {
+    NeoError::Network(format!("{NETWORK_ERROR}: failed to reach OpenRouter: {e}"))
+}
+
+/// Handle one streamed event: `Ok(None)` at the end of the stream, otherwise the
+/// text it adds, which may be empty.
+fn token(data: &str) -> Result<Option<String>, NeoError> {
+    if data.trim() == DONE_SENTINEL {
+        return Ok(None);
+    }
+    let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+        return Ok(Some(String::new()));
+    };
+    // Errors after the response started arrive as a chunk instead of a status code.
+    if let Some(message) = chunk["error"]["message"].as_str() {
+        return Err(NeoError::Failed(format!("OpenRouter error: {message}")));
+    }
+    let text = chunk["choices"][0]["delta"]["content"]
+        .as_str()
+        .unwrap_or("");
+    Ok(Some(text.to_string()))
+}
+
+async fn stream(
+    app: &tauri::AppHandle,
+    request_id: &str,
+    key: &str,
+    model: String,
+    messages: Vec<Message>,
+    retry: RetryPolicy,
+    mut cancelled: watch::Receiver<bool>,
+) -> Result<String, NeoError> {
+    let request = http::client()
+        .post(OPENROUTER_CHAT_URL)
+        .bearer_auth(key)
+        .json(&json!({ "model": model, "messages": messages, "stream": true }));
+    let sent = tokio::select! {
+        sent = http::send_with_retry(request, &retry, "OpenRouter") => sent?,
+        _ = cancelled.changed() => return Err(cancelled_error(request_id)),
+    };
+    let mut response = sent.response;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(status_error(status, sent.attempts, &body));
+    }
+
+    let mut decoder = SseDecoder::default();
+    let mut reply = String::new();
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk.map_err(network_error)?,
+            _ = cancelled.changed() => return Err(cancelled_error(request_id)),
+        };
+        let Some(chunk) = chunk else {
+            return Err(NeoError::Network(format!(
+                "{NETWORK_ERROR}: OpenRouter closed the stream early"
+            )));
+        };
+        for event in decoder.push(&chunk) {
+            let Some(token) = token(&event.data)? else {
+                return Ok(reply);
+            };
+            if !token.is_empty() {
+                let _ = app.emit(
+                    TOKEN_EVENT,
+                    ChatToken {
+                        request_id: request_id.to_string(),
+                        token: token.clone(),
+                    },
+                );
+                reply.push_str(&token);
+            }
+        }
+    }
+}
+
+/// Stream a chat completion from OpenRouter with the saved API key.
+///
+/// Each piece of the reply is emitted as a `chat://token` event tagged with
+/// `requestId`, and the full reply is returned once the stream ends. Error bodies
+/// from OpenRouter are returned as readable messages. `cancel_chat_stream` with the
+/// same `requestId` stops the request, which then fails with `cancelled`.
+///
+/// Rate limits, server errors and failures to connect are retried with backoff as
+/// `retry` allows, until the reply starts streaming.
+#[tauri::command]
+pub async fn openrouter_chat_stream(
+    app: tauri::AppHandle,
+    request_id: String,
+    model: String,
+    messages: Vec<Message>,
+    retry: Option<RetryPolicy>,
+) -> Result<String, NeoError> {
+    let key = crate::read_provider_key(&app.state::<WorkspaceEnv>(), "openrouter")?;
+    let streams = app.state::<ChatStreams>();
+    let (cancel, cancelled) = watch::channel(false);
+    {
+        let mut active = streams
+            .0
+            .lock()
+            .map_err(|_| NeoError::Failed("Chat stream state is unavailable".to_string()))?;
+        if active.contains_key(&request_id) {
+            return Err(NeoError::InvalidInput(format!(
+                "Chat request {request_id} is already running"
+            )));
+        }
+        active.insert(request_id.clone(), cancel);
+    }
+    let _guard = StreamGuard {
+        streams: &streams,
+        request_id: request_id.clone(),
+    };
+    let retry = retry.unwrap_or_default();
+    stream(&app, &request_id, &key, model, messages, retry, cancelled).await
+}
+
+/// Cancel a stream started by `openrouter_chat_stream`. Returns whether it was still
+/// running.
+#[tauri::command]
+pub fn cancel_chat_stream(streams: State<'_, ChatStreams>, request_id: String) -> bool {
+    let cancel = streams
+        .0
+        .lock()
+        .ok()
+        .and_then(|mut active| active.remove(&request_id));
+    cancel.is_some_and(|cancel| cancel.send(true).is_ok())
+}
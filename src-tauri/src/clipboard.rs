@@ -0,0 +1,58 @@
+//! Clipboard access from Rust, so clipboard text can be cleaned up before it reaches
+//! a conversation or the system clipboard.
+
+use std::sync::Mutex;
+
+use arboard::Clipboard;
+use tauri::State;
+
+use crate::error::NeoError;
+
+/// The clipboard handle, created on first use and kept for the app's lifetime: on
+/// Linux the text we set is only served while its owning handle is alive.
+#[derive(Default)]
+pub struct ClipboardState(Mutex<Option<Clipboard>>);
+
+impl ClipboardState {
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&mut Clipboard) -> Result<T, NeoError>,
+    ) -> Result<T, NeoError> {
+        let mut clipboard = self
+            .0
+            .lock()
+            .map_err(|_| NeoError::Failed("Clipboard is unavailable".to_string()))?;
+        if clipboard.is_none() {
+            let opened = Clipboard::new()
+                .map_err(|e| NeoError::Io(format!("Failed to open clipboard: {e}")))?;
+            *clipboard = Some(opened);
+        }
+        match clipboard.as_mut() {
+            Some(clipboard) => f(clipboard),
+            None => Err(NeoError::Failed("Clipboard is unavailable".to_string())),
+        }
+    }
+}
+
+/// Read the clipboard as text.
+#[tauri::command]
+pub fn get_clipboard_text(clipboard: State<'_, ClipboardState>) -> Result<String, NeoError> {
+    clipboard.with(|c| {
+        c.get_text()
+            .map_err(|e| NeoError::Io(format!("Failed to read clipboard: {e}")))
+    })
+}
+
+/// Replace the clipboard with text. Null bytes are stripped since some platforms
+/// truncate or corrupt the clipboard at the first one.
+#[tauri::command]
+pub fn set_clipboard_text(
+    clipboard: State<'_, ClipboardState>,
+    text: String,
+) -> Result<(), NeoError> {
+    let text = text.replace('\0', "");
+    clipboard.with(|c| {
+        c.set_text(text)
+            .map_err(|e| NeoError::Io(format!("Failed to write clipboard: {e}")))
+    })
+}
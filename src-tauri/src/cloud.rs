@@ -0,0 +1,143 @@
+//! Cloud-synced folders (iCloud Drive, Dropbox, OneDrive, ...) whose files may be
+//! placeholders that only download when read.
+
+use std::fs::File;
+use std::io;
+use std::path::{Component, Path};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tauri::{Manager, State};
+
+use crate::error::NeoError;
+use crate::workspaces::WorkspaceGrants;
+
+/// Prefix of errors for files whose content hasn't been downloaded yet, so the UI can
+/// tell them apart from permission errors and offer `materialize_file`.
+pub const NOT_DOWNLOADED_ERROR: &str = "File is not downloaded";
+
+/// How long `materialize_file` waits for a download unless told otherwise.
+const DEFAULT_MATERIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `Library/CloudStorage` folder prefixes used by macOS File Provider apps.
+const CLOUD_STORAGE_PREFIXES: &[(&str, &str)] = &[
+    ("Dropbox", "dropbox"),
+    ("OneDrive", "oneDrive"),
+    ("GoogleDrive", "googleDrive"),
+    ("Box", "box"),
+];
+
+/// Default folder names of desktop sync clients.
+fn sync_folder(name: &str) -> Option<&'static str> {
+    if name == "Dropbox" || name.starts_with("Dropbox (") {
+        Some("dropbox")
+    } else if name == "OneDrive" || name.starts_with("OneDrive - ") {
+        Some("oneDrive")
+    } else if name == "Google Drive" {
+        Some("googleDrive")
+    } else {
+        None
+    }
+}
+
+/// Identify the sync service that manages a folder from its path.
+///
+/// Recognizes iCloud Drive, macOS File Provider folders under `Library/CloudStorage`,
+/// and the default Dropbox, OneDrive and Google Drive folder names.
+pub fn provider(path: &Path) -> Option<&'static str> {
+    let names: Vec<String> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    for (i, pair) in names.windows(2).enumerate() {
+        match (pair[0].as_str(), pair[1].as_str()) {
+            ("Library", "Mobile Documents") => return Some("icloud"),
+            ("Library", "CloudStorage") => {
+                let folder = names.get(i + 2)?;
+                return CLOUD_STORAGE_PREFIXES
+                    .iter()
+                    .find(|(prefix, _)| folder.starts_with(prefix))
+                    .map(|(_, provider)| *provider)
+                    .or(Some("other"));
+            }
+            _ => {}
+        }
+    }
+    names.iter().find_map(|n| sync_folder(n))
+}
+
+/// Whether a file is a placeholder whose content lives only in the cloud.
+#[cfg(target_os = "macos")]
+pub fn is_dataless(path: &Path) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    /// `SF_DATALESS` from `<sys/stat.h>`.
+    const SF_DATALESS: u32 = 0x4000_0000;
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.st_flags() & SF_DATALESS != 0)
+}
+
+/// Whether a file is a placeholder whose content lives only in the cloud.
+#[cfg(windows)]
+pub fn is_dataless(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+    std::fs::symlink_metadata(path).is_ok_and(|m| {
+        m.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) != 0
+    })
+}
+
+/// Whether a file is a placeholder whose content lives only in the cloud.
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn is_dataless(_path: &Path) -> bool {
+    false
+}
+
+/// Download a cloud placeholder file by reading it through, waiting at most
+/// `timeoutMs` (60 seconds by default). Files that are already local return at once.
+#[tauri::command]
+pub async fn materialize_file(
+    app: tauri::AppHandle,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> Result<(), NeoError> {
+    let timeout = timeout_ms.map_or(DEFAULT_MATERIALIZE_TIMEOUT, Duration::from_millis);
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants: State<'_, WorkspaceGrants> = app.state();
+        let canonical = Path::new(&path)
+            .canonicalize()
+            .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
+        if grants.covering(&canonical).is_none() {
+            return Err(NeoError::PermissionDenied(
+                "File is not inside a granted workspace".to_string(),
+            ));
+        }
+        if !is_dataless(&canonical) {
+            return Ok(());
+        }
+
+        // The read can block for as long as the download takes, so it runs on its own
+        // thread and is abandoned if it outlives the timeout.
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let result = File::open(&canonical).and_then(|mut f| io::copy(&mut f, &mut io::sink()));
+            let _ = tx.send(result);
+        });
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) if e.kind() == io::ErrorKind::PermissionDenied => Err(
+                NeoError::PermissionDenied(format!("Permission denied: {path}")),
+            ),
+            Ok(Err(e)) => Err(NeoError::Io(format!("Failed to download {path}: {e}"))),
+            Err(_) => Err(NeoError::Failed(format!(
+                "Timed out waiting for {path} to download"
+            ))),
+        }
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to download file: {e}")))?
+}
@@ -0,0 +1,333 @@
+//! Compaction of `.neomemory/`: journal fragments are merged into one archive per
+//! month, and journal entries and conversations past a retention window are dropped.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::conversations::{self, ConversationMeta, INDEX_FILE};
+use crate::encryption;
+use crate::error::NeoError;
+use crate::lock::{self, WorkspaceLocks};
+use crate::memory::{self, CONVERSATIONS_DIR, JOURNAL_DIR, MEMORY_DIR};
+use crate::quota;
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Journal files written by compaction, named `archive-YYYY-MM.json`.
+const ARCHIVE_PREFIX: &str = "archive-";
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionReport {
+    pub dry_run: bool,
+    pub bytes_before: u64,
+    /// Estimated for a dry run.
+    pub bytes_after: u64,
+    /// Journal entries plus conversations.
+    pub entries_before: usize,
+    pub entries_after: usize,
+    /// Files merged into archives or dropped, relative to `.neomemory/`.
+    pub removed: Vec<String>,
+    /// Archives written, relative to `.neomemory/`.
+    pub archives: Vec<String>,
+}
+
+/// What a compaction changes, worked out from the live `.neomemory/`.
+#[derive(Default)]
+struct Plan {
+    /// Entries to keep per archive file name, with their timestamps.
+    archives: BTreeMap<String, Vec<(u64, Value)>>,
+    /// Journal files folded into the archives, including archives that are rewritten.
+    merged: Vec<PathBuf>,
+    /// Merged files that weren't archives already.
+    fragments: usize,
+    expired_conversations: Vec<PathBuf>,
+    kept_conversations: Vec<ConversationMeta>,
+    entries_before: usize,
+    entries_after: usize,
+}
+
+impl Plan {
+    fn changes_anything(&self) -> bool {
+        self.fragments > 0 || self.entries_after < self.entries_before
+    }
+
+    fn removed(&self) -> impl Iterator<Item = &PathBuf> {
+        self.merged.iter().chain(&self.expired_conversations)
+    }
+}
+
+fn archive_name(millis: u64) -> String {
+    let (year, month, _) = workspaces::utc_date(millis);
+    format!("{ARCHIVE_PREFIX}{year:04}-{month:02}.json")
+}
+
+/// When a journal entry was written, falling back to its file's modification time.
+fn entry_timestamp(entry: &Value, fallback: u64) -> u64 {
+    ["timestamp", "createdAt", "updatedAt"]
+        .iter()
+        .find_map(|key| entry.get(key).and_then(Value::as_u64))
+        .unwrap_or(fallback)
+}
+
+fn modified_millis(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Merge every journal file into monthly archives. Files that aren't valid JSON are
+/// left alone.
+fn plan_journal(plan: &mut Plan, memory: &Path, cutoff: u64) {
+    for path in json_files(&memory.join(JOURNAL_DIR)) {
+        let Some(value) = encryption::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        else {
+            continue;
+        };
+        let fallback = modified_millis(&path);
+        let entries = match value {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+
+        plan.entries_before += entries.len();
+        for entry in entries {
+            let timestamp = entry_timestamp(&entry, fallback);
+            if timestamp >= cutoff {
+                plan.entries_after += 1;
+                plan.archives
+                    .entry(archive_name(timestamp))
+                    .or_default()
+                    .push((timestamp, entry));
+            }
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.starts_with(ARCHIVE_PREFIX) {
+            plan.fragments += 1;
+        }
+        plan.merged.push(path);
+    }
+    // A compaction interrupted after writing its archives leaves the fragments in
+    // place, so their entries show up twice the next time.
+    for entries in plan.archives.values_mut() {
+        let mut seen = HashSet::new();
+        entries.retain(|(timestamp, entry)| seen.insert((*timestamp, entry.to_string())));
+        entries.sort_by_key(|(timestamp, _)| *timestamp);
+    }
+}
+
+fn plan_conversations(plan: &mut Plan, memory: &Path, cutoff: u64) {
+    let dir = memory.join(CONVERSATIONS_DIR);
+    for path in json_files(&dir)
+        .into_iter()
+        .filter(|p| conversations::is_conversation_file(p))
+    {
+        let Some(meta) = conversations::read_meta(&path) else {
+            continue;
+        };
+        plan.entries_before += 1;
+        if meta.updated_at < cutoff && !quota::is_pinned(&path) {
+            plan.expired_conversations.push(path);
+        } else {
+            plan.entries_after += 1;
+            plan.kept_conversations.push(meta);
+        }
+    }
+    plan.kept_conversations
+        .sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+}
+
+fn archive_json(entries: &[(u64, Value)]) -> Result<String, NeoError> {
+    let values: Vec<&Value> = entries.iter().map(|(_, entry)| entry).collect();
+    serde_json::to_string_pretty(&values)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize archive: {e}")))
+}
+
+fn tree_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => tree_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |m| m.len())
+}
+
+/// Size `.neomemory/` would have after applying the plan.
+fn estimate_size(plan: &Plan, memory: &Path, before: u64) -> Result<u64, NeoError> {
+    let mut removed: u64 = plan.removed().map(|path| file_size(path)).sum();
+    let mut added = 0;
+    for entries in plan.archives.values() {
+        added += archive_json(entries)?.len() as u64;
+    }
+    if !plan.expired_conversations.is_empty() {
+        let index = serde_json::to_string_pretty(&plan.kept_conversations).map_err(|e| {
+            NeoError::Failed(format!("Failed to serialize conversation index: {e}"))
+        })?;
+        added += index.len() as u64;
+        removed += file_size(&memory.join(INDEX_FILE));
+    }
+    Ok((before + added).saturating_sub(removed))
+}
+
+/// Apply the plan to `.neomemory/`, one atomic write or removal per file.
+///
+/// New content is written before anything is removed, so an interruption leaves
+/// at worst fragments that were already archived, which the next compaction merges
+/// again without duplicating their entries.
+fn apply(plan: &Plan, workspace: &Path) -> Result<(), NeoError> {
+    let memory = memory::memory_dir(workspace);
+    let journal = memory.join(JOURNAL_DIR);
+    for (name, entries) in &plan.archives {
+        let archive = archive_json(entries)?;
+        encryption::write(workspace, &journal.join(name), archive.as_bytes())?;
+    }
+    if !plan.expired_conversations.is_empty() {
+        conversations::write_index(workspace, &plan.kept_conversations)?;
+    }
+    let rewritten = |path: &Path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.parent() == Some(journal.as_path()) && plan.archives.contains_key(name.as_ref())
+    };
+    for path in plan.removed().filter(|path| !rewritten(path)) {
+        let relative = path.strip_prefix(&memory).unwrap_or(path);
+        match fs::remove_file(path) {
+            Ok(()) => search::refresh(workspace, path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(NeoError::Io(format!(
+                    "Failed to remove {}: {e}",
+                    relative.display()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn relative_names<'a>(memory: &Path, paths: impl Iterator<Item = &'a PathBuf>) -> Vec<String> {
+    paths
+        .map(|path| {
+            let relative = path.strip_prefix(memory).unwrap_or(path);
+            relative.to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+fn compact(
+    workspace: &Path,
+    retention_days: Option<u32>,
+    dry_run: bool,
+) -> Result<CompactionReport, NeoError> {
+    let memory = memory::memory_dir(workspace);
+    let cutoff = retention_days.map_or(0, |days| {
+        workspaces::now_millis().saturating_sub(u64::from(days) * DAY_MILLIS)
+    });
+
+    let mut plan = Plan::default();
+    plan_journal(&mut plan, &memory, cutoff);
+    plan_conversations(&mut plan, &memory, cutoff);
+    let bytes_before = tree_size(&memory);
+    let changes_anything = plan.changes_anything();
+
+    let bytes_after = if dry_run || !changes_anything {
+        if changes_anything {
+            estimate_size(&plan, &memory, bytes_before)?
+        } else {
+            bytes_before
+        }
+    } else {
+        apply(&plan, workspace)?;
+        tree_size(&memory)
+    };
+
+    let (removed, archives) = if changes_anything {
+        let archives = plan
+            .archives
+            .keys()
+            .map(|name| {
+                Path::new(JOURNAL_DIR)
+                    .join(name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        // Archives that are only rewritten aren't reported as removed.
+        let removed = plan.removed().filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            !plan.archives.contains_key(name.as_ref())
+        });
+        (relative_names(&memory, removed), archives)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    Ok(CompactionReport {
+        dry_run,
+        bytes_before,
+        bytes_after,
+        entries_before: plan.entries_before,
+        entries_after: plan.entries_after,
+        removed,
+        archives,
+    })
+}
+
+/// Compact a workspace's `.neomemory/`.
+///
+/// Journal fragments are merged into one `journal/archive-YYYY-MM.json` per month.
+/// With `retentionDays`, journal entries and conversations older than that are
+/// dropped and the conversation index is rewritten; without it nothing is deleted.
+/// Pinned and starred conversations are kept however old they are. With `dryRun`
+/// nothing is written and the report says what would change.
+///
+/// Each file is replaced atomically, and archives are written before the files they
+/// replace are removed, so an interrupted compaction loses nothing. The workspace
+/// lock is held throughout.
+#[tauri::command]
+pub async fn compact_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    retention_days: Option<u32>,
+    dry_run: Option<bool>,
+) -> Result<CompactionReport, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace)?;
+        let dry_run = dry_run.unwrap_or(false);
+        if !memory::memory_dir(&root).is_dir() {
+            return Err(NeoError::Failed(format!("Workspace has no {MEMORY_DIR}")));
+        }
+        app.state::<WorkspaceLocks>().while_held(&root, || {
+            lock::with_write_lock(&root, || compact(&root, retention_days, dry_run))
+        })??
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to compact memory: {e}")))?
+}
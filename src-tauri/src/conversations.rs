@@ -0,0 +1,645 @@
+//! Conversation metadata for a workspace, served from a lightweight index so the
+//! sidebar doesn't have to parse every conversation file, and helpers for the
+//! messages themselves.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{Manager, State};
+
+use crate::backups;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::lock;
+use crate::memories;
+use crate::memory::{self, CONVERSATIONS_DIR, TRASH_DIR};
+use crate::portable;
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Index of conversation metadata inside `.neomemory/`.
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationMeta {
+    pub id: String,
+    pub title: String,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds.
+    pub updated_at: u64,
+    pub message_count: usize,
+}
+
+pub fn modified_millis(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_else(workspaces::now_millis)
+}
+
+/// Read a conversation file's metadata. Missing fields fall back to the file name
+/// and modification time; files that aren't JSON objects are skipped.
+pub fn read_meta(path: &Path) -> Option<ConversationMeta> {
+    let content = encryption::read_to_string(path).ok()?;
+    let value: Value = serde_json::from_str(&content).ok()?;
+    let object = value.as_object()?;
+    let modified = modified_millis(path);
+    let text = |key: &str| object.get(key).and_then(Value::as_str).map(str::to_string);
+    let millis = |key: &str| object.get(key).and_then(Value::as_u64);
+
+    Some(ConversationMeta {
+        id: text("id").or_else(|| Some(path.file_stem()?.to_string_lossy().into_owned()))?,
+        title: text("title").unwrap_or_else(|| "Untitled".to_string()),
+        created_at: millis("createdAt").unwrap_or(modified),
+        updated_at: millis("updatedAt").unwrap_or(modified),
+        message_count: object
+            .get("messages")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len),
+    })
+}
+
+pub fn is_conversation_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "json")
+}
+
+/// Build metadata for every conversation file, most recently updated first.
+fn scan(workspace: &Path) -> Vec<ConversationMeta> {
+    let dir = memory::memory_dir(workspace).join(CONVERSATIONS_DIR);
+    let mut conversations: Vec<ConversationMeta> = fs::read_dir(dir)
+        .into_iter()
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|path| is_conversation_file(path))
+        .filter_map(|path| read_meta(&path))
+        .collect();
+    conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+    conversations
+}
+
+fn read_index(workspace: &Path) -> Option<Vec<ConversationMeta>> {
+    let content = encryption::read_to_string(&memory::memory_dir(workspace).join(INDEX_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn write_index(workspace: &Path, conversations: &[ConversationMeta]) -> Result<(), NeoError> {
+    let content = serde_json::to_string_pretty(conversations)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize conversation index: {e}")))?;
+    let path = memory::memory_dir(workspace).join(INDEX_FILE);
+    encryption::write(workspace, &path, content.as_bytes())
+}
+
+/// Build the index from the conversation files if it doesn't exist yet.
+pub fn ensure_index(workspace: &Path) -> Result<(), NeoError> {
+    if memory::memory_dir(workspace).join(INDEX_FILE).exists() {
+        return Ok(());
+    }
+    write_index(workspace, &scan(workspace))
+}
+
+/// Refresh the index entry for a file just written, if it is a conversation, and
+/// schedule a memory snapshot.
+pub fn record_write(workspace: &Path, path: &Path) -> Result<(), NeoError> {
+    let dir = memory::memory_dir(workspace).join(CONVERSATIONS_DIR);
+    if path.parent() != Some(dir.as_path()) || !is_conversation_file(path) {
+        return Ok(());
+    }
+    let Some(meta) = read_meta(path) else {
+        return Ok(());
+    };
+
+    let mut conversations = read_index(workspace).unwrap_or_else(|| scan(workspace));
+    conversations.retain(|c| c.id != meta.id);
+    conversations.push(meta);
+    conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+    write_index(workspace, &conversations)?;
+    backups::schedule(workspace);
+    Ok(())
+}
+
+/// Drop conversations whose files were removed from the index.
+pub fn remove_from_index(workspace: &Path, ids: &[String]) -> Result<(), NeoError> {
+    let mut conversations = read_index(workspace).unwrap_or_else(|| scan(workspace));
+    conversations.retain(|c| !ids.contains(&c.id));
+    write_index(workspace, &conversations)
+}
+
+/// List a workspace's conversations, most recently updated first.
+///
+/// Served from `.neomemory/index.json`; a missing or corrupt index is rebuilt by
+/// scanning `.neomemory/conversations/`.
+#[tauri::command]
+pub fn list_workspace_conversations(
+    grants: State<'_, WorkspaceGrants>,
+    workspace_path: String,
+) -> Result<Vec<ConversationMeta>, NeoError> {
+    let workspace = grants.require(&workspace_path)?;
+    if let Some(conversations) = read_index(&workspace) {
+        return Ok(conversations);
+    }
+
+    let conversations = scan(&workspace);
+    if memory::memory_dir(&workspace).is_dir() {
+        write_index(&workspace, &conversations)?;
+    }
+    Ok(conversations)
+}
+
+fn trash_dir(workspace: &Path) -> PathBuf {
+    memory::memory_dir(workspace).join(TRASH_DIR)
+}
+
+fn check_id(id: &str) -> Result<(), NeoError> {
+    memories::validate_id(id)
+        .map_err(|_| NeoError::InvalidInput(format!("Invalid conversation id: {id}")))
+}
+
+/// The file holding a conversation: `<id>.json`, or whichever file records that id.
+fn conversation_file(workspace: &Path, id: &str) -> Option<PathBuf> {
+    let dir = memory::memory_dir(workspace).join(CONVERSATIONS_DIR);
+    let named = dir.join(format!("{id}.json"));
+    if named.is_file() {
+        return Some(named);
+    }
+    fs::read_dir(dir)
+        .into_iter()
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|path| is_conversation_file(path))
+        .find(|path| read_meta(path).is_some_and(|meta| meta.id == id))
+}
+
+/// The most recently trashed copy of a conversation, named `<timestamp>_<id>.json`.
+fn trashed_file(workspace: &Path, id: &str) -> Option<PathBuf> {
+    let suffix = format!("_{id}.json");
+    fs::read_dir(trash_dir(workspace))
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_suffix(&suffix)?.parse::<u64>().ok()?;
+            Some((stamp, entry.path()))
+        })
+        .max_by_key(|(stamp, _)| *stamp)
+        .map(|(_, path)| path)
+}
+
+fn trash(workspace: &Path, id: &str) -> Result<(), NeoError> {
+    let path = conversation_file(workspace, id)
+        .ok_or_else(|| NeoError::NotFound(format!("Conversation not found: {id}")))?;
+    let dir = trash_dir(workspace);
+    fs::create_dir_all(&dir)
+        .map_err(|e| NeoError::Io(format!("Failed to create {TRASH_DIR}: {e}")))?;
+    let trashed = dir.join(format!("{}_{id}.json", workspaces::now_millis()));
+    fs::rename(&path, &trashed).map_err(|e| {
+        NeoError::Io(format!(
+            "Failed to move conversation {id} to the trash: {e}"
+        ))
+    })?;
+    search::refresh(workspace, &path);
+    remove_from_index(workspace, &[id.to_string()])?;
+    backups::schedule(workspace);
+    Ok(())
+}
+
+fn untrash(workspace: &Path, id: &str) -> Result<(), NeoError> {
+    let trashed = trashed_file(workspace, id)
+        .ok_or_else(|| NeoError::NotFound(format!("Conversation not in the trash: {id}")))?;
+    if conversation_file(workspace, id).is_some() {
+        return Err(NeoError::InvalidInput(format!(
+            "A conversation with id {id} already exists"
+        )));
+    }
+    let dir = memory::memory_dir(workspace).join(CONVERSATIONS_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| NeoError::Io(format!("Failed to create {CONVERSATIONS_DIR}: {e}")))?;
+    let path = dir.join(format!("{id}.json"));
+    fs::rename(&trashed, &path)
+        .map_err(|e| NeoError::Io(format!("Failed to restore conversation {id}: {e}")))?;
+    search::refresh(workspace, &path);
+    record_write(workspace, &path)
+}
+
+fn empty_trash(workspace: &Path) -> Result<u32, NeoError> {
+    let Ok(entries) = fs::read_dir(trash_dir(workspace)) else {
+        return Ok(0);
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_file()) {
+            continue;
+        }
+        fs::remove_file(entry.path()).map_err(|e| {
+            NeoError::Io(format!(
+                "Failed to delete {}: {e}",
+                entry.file_name().to_string_lossy()
+            ))
+        })?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Move a conversation to `.neomemory/.trash/` instead of deleting it, so
+/// `restore_workspace_conversation` can bring it back until the trash is emptied.
+#[tauri::command]
+pub async fn delete_workspace_conversation(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    conversation_id: String,
+) -> Result<(), NeoError> {
+    check_id(&conversation_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace_path)?;
+        lock::with_write_lock(&root, || trash(&root, &conversation_id))?
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to delete conversation: {e}")))?
+}
+
+/// Move a conversation deleted with `delete_workspace_conversation` back, restoring
+/// the most recently deleted copy if it was deleted more than once. Fails if a
+/// conversation with the same id exists again.
+#[tauri::command]
+pub async fn restore_workspace_conversation(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    conversation_id: String,
+) -> Result<(), NeoError> {
+    check_id(&conversation_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace_path)?;
+        lock::with_write_lock(&root, || untrash(&root, &conversation_id))?
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to restore conversation: {e}")))?
+}
+
+/// Permanently delete every conversation in a workspace's trash. Returns how many
+/// files were removed.
+#[tauri::command]
+pub async fn empty_workspace_trash(
+    app: tauri::AppHandle,
+    workspace_path: String,
+) -> Result<u32, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace_path)?;
+        lock::with_write_lock(&root, || empty_trash(&root))?
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to empty the trash: {e}")))?
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+/// Shorten a conversation to fit a context window: only the last `keepLastN`
+/// non-system messages are kept, and the ones dropped are replaced by one system
+/// message holding `summaryPlaceholder`, at the position of the first of them.
+/// System messages are always kept where they are. No model is called; the summary
+/// is written by the frontend.
+#[tauri::command]
+pub fn compress_conversation(
+    messages: Vec<Message>,
+    keep_last_n: usize,
+    summary_placeholder: String,
+) -> Result<Vec<Message>, NeoError> {
+    let conversational = messages.iter().filter(|m| m.role != "system").count();
+    let mut to_drop = conversational.saturating_sub(keep_last_n);
+    if to_drop == 0 {
+        return Ok(messages);
+    }
+
+    let mut compressed = Vec::with_capacity(messages.len() - to_drop + 1);
+    let mut summary = Some(Message {
+        role: "system".to_string(),
+        content: summary_placeholder,
+    });
+    for message in messages {
+        if message.role == "system" || to_drop == 0 {
+            compressed.push(message);
+        } else {
+            to_drop -= 1;
+            compressed.extend(summary.take());
+        }
+    }
+    Ok(compressed)
+}
+
+/// The role a speaker label in a transcript stands for, if it is one.
+fn speaker_role(label: &str) -> Option<&'static str> {
+    match label.trim().to_ascii_lowercase().as_str() {
+        "user" | "human" | "you" | "me" => Some("user"),
+        "assistant" | "ai" | "chatgpt" | "claude" | "gemini" => Some("assistant"),
+        "system" => Some("system"),
+        _ => None,
+    }
+}
+
+/// The role an export-style heading (`## User`, `## Assistant`, `## System` or
+/// `## Role: tool`) stands for. Only the exact line export writes counts.
+fn export_heading(line: &str) -> Option<String> {
+    let label = line.strip_prefix("## ")?;
+    match label {
+        "User" | "Assistant" | "System" => Some(label.to_ascii_lowercase()),
+        _ => label
+            .strip_prefix("Role: ")
+            .filter(|role| !role.trim().is_empty())
+            .map(str::to_string),
+    }
+}
+
+/// Recognize a line that starts a new message: a `## User` style heading, a
+/// `**User:**` label or a `Human:` prefix. Returns the role and any text after the
+/// label on the same line.
+fn message_start(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        if let Some(role) = export_heading(trimmed) {
+            return Some((role, ""));
+        }
+        let label = trimmed.trim_start_matches('#').trim().trim_matches('*');
+        return speaker_role(label.trim_end_matches(':')).map(|role| (role.to_string(), ""));
+    }
+    if let Some(rest) = trimmed.strip_prefix("**") {
+        let (label, text) = rest.split_once("**")?;
+        let label = label.trim_end_matches(':');
+        let text = text.strip_prefix(':').unwrap_or(text);
+        return speaker_role(label).map(|role| (role.to_string(), text.trim_start()));
+    }
+    let (label, text) = trimmed.split_once(':')?;
+    // Only a bare word counts, so "Note: ..." in a message isn't a new speaker.
+    if label.contains(char::is_whitespace) {
+        return None;
+    }
+    speaker_role(label).map(|role| (role.to_string(), text.trim_start()))
+}
+
+/// The length of the backtick run a line opens a code fence with, if it does.
+fn fence_len(line: &str) -> Option<usize> {
+    let len = line.trim_start().chars().take_while(|&c| c == '`').count();
+    (len >= 3).then_some(len)
+}
+
+/// The transcript after a leading YAML front-matter block, if it has one.
+fn strip_front_matter(markdown: &str) -> &str {
+    let Some(rest) = markdown
+        .strip_prefix("---\n")
+        .or_else(|| markdown.strip_prefix("---\r\n"))
+    else {
+        return markdown;
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == "---" {
+            return &rest[offset..];
+        }
+    }
+    markdown
+}
+
+/// Whether a content line would be read back as the start of a message, so export
+/// has to escape it. Lines that are already escaped count too, so escaping nests.
+fn needs_escape(line: &str) -> bool {
+    message_start(line.trim_start_matches('\\')).is_some()
+}
+
+/// Whether a line inside a code fence has to be escaped: only export-style headings
+/// end a fence on import.
+fn needs_escape_in_fence(line: &str) -> bool {
+    export_heading(line.trim_start_matches('\\')).is_some()
+}
+
+/// Parse a chat transcript exported as Markdown into messages.
+///
+/// Messages start at a speaker heading (`## User` / `## Assistant`, as in ChatGPT
+/// exports), a bold label (`**User:**`) or a plain prefix (`Human:` / `Assistant:`,
+/// as in Claude.ai exports). Labels inside fenced code blocks are left as content,
+/// as are labels escaped with a leading `\` by `export_conversation_to_markdown`,
+/// but a heading written by export always starts a message, so a fence one message
+/// left open doesn't swallow the rest. A YAML front-matter block is skipped. Text
+/// before the first label, or a transcript with no labels at all, is kept as a
+/// `user` message rather than rejected; a label with no text after it is kept as an
+/// empty message.
+#[tauri::command]
+pub fn import_conversation_from_markdown(markdown: String) -> Result<Vec<Message>, NeoError> {
+    let mut messages = Vec::new();
+    let mut role = "user".to_string();
+    // Whether the current message was started by a label, so it's kept even if empty.
+    let mut labelled = false;
+    let mut lines: Vec<&str> = Vec::new();
+    let mut fence: Option<usize> = None;
+    let mut flush = |role: &str, labelled: bool, lines: &mut Vec<&str>| {
+        let content = lines.join("\n").trim_matches('\n').to_string();
+        if labelled || !content.trim().is_empty() {
+            messages.push(Message {
+                role: role.to_string(),
+                content,
+            });
+        }
+        lines.clear();
+    };
+
+    let source: Vec<&str> = strip_front_matter(&markdown).lines().collect();
+    for (i, &line) in source.iter().enumerate() {
+        if let Some(next) = export_heading(line) {
+            flush(&role, labelled, &mut lines);
+            (role, labelled, fence) = (next, true, None);
+            continue;
+        }
+        if let Some(open) = fence {
+            if line.trim_start().starts_with("```") {
+                fence = None;
+                // Export closes a fence a message left open with one backtick more
+                // than it was opened with, right before the next heading.
+                let last = source[i + 1..]
+                    .iter()
+                    .find(|next| !next.is_empty())
+                    .is_none_or(|next| export_heading(next).is_some());
+                if last && line.trim() == "`".repeat(open + 1) {
+                    continue;
+                }
+            } else if let Some(escaped) = line
+                .strip_prefix('\\')
+                .filter(|rest| needs_escape_in_fence(rest))
+            {
+                lines.push(escaped);
+                continue;
+            }
+        } else if let Some(len) = fence_len(line) {
+            fence = Some(len);
+        } else {
+            if let Some((next, text)) = message_start(line) {
+                flush(&role, labelled, &mut lines);
+                (role, labelled) = (next, true);
+                lines.push(text);
+                continue;
+            }
+            if let Some(escaped) = line.strip_prefix('\\').filter(|rest| needs_escape(rest)) {
+                lines.push(escaped);
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    flush(&role, labelled, &mut lines);
+
+    if messages.is_empty() {
+        return Err(NeoError::InvalidInput("Transcript is empty".to_string()));
+    }
+    Ok(messages)
+}
+
+/// Render a conversation as a Markdown document that `import_conversation_from_markdown`
+/// reads back into the same messages.
+///
+/// The document starts with YAML front matter holding the title, today's date (UTC)
+/// and the message count, followed by a `## User`, `## Assistant` or `## System`
+/// section per message, or `## Role: <role>` for any other role. Content is kept as
+/// written, code fences included; lines outside fences that would read as a new
+/// speaker, and headings inside them, are escaped with a leading `\`. A fence left
+/// open at the end of a message is closed with one more backtick than it was opened
+/// with, which import drops again. Leading and trailing blank lines of each message
+/// are not kept.
+#[tauri::command]
+pub fn export_conversation_to_markdown(
+    messages: Vec<Message>,
+    title: String,
+) -> Result<String, NeoError> {
+    let title = match title.trim() {
+        "" => "Untitled",
+        title => title,
+    };
+    // A JSON string is also a valid double-quoted YAML scalar.
+    let quoted_title = serde_json::to_string(title)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize title: {e}")))?;
+    let (year, month, day) = workspaces::utc_date(workspaces::now_millis());
+    let mut out = format!(
+        "---\ntitle: {quoted_title}\ndate: {year:04}-{month:02}-{day:02}\nmessages: {}\n---\n",
+        messages.len()
+    );
+
+    for message in &messages {
+        let heading = match message.role.as_str() {
+            role if role.trim().is_empty() => "User".to_string(),
+            role @ ("user" | "assistant" | "system") => portable::capitalize(role),
+            role => format!("Role: {}", role.replace(['\r', '\n'], " ")),
+        };
+        out.push_str(&format!("\n## {heading}\n\n"));
+        let mut fence: Option<usize> = None;
+        for line in message.content.trim_matches('\n').lines() {
+            let escape = match fence {
+                Some(_) if line.trim_start().starts_with("```") => {
+                    fence = None;
+                    false
+                }
+                Some(_) => needs_escape_in_fence(line),
+                None => match fence_len(line) {
+                    Some(len) => {
+                        fence = Some(len);
+                        false
+                    }
+                    None => needs_escape(line),
+                },
+            };
+            if escape {
+                out.push('\\');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        if let Some(open) = fence {
+            out.push_str(&"`".repeat(open + 1));
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn round_trip(messages: Vec<Message>) -> Vec<Message> {
+        let markdown = export_conversation_to_markdown(messages, "Chat".to_string()).unwrap();
+        import_conversation_from_markdown(markdown).unwrap()
+    }
+
+    fn assert_round_trips(messages: Vec<Message>) {
+        let back = round_trip(messages.clone());
+        assert_eq!(
+            back.iter()
+                .map(|m| (&m.role, &m.content))
+                .collect::<Vec<_>>(),
+            messages
+                .iter()
+                .map(|m| (&m.role, &m.content))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn keeps_roles_and_empty_messages() {
+        assert_round_trips(vec![
+            message("system", "Be brief."),
+            message("user", ""),
+            message("tool", "{\"ok\": true}"),
+            message("assistant", "  indented\n\ttabbed  "),
+        ]);
+    }
+
+    #[test]
+    fn escapes_speaker_lines() {
+        assert_round_trips(vec![
+            message(
+                "user",
+                "Human: not a speaker\n\\## User\n**Assistant:** either",
+            ),
+            message("assistant", "```\n## User\n\\## Role: tool\n```\nAI: done"),
+        ]);
+    }
+
+    #[test]
+    fn closes_fences_left_open() {
+        let messages = vec![
+            message("assistant", "```rust\nfn main() {}"),
+            message("user", "User: still a separate message"),
+            message("assistant", "~~~ not a fence\n```\nstill open"),
+        ];
+        let markdown =
+            export_conversation_to_markdown(messages.clone(), "Chat".to_string()).unwrap();
+        assert!(markdown.contains("fn main() {}\n````\n"));
+        assert_round_trips(messages);
+    }
+
+    #[test]
+    fn reads_other_transcripts() {
+        let messages =
+            import_conversation_from_markdown("Hi\n\n**Claude:** Hello\nUser:\n".to_string())
+                .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            (messages[1].role.as_str(), messages[1].content.as_str()),
+            ("assistant", "Hello")
+        );
+        assert_eq!(
+            (messages[2].role.as_str(), messages[2].content.as_str()),
+            ("user", "")
+        );
+    }
+}
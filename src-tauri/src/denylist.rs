@@ -0,0 +1,167 @@
+//! Sensitive paths inside a granted workspace that stay off-limits.
+//!
+//! The plugin scope only accepts literal paths in its forbid APIs, so patterns are
+//! matched against the workspace tree when it is granted and every match is forbidden
+//! individually. Paths resolved through Neo's own commands are also checked against
+//! the patterns directly, which covers files created after the grant.
+//!
+//! The walk skips dependency and build directories and git's object store, which can
+//! hold hundreds of thousands of files. Files in them are still refused through
+//! Neo's commands, just not forbidden in the plugin scope.
+
+use std::fs;
+use std::path::{Component, Path};
+
+use glob::{MatchOptions, Pattern};
+use tauri_plugin_fs::FsExt;
+
+use crate::error::NeoError;
+
+/// Patterns applied to workspaces that haven't configured their own list.
+pub const DEFAULT_PATTERNS: &[&str] = &["**/.git/config", "**/.env*", "**/*.pem", "**/id_rsa*"];
+
+/// Error prefix for denied paths, so the UI can tell them apart from missing files.
+pub const DENIED_ERROR: &str = "Denied by workspace deny-list";
+
+/// Directories the walk never descends into, matched by name.
+const PRUNED_DIRS: &[&str] = &["node_modules", "target", ".venv", "__pycache__"];
+/// Directories inside `.git` the walk never descends into.
+const PRUNED_GIT_DIRS: &[&str] = &["objects", "lfs", "logs"];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: !cfg!(windows),
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+pub fn default_patterns() -> Vec<String> {
+    DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+/// Parse deny-list globs, rejecting the whole list if any pattern is invalid.
+pub fn compile(patterns: &[String]) -> Result<Vec<Pattern>, NeoError> {
+    patterns
+        .iter()
+        .map(|p| {
+            Pattern::new(p).map_err(|e| {
+                NeoError::InvalidInput(format!("Invalid deny-list pattern '{p}': {e}"))
+            })
+        })
+        .collect()
+}
+
+/// Whether a workspace-relative path matches any deny-list pattern.
+pub fn is_denied(patterns: &[Pattern], relative: &Path) -> bool {
+    patterns
+        .iter()
+        .any(|p| p.matches_path_with(relative, MATCH_OPTIONS))
+}
+
+fn is_pruned(relative: &Path) -> bool {
+    let mut names = relative.components().rev().map(|c| c.as_os_str());
+    let Some(name) = names.next().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    PRUNED_DIRS.contains(&name)
+        || (PRUNED_GIT_DIRS.contains(&name) && names.next().is_some_and(|n| n == ".git"))
+}
+
+/// Whether some path beneath the workspace-relative directory `dir` could match
+/// `pattern`. Patterns are compared one component at a time until one with a `*`,
+/// which can match separators too and so reach any depth.
+fn could_match_below(pattern: &Pattern, dir: &Path) -> bool {
+    let parts: Vec<&str> = pattern.as_str().split(['/', '\\']).collect();
+    for (i, component) in dir.components().enumerate() {
+        let Component::Normal(name) = component else {
+            return true;
+        };
+        match parts.get(i) {
+            Some(part) if part.contains('*') => return true,
+            // The pattern ends here, so nothing deeper can match it.
+            Some(_) if i + 1 >= parts.len() => return false,
+            Some(part) => {
+                let matches = Pattern::new(part).map_or(true, |p| {
+                    p.matches_with(&name.to_string_lossy(), MATCH_OPTIONS)
+                });
+                if !matches {
+                    return false;
+                }
+            }
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Forbid every file and directory under `root` that matches a pattern.
+///
+/// Matching directories are forbidden recursively and not descended into, and
+/// neither are pruned directories or ones no pattern can match inside. Symlinks are
+/// not followed.
+///
+/// This walks the workspace, so callers run it off the main thread.
+pub fn apply(app: &tauri::AppHandle, root: &Path, patterns: &[String]) -> Result<(), NeoError> {
+    let patterns = compile(patterns)?;
+    if patterns.is_empty() {
+        return Ok(());
+    }
+    let scope = app.fs_scope();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+
+            if is_denied(&patterns, relative) {
+                let result = if file_type.is_dir() {
+                    scope.forbid_directory(&path, true)
+                } else {
+                    scope.forbid_file(&path)
+                };
+                result.map_err(|e| NeoError::Failed(format!("Failed to apply deny-list: {e}")))?;
+            } else if file_type.is_dir()
+                && !is_pruned(relative)
+                && patterns.iter().any(|p| could_match_below(p, relative))
+            {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn below(pattern: &str, dir: &str) -> bool {
+        could_match_below(&Pattern::new(pattern).unwrap(), Path::new(dir))
+    }
+
+    #[test]
+    fn prunes_heavy_directories() {
+        assert!(is_pruned(Path::new("node_modules")));
+        assert!(is_pruned(Path::new("crates/app/target")));
+        assert!(is_pruned(Path::new(".git/objects")));
+        assert!(!is_pruned(Path::new(".git")));
+        assert!(!is_pruned(Path::new("src/objects")));
+    }
+
+    #[test]
+    fn descends_only_where_a_pattern_can_match() {
+        assert!(below("**/.env*", "a/b/c"));
+        assert!(below("secrets/*.json", "secrets"));
+        assert!(!below("secrets/*.json", "src"));
+        assert!(below("*.pem", "src"));
+        assert!(!below("config.json", "src"));
+        assert!(below("config/**/key", "config/a/b"));
+        assert!(!below("a/b", "a/b"));
+    }
+}
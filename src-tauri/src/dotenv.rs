@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::error::NeoError;
+
+pub const ENV_FILE: &str = ".env";
+/// Event emitted when a `.env` file has lines that couldn't be parsed.
+pub const DOTENV_WARNING_EVENT: &str = "dotenv-warning";
+
+/// Variables loaded from `.env` files: the granted workspace's, consulted before the
+/// process environment, and the app config dir's, consulted after it.
+#[derive(Default)]
+pub struct WorkspaceEnv {
+    workspace: Mutex<Option<(PathBuf, HashMap<String, String>)>>,
+    config: Mutex<HashMap<String, String>>,
+}
+
+impl WorkspaceEnv {
+    /// A variable from the workspace `.env`.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let store = self.workspace.lock().ok()?;
+        store.as_ref()?.1.get(key).cloned()
+    }
+
+    /// A variable from the app config dir's `.env`.
+    pub fn get_config(&self, key: &str) -> Option<String> {
+        self.config.lock().ok()?.get(key).cloned()
+    }
+
+    /// A copy of every variable from the workspace `.env`.
+    pub fn snapshot(&self) -> HashMap<String, String> {
+        self.workspace
+            .lock()
+            .ok()
+            .and_then(|store| store.as_ref().map(|(_, vars)| vars.clone()))
+            .unwrap_or_default()
+    }
+
+    /// A copy of every variable from the app config dir's `.env`.
+    pub fn config_snapshot(&self) -> HashMap<String, String> {
+        self.config
+            .lock()
+            .map(|store| store.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the workspace variables with those parsed from `workspace`'s `.env`.
+    pub fn replace(&self, workspace: &Path, vars: HashMap<String, String>) {
+        if let Ok(mut store) = self.workspace.lock() {
+            *store = Some((workspace.to_path_buf(), vars));
+        }
+    }
+
+    pub fn replace_config(&self, vars: HashMap<String, String>) {
+        if let Ok(mut store) = self.config.lock() {
+            *store = vars;
+        }
+    }
+
+    /// Drop the workspace variables if they came from `workspace`, once its grant is
+    /// revoked.
+    pub fn forget(&self, workspace: &Path) {
+        if let Ok(mut store) = self.workspace.lock() {
+            if store.as_ref().is_some_and(|(root, _)| root == workspace) {
+                *store = None;
+            }
+        }
+    }
+}
+
+/// Payload of `DOTENV_WARNING_EVENT`. Problems name line numbers, never values.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DotenvWarning {
+    pub path: String,
+    pub problems: Vec<String>,
+}
+
+/// A parsed `.env` file.
+pub struct Parsed {
+    pub vars: HashMap<String, String>,
+    /// The lines that were skipped or look wrong, by line number.
+    pub problems: Vec<String>,
+}
+
+/// Parse the contents of a `.env` file into key/value pairs.
+///
+/// Supports blank lines, `#` comments, an optional `export ` prefix, and single- or
+/// double-quoted values. Lines without `=` are skipped and reported in `problems`.
+pub fn parse(content: &str) -> Parsed {
+    let mut vars = HashMap::new();
+    let mut problems = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+
+        let Some((key, value)) = line.split_once('=') else {
+            problems.push(format!("line {number}: expected KEY=value"));
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            problems.push(format!("line {number}: missing variable name"));
+            continue;
+        }
+
+        let value = value.trim();
+        let unterminated = ['"', '\'']
+            .iter()
+            .any(|&q| value.starts_with(q) && (value.len() < 2 || !value.ends_with(q)));
+        if unterminated {
+            problems.push(format!("line {number}: unterminated quote in {key}"));
+        }
+        vars.insert(key.to_string(), parse_value(value));
+    }
+
+    Parsed { vars, problems }
+}
+
+/// Read and parse the `.env` file in `dir`. `Ok(None)` when there is none.
+pub fn load(dir: &Path) -> Result<Option<Parsed>, NeoError> {
+    let path = dir.join(ENV_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(Some(parse(&content))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(NeoError::Io(format!(
+            "Failed to read {}: {e}",
+            path.display()
+        ))),
+    }
+}
+
+fn parse_value(value: &str) -> String {
+    let quoted = |q: char| value.len() >= 2 && value.starts_with(q) && value.ends_with(q);
+
+    if quoted('"') {
+        return value[1..value.len() - 1]
+            .replace("\\n", "\n")
+            .replace("\\\"", "\"");
+    }
+    if quoted('\'') {
+        return value[1..value.len() - 1].to_string();
+    }
+
+    // Unquoted values may carry a trailing ` # comment`.
+    match value.find(" #") {
+        Some(idx) => value[..idx].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        let parsed = parse(
+            "# comment\n\nA=1\nexport  B = two \nC=\"line\\nnext \\\"q\\\"\"\nD='raw\\n # kept'\nE=v # note\nF=\n",
+        );
+        let get = |key: &str| parsed.vars.get(key).map(String::as_str);
+        assert_eq!(get("A"), Some("1"));
+        assert_eq!(get("B"), Some("two"));
+        assert_eq!(get("C"), Some("line\nnext \"q\""));
+        assert_eq!(get("D"), Some("raw\\n # kept"));
+        assert_eq!(get("E"), Some("v"));
+        assert_eq!(get("F"), Some(""));
+        assert!(parsed.problems.is_empty());
+    }
+
+    #[test]
+    fn reports_problems_by_line_without_values() {
+        let parsed = parse("GOOD=1\nno equals sign\n=secret\nKEY=\"open\nQ='\n");
+        assert_eq!(
+            parsed.problems,
+            [
+                "line 2: expected KEY=value",
+                "line 3: missing variable name",
+                "line 4: unterminated quote in KEY",
+                "line 5: unterminated quote in Q",
+            ]
+        );
+        assert!(parsed.problems.iter().all(|p| !p.contains("secret")));
+        assert_eq!(parsed.vars.get("KEY").map(String::as_str), Some("\"open"));
+    }
+
+    #[test]
+    fn later_lines_win() {
+        let parsed = parse("A=1\nA=2\n");
+        assert_eq!(parsed.vars.get("A").map(String::as_str), Some("2"));
+    }
+}
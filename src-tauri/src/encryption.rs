@@ -0,0 +1,521 @@
+//! Optional encryption at rest for a workspace's `.neomemory/`.
+//!
+//! The key is derived from a passphrase with Argon2 and kept in the OS keychain,
+//! wrapped with a random key held in a separate keychain entry.
+//! Encrypted files start with a plaintext header carrying the format version and
+//! the key's salt, followed by an XChaCha20-Poly1305 nonce and the ciphertext. The
+//! manifest, `config.json` and other bookkeeping stay readable, and the manifest
+//! records whether the workspace is encrypted.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::error::NeoError;
+use crate::lock::{self, WorkspaceLocks};
+use crate::logging;
+use crate::memory::{
+    self, BACKUPS_DIR, CONFIG_FILE, GITIGNORE_FILE, LOGS_DIR, MANIFEST_FILE, MEMORY_DIR,
+};
+use crate::workspaces::{self, WorkspaceGrants};
+
+pub const LOCKED_ERROR: &str = "Memory is locked";
+
+/// Keychain service holding memory keys; the base64 salt is the account.
+const KEYCHAIN_SERVICE: &str = "dev.neo.memorykeys";
+/// Account of the key that memory keys are wrapped with. Not valid base64, so it
+/// can't collide with a salt.
+const WRAPPING_ACCOUNT: &str = "wrapping-key";
+/// Marks a keychain entry holding a wrapped key. Entries written before keys were
+/// wrapped hold the bare base64 key and are rewrapped when next read.
+const WRAPPED_PREFIX: &str = "wrapped-v1:";
+const MAGIC: &[u8] = b"NEOENC";
+/// Current version of the encrypted file format.
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN;
+/// Sealed into the manifest so a passphrase can be checked before anything is
+/// decrypted with it.
+const KEY_CHECK: &[u8] = b"neo-memory-key-check";
+
+type Key = [u8; 32];
+
+/// Keys unlocked in this session, keyed by base64 salt, so the keychain isn't asked
+/// on every read.
+static KEYS: Mutex<BTreeMap<String, Key>> = Mutex::new(BTreeMap::new());
+
+/// The manifest's `encryption` field.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionInfo {
+    pub version: u8,
+    /// Base64 Argon2 salt, also written into every encrypted file's header.
+    pub salt: String,
+    /// Base64 `KEY_CHECK` sealed with the key.
+    pub key_check: String,
+    /// Unix timestamp in milliseconds.
+    #[serde(default)]
+    pub enabled_at: u64,
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn locked() -> NeoError {
+    NeoError::Locked(format!(
+        "{LOCKED_ERROR}: unlock the workspace with its passphrase"
+    ))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, NeoError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| NeoError::Failed(format!("Failed to derive memory key: {e}")))?;
+    Ok(key)
+}
+
+fn keychain_entry(salt: &str) -> Result<keyring::Entry, NeoError> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, salt)
+        .map_err(|e| NeoError::PermissionDenied(format!("Keychain access denied: {e}")))
+}
+
+fn decode_key(encoded: &str) -> Option<Key> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+}
+
+/// The key memory keys are wrapped with, created on first use.
+fn wrapping_key() -> Result<Key, NeoError> {
+    let entry = keychain_entry(WRAPPING_ACCOUNT)?;
+    match entry.get_password() {
+        Ok(stored) => decode_key(&stored)
+            .ok_or_else(|| NeoError::Failed("Wrapping key in the keychain is corrupt".to_string())),
+        Err(keyring::Error::NoEntry) => {
+            let mut key = [0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            entry.set_password(&encode(&key)).map_err(|e| {
+                NeoError::Io(format!("Failed to save wrapping key to keychain: {e}"))
+            })?;
+            Ok(key)
+        }
+        Err(e) => Err(NeoError::PermissionDenied(format!(
+            "Keychain access denied: {e}"
+        ))),
+    }
+}
+
+/// Seal a memory key with the wrapping key, bound to its salt.
+fn wrap(salt: &str, key: &Key) -> Result<String, NeoError> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(&wrapping_key()?.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: key,
+                aad: salt.as_bytes(),
+            },
+        )
+        .map_err(|_| NeoError::Failed("Failed to wrap memory key".to_string()))?;
+    let mut wrapped = nonce.to_vec();
+    wrapped.extend_from_slice(&ciphertext);
+    Ok(format!("{WRAPPED_PREFIX}{}", encode(&wrapped)))
+}
+
+fn unwrap(salt: &str, wrapped: &str) -> Result<Key, NeoError> {
+    let corrupt = || NeoError::Failed("Memory key in the keychain is corrupt".to_string());
+    let wrapped = base64::engine::general_purpose::STANDARD
+        .decode(wrapped)
+        .map_err(|_| corrupt())?;
+    if wrapped.len() < NONCE_LEN {
+        return Err(corrupt());
+    }
+    let (nonce, ciphertext) = wrapped.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(&wrapping_key()?.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: salt.as_bytes(),
+            },
+        )
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(corrupt)
+}
+
+/// Look up the key for a salt in this session's cache, then in the keychain.
+fn key_for(salt: &str) -> Result<Option<Key>, NeoError> {
+    if let Some(key) = KEYS.lock().ok().and_then(|keys| keys.get(salt).copied()) {
+        return Ok(Some(key));
+    }
+    let stored = match keychain_entry(salt)?.get_password() {
+        Ok(stored) => stored,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => {
+            return Err(NeoError::PermissionDenied(format!(
+                "Keychain access denied: {e}"
+            )))
+        }
+    };
+    let key = match stored.strip_prefix(WRAPPED_PREFIX) {
+        Some(wrapped) => unwrap(salt, wrapped)?,
+        None => {
+            let key = decode_key(&stored).ok_or_else(|| {
+                NeoError::Failed("Memory key in the keychain is corrupt".to_string())
+            })?;
+            store_key(salt, &key)?;
+            key
+        }
+    };
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.insert(salt.to_string(), key);
+    }
+    Ok(Some(key))
+}
+
+fn store_key(salt: &str, key: &Key) -> Result<(), NeoError> {
+    keychain_entry(salt)?
+        .set_password(&wrap(salt, key)?)
+        .map_err(|e| NeoError::Io(format!("Failed to save memory key to keychain: {e}")))?;
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.insert(salt.to_string(), *key);
+    }
+    Ok(())
+}
+
+fn forget_key(salt: &str) -> Result<(), NeoError> {
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.remove(salt);
+    }
+    match keychain_entry(salt)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(NeoError::Io(format!(
+            "Failed to remove memory key from keychain: {e}"
+        ))),
+    }
+}
+
+fn is_sealed(content: &[u8]) -> bool {
+    content.starts_with(MAGIC)
+}
+
+fn seal(key: &Key, salt: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, NeoError> {
+    let mut sealed = Vec::with_capacity(HEADER_LEN + NONCE_LEN + plaintext.len() + 16);
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(FORMAT_VERSION);
+    sealed.extend_from_slice(salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    // The header is authenticated too, so its salt can't be swapped.
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &sealed,
+            },
+        )
+        .map_err(|_| NeoError::Failed("Failed to encrypt memory".to_string()))?;
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn unseal(key: &Key, sealed: &[u8]) -> Result<Vec<u8>, NeoError> {
+    if sealed.len() < HEADER_LEN + NONCE_LEN {
+        return Err(NeoError::Failed(
+            "Failed to decrypt memory: the file is truncated".to_string(),
+        ));
+    }
+    let (header, rest) = sealed.split_at(HEADER_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| {
+            NeoError::Failed(
+                "Failed to decrypt memory: the file is corrupt or has another key".to_string(),
+            )
+        })
+}
+
+/// Decrypt file content if it is encrypted, and return it unchanged if it isn't.
+pub fn open(content: Vec<u8>) -> Result<Vec<u8>, NeoError> {
+    if !is_sealed(&content) {
+        return Ok(content);
+    }
+    if content.len() < HEADER_LEN {
+        return Err(NeoError::Failed(
+            "Failed to decrypt memory: the file is truncated".to_string(),
+        ));
+    }
+    let version = content[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(NeoError::Failed(format!(
+            "Unsupported memory encryption version {version}"
+        )));
+    }
+    let salt = encode(&content[MAGIC.len() + 1..HEADER_LEN]);
+    let key = key_for(&salt)?.ok_or_else(locked)?;
+    unseal(&key, &content)
+}
+
+/// Read a file inside `.neomemory/` as text, decrypting it if needed.
+pub fn read_to_string(path: &Path) -> Result<String, NeoError> {
+    let content = fs::read(path)
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", path.display())))?;
+    String::from_utf8(open(content)?)
+        .map_err(|_| NeoError::Failed(format!("File is binary: {}", path.display())))
+}
+
+/// The workspace's encryption settings from its manifest, if it is encrypted.
+pub fn info(workspace: &Path) -> Option<EncryptionInfo> {
+    let content = fs::read_to_string(memory::memory_dir(workspace).join(MANIFEST_FILE)).ok()?;
+    let mut manifest: Value = serde_json::from_str(&content).ok()?;
+    serde_json::from_value(manifest.get_mut("encryption")?.take()).ok()
+}
+
+fn decode_salt(info: &EncryptionInfo) -> Result<Vec<u8>, NeoError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(&info.salt)
+        .ok()
+        .filter(|salt| salt.len() == SALT_LEN)
+        .ok_or_else(|| {
+            NeoError::CorruptManifest(format!(
+                "Corrupt manifest: {MEMORY_DIR}/{MANIFEST_FILE}: invalid salt"
+            ))
+        })
+}
+
+/// Write a file of a workspace's memory atomically, encrypting it if the workspace
+/// is encrypted. `path` may be inside a staging copy of `.neomemory/`.
+pub fn write(workspace: &Path, path: &Path, content: &[u8]) -> Result<(), NeoError> {
+    let Some(info) = info(workspace) else {
+        return memory::write_atomic(path, content);
+    };
+    let key = key_for(&info.salt)?.ok_or_else(locked)?;
+    memory::write_atomic(path, &seal(&key, &decode_salt(&info)?, content)?)
+}
+
+/// Top-level `.neomemory/` entries that are never encrypted.
+const UNPROTECTED: &[&str] = &[
+    MANIFEST_FILE,
+    CONFIG_FILE,
+    GITIGNORE_FILE,
+    LOGS_DIR,
+    BACKUPS_DIR,
+];
+
+/// Whether a file inside `.neomemory/` holds memory content, as opposed to the
+/// manifest, settings and logs, which are never encrypted. Backups are left as they
+/// were copied.
+pub fn is_protected(workspace: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(memory::memory_dir(workspace)) else {
+        return false;
+    };
+    let Some(first) = relative.components().next() else {
+        return false;
+    };
+    let first = first.as_os_str().to_string_lossy();
+    !UNPROTECTED.contains(&first.as_ref())
+        && !lock::is_lock_file(&first)
+        && !logging::is_log_file(&first)
+}
+
+/// Every protected file in `.neomemory/`, skipping temp files.
+fn protected_files(workspace: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => walk(&path, out),
+                Ok(t) if t.is_file() && !(name.starts_with('.') && name.contains(".tmp-")) => {
+                    out.push(path);
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(&memory::memory_dir(workspace), &mut files);
+    files.retain(|path| is_protected(workspace, path));
+    files
+}
+
+/// Rewrite every protected file with a plaintext transform.
+fn rewrite_all(
+    workspace: &Path,
+    transform: impl Fn(Vec<u8>) -> Result<Option<Vec<u8>>, NeoError>,
+) -> Result<(), NeoError> {
+    for path in protected_files(workspace) {
+        let content = fs::read(&path)
+            .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", path.display())))?;
+        if let Some(updated) = transform(content)? {
+            memory::write_atomic(&path, &updated)?;
+        }
+    }
+    Ok(())
+}
+
+fn set_manifest_encryption(
+    workspace: &Path,
+    info: Option<&EncryptionInfo>,
+) -> Result<(), NeoError> {
+    let value = info
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize {MANIFEST_FILE}: {e}")))?;
+    memory::update_manifest(workspace, |raw| match value {
+        Some(value) => {
+            raw.insert("encryption".to_string(), value);
+        }
+        None => {
+            raw.remove("encryption");
+        }
+    })
+}
+
+/// Derive a key from the passphrase and check it against the manifest.
+fn check_passphrase(info: &EncryptionInfo, passphrase: &str) -> Result<Key, NeoError> {
+    let key = derive_key(passphrase, &decode_salt(info)?)?;
+    let key_check = base64::engine::general_purpose::STANDARD
+        .decode(&info.key_check)
+        .map_err(|e| {
+            NeoError::CorruptManifest(format!(
+                "Corrupt manifest: {MEMORY_DIR}/{MANIFEST_FILE}: {e}"
+            ))
+        })?;
+    match is_sealed(&key_check).then(|| unseal(&key, &key_check)) {
+        Some(Ok(check)) if check == KEY_CHECK => Ok(key),
+        _ => Err(NeoError::InvalidInput("Wrong passphrase".to_string())),
+    }
+}
+
+fn enable(workspace: &Path, passphrase: &str) -> Result<(), NeoError> {
+    if info(workspace).is_some() {
+        return Err(NeoError::InvalidInput(
+            "Memory encryption is already enabled".to_string(),
+        ));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let info = EncryptionInfo {
+        version: FORMAT_VERSION,
+        salt: encode(&salt),
+        key_check: encode(&seal(&key, &salt, KEY_CHECK)?),
+        enabled_at: workspaces::now_millis(),
+    };
+    store_key(&info.salt, &key)?;
+    // Recorded first, so anything written while the files are converted is encrypted.
+    set_manifest_encryption(workspace, Some(&info))?;
+    rewrite_all(workspace, |content| {
+        (!is_sealed(&content))
+            .then(|| seal(&key, &salt, &content))
+            .transpose()
+    })
+}
+
+fn disable(workspace: &Path) -> Result<(), NeoError> {
+    let Some(info) = info(workspace) else {
+        return Err(NeoError::InvalidInput(
+            "Memory encryption is not enabled".to_string(),
+        ));
+    };
+    if key_for(&info.salt)?.is_none() {
+        return Err(locked());
+    }
+    set_manifest_encryption(workspace, None)?;
+    rewrite_all(workspace, |content| {
+        is_sealed(&content).then(|| open(content)).transpose()
+    })?;
+    forget_key(&info.salt)
+}
+
+fn require_memory(app: &tauri::AppHandle, workspace: &str) -> Result<PathBuf, NeoError> {
+    let root = app.state::<WorkspaceGrants>().require(workspace)?;
+    if !memory::memory_dir(&root).is_dir() {
+        return Err(NeoError::Failed(format!("Workspace has no {MEMORY_DIR}")));
+    }
+    Ok(root)
+}
+
+/// Encrypt a workspace's memory with a key derived from `passphrase`.
+///
+/// The key is saved in the OS keychain, so reads and writes stay transparent on
+/// this machine; elsewhere, or once the keychain entry is gone, memory commands fail
+/// with `locked` until `unlock_memory` is called. Existing memory files are encrypted
+/// in place while the workspace lock is held.
+#[tauri::command]
+pub async fn enable_memory_encryption(
+    app: tauri::AppHandle,
+    workspace: String,
+    passphrase: String,
+) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = require_memory(&app, &workspace)?;
+        if passphrase.is_empty() {
+            return Err(NeoError::InvalidInput("Passphrase is empty".to_string()));
+        }
+        app.state::<WorkspaceLocks>().while_held(&root, || {
+            lock::with_write_lock(&root, || enable(&root, &passphrase))
+        })??
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to enable memory encryption: {e}")))?
+}
+
+/// Decrypt every memory file of a workspace and turn encryption off. The workspace
+/// must be unlocked.
+#[tauri::command]
+pub async fn disable_memory_encryption(
+    app: tauri::AppHandle,
+    workspace: String,
+) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = require_memory(&app, &workspace)?;
+        app.state::<WorkspaceLocks>()
+            .while_held(&root, || lock::with_write_lock(&root, || disable(&root)))??
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to disable memory encryption: {e}")))?
+}
+
+/// Unlock an encrypted workspace with its passphrase and save the key in the keychain.
+#[tauri::command]
+pub async fn unlock_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    passphrase: String,
+) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = require_memory(&app, &workspace)?;
+        let info = info(&root).ok_or_else(|| {
+            NeoError::InvalidInput("Memory encryption is not enabled".to_string())
+        })?;
+        let key = check_passphrase(&info, &passphrase)?;
+        store_key(&info.salt, &key)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to unlock memory: {e}")))?
+}
@@ -0,0 +1,127 @@
+//! The error type returned by Tauri commands.
+
+use std::fmt;
+use std::io;
+
+use serde_json::json;
+use tauri::ipc::InvokeError;
+
+/// Why a command failed. Serialized as `{ kind, message }` so the UI can react to the
+/// category, such as retrying on `network` or prompting on `permissionDenied`, and
+/// still show the message.
+#[derive(Debug)]
+pub enum NeoError {
+    NotFound(String),
+    PermissionDenied(String),
+    Io(String),
+    Subprocess(String),
+    Network(String),
+    InvalidInput(String),
+    /// The workspace is locked by another running Neo instance.
+    AlreadyOpen(String),
+    /// The workspace's memory is encrypted and its key isn't on this machine.
+    Locked(String),
+    /// The user dismissed a prompt; not worth reporting.
+    Cancelled(String),
+    /// A write would take the workspace's memory over its quota.
+    QuotaExceeded(String),
+    /// The workspace's manifest can't be parsed.
+    CorruptManifest(String),
+    /// Anything that doesn't fit another category.
+    Failed(String),
+}
+
+impl NeoError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NeoError::NotFound(_) => "notFound",
+            NeoError::PermissionDenied(_) => "permissionDenied",
+            NeoError::Io(_) => "io",
+            NeoError::Subprocess(_) => "subprocess",
+            NeoError::Network(_) => "network",
+            NeoError::InvalidInput(_) => "invalidInput",
+            NeoError::AlreadyOpen(_) => "alreadyOpen",
+            NeoError::Locked(_) => "locked",
+            NeoError::Cancelled(_) => "cancelled",
+            NeoError::QuotaExceeded(_) => "quotaExceeded",
+            NeoError::CorruptManifest(_) => "corruptManifest",
+            NeoError::Failed(_) => "failed",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            NeoError::NotFound(message)
+            | NeoError::PermissionDenied(message)
+            | NeoError::Io(message)
+            | NeoError::Subprocess(message)
+            | NeoError::Network(message)
+            | NeoError::InvalidInput(message)
+            | NeoError::AlreadyOpen(message)
+            | NeoError::Locked(message)
+            | NeoError::Cancelled(message)
+            | NeoError::QuotaExceeded(message)
+            | NeoError::CorruptManifest(message)
+            | NeoError::Failed(message) => message,
+        }
+    }
+
+    /// The same kind of error with `message` in place of its own, to add context.
+    pub fn with_message(self, message: String) -> Self {
+        match self {
+            NeoError::NotFound(_) => NeoError::NotFound(message),
+            NeoError::PermissionDenied(_) => NeoError::PermissionDenied(message),
+            NeoError::Io(_) => NeoError::Io(message),
+            NeoError::Subprocess(_) => NeoError::Subprocess(message),
+            NeoError::Network(_) => NeoError::Network(message),
+            NeoError::InvalidInput(_) => NeoError::InvalidInput(message),
+            NeoError::AlreadyOpen(_) => NeoError::AlreadyOpen(message),
+            NeoError::Locked(_) => NeoError::Locked(message),
+            NeoError::Cancelled(_) => NeoError::Cancelled(message),
+            NeoError::QuotaExceeded(_) => NeoError::QuotaExceeded(message),
+            NeoError::CorruptManifest(_) => NeoError::CorruptManifest(message),
+            NeoError::Failed(_) => NeoError::Failed(message),
+        }
+    }
+}
+
+impl fmt::Display for NeoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for NeoError {}
+
+/// How a failed command's error reaches the frontend. Every command returns
+/// `NeoError`, and this conversion happens once per failed invocation as its response
+/// is built, so it is also where failures are logged; the invoke handler itself
+/// never sees the result of an async command.
+impl From<NeoError> for InvokeError {
+    fn from(error: NeoError) -> Self {
+        if !matches!(error, NeoError::Cancelled(_)) {
+            tracing::error!(
+                kind = error.kind(),
+                message = error.message(),
+                "command failed"
+            );
+        }
+        InvokeError(json!({ "kind": error.kind(), "message": error.message() }))
+    }
+}
+
+impl From<io::Error> for NeoError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => NeoError::NotFound(error.to_string()),
+            io::ErrorKind::PermissionDenied => NeoError::PermissionDenied(error.to_string()),
+            _ => NeoError::Io(error.to_string()),
+        }
+    }
+}
+
+impl From<NeoError> for String {
+    fn from(error: NeoError) -> Self {
+        error.to_string()
+    }
+}
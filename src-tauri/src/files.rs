@@ -0,0 +1,375 @@
+//! Reading and writing files inside granted workspaces from Rust, with the same
+//! containment and deny-list checks applied to every path.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{Manager, State};
+use tauri_plugin_fs::FsExt;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::cloud;
+use crate::conversations;
+use crate::denylist;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::lock;
+use crate::memory::{self, MANIFEST_FILE, MEMORY_DIR};
+use crate::paths;
+use crate::quota::{self, EvictedFile};
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Largest file `read_workspace_file` returns unless the caller raises the limit.
+const DEFAULT_MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Resolve `relative` inside a granted workspace, rejecting anything that escapes it
+/// through `..` or symlinks, that is in a subfolder of a non-recursive grant, or that
+/// matches the workspace's deny-list.
+pub fn resolve(
+    app: &tauri::AppHandle,
+    grants: &WorkspaceGrants,
+    workspace: &str,
+    relative: &str,
+) -> Result<PathBuf, NeoError> {
+    let root = grants.require(workspace)?;
+    let resolved = paths::resolve_in_workspace(&root, Path::new(relative))?;
+    if resolved != root
+        && resolved.parent() != Some(root.as_path())
+        && !grants.covers_subfolders(&root)
+    {
+        return Err(NeoError::PermissionDenied(format!(
+            "Subfolders of this workspace were not granted: {relative}"
+        )));
+    }
+    let patterns = denylist::compile(&workspaces::denylist_for(app, &root))?;
+    let inside = resolved.strip_prefix(&root).unwrap_or(&resolved);
+    if denylist::is_denied(&patterns, inside) {
+        return Err(NeoError::PermissionDenied(format!(
+            "{}: {relative}",
+            denylist::DENIED_ERROR
+        )));
+    }
+    Ok(resolved)
+}
+
+fn read_error(relative_path: &str, e: std::io::Error) -> NeoError {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        NeoError::PermissionDenied(format!("Permission denied: {relative_path}"))
+    } else {
+        NeoError::Io(format!("Failed to read {relative_path}: {e}"))
+    }
+}
+
+/// Resolve a path inside a granted workspace; see `resolve`.
+#[tauri::command]
+pub fn resolve_workspace_path(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    relative: String,
+) -> Result<String, NeoError> {
+    resolve(&app, &grants, &workspace, &relative).map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Read a UTF-8 text file inside a granted workspace.
+///
+/// Files larger than `maxBytes` (10 MB by default) and binary files are rejected, as
+/// are cloud placeholders that haven't been downloaded yet. The file is read on a
+/// blocking thread.
+#[tauri::command]
+pub async fn read_workspace_file(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    relative_path: String,
+    max_bytes: Option<u64>,
+) -> Result<String, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let path = resolve(&app, &grants, &workspace_path, &relative_path)?;
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_READ_BYTES);
+
+        let metadata = fs::metadata(&path).map_err(|e| read_error(&relative_path, e))?;
+        if !metadata.is_file() {
+            return Err(NeoError::InvalidInput(format!(
+                "Not a file: {relative_path}"
+            )));
+        }
+        if metadata.len() > max_bytes {
+            return Err(NeoError::InvalidInput(format!(
+                "File is too large: {relative_path} is {} bytes (limit {max_bytes})",
+                metadata.len()
+            )));
+        }
+
+        // Reading a cloud placeholder would block on the download; let the UI decide.
+        if cloud::is_dataless(&path) {
+            return Err(NeoError::Io(format!(
+                "{}: {relative_path}",
+                cloud::NOT_DOWNLOADED_ERROR
+            )));
+        }
+        let bytes = fs::read(&path).map_err(|e| read_error(&relative_path, e))?;
+        let bytes = encryption::open(bytes)?;
+        if bytes.contains(&0) {
+            return Err(NeoError::Failed(format!("File is binary: {relative_path}")));
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| NeoError::Failed(format!("File is binary: {relative_path}")))
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to read file: {e}")))?
+}
+
+/// Write a file inside `.neomemory/`, making room for it within the quota. Callers
+/// hold the workspace's write lock.
+fn write_memory_file(
+    root: &Path,
+    path: &Path,
+    content: &str,
+) -> Result<Vec<EvictedFile>, NeoError> {
+    let evicted = quota::make_room(root, path, content.len() as u64)?;
+    if encryption::is_protected(root, path) {
+        encryption::write(root, path, content.as_bytes())?;
+    } else {
+        memory::write_atomic(path, content.as_bytes())?;
+    }
+    search::refresh(root, path);
+    conversations::record_write(root, path)?;
+    Ok(evicted)
+}
+
+/// Write a text file inside a granted workspace, creating missing parent directories.
+///
+/// The content goes to a temp sibling first and is renamed over the target, so a
+/// crash mid-write never leaves a truncated file. Writes to conversation files also
+/// refresh the conversation index.
+///
+/// Writes inside `.neomemory/` take the workspace's write lock and are checked
+/// against its memory quota; they return the files evicted to make room, if any. The
+/// write runs on a blocking thread.
+#[tauri::command]
+pub async fn write_workspace_file(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    relative_path: String,
+    content: String,
+) -> Result<Vec<EvictedFile>, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let path = resolve(&app, &grants, &workspace_path, &relative_path)?;
+        if path.is_dir() {
+            return Err(NeoError::InvalidInput(format!(
+                "Not a file: {relative_path}"
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                NeoError::Io(format!("Failed to create folders for {relative_path}: {e}"))
+            })?;
+        }
+        let root = grants.require(&workspace_path)?;
+        if path.starts_with(memory::memory_dir(&root)) {
+            return lock::with_write_lock(&root, || write_memory_file(&root, &path, &content))?;
+        }
+        memory::write_atomic(&path, content.as_bytes())?;
+        search::refresh(&root, &path);
+        Ok(Vec::new())
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to write file: {e}")))?
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+fn hash_file(
+    path: &Path,
+    relative_path: &str,
+    algorithm: HashAlgorithm,
+) -> Result<String, NeoError> {
+    let mut file = fs::File::open(path).map_err(|e| read_error(relative_path, e))?;
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            io::copy(&mut file, &mut hasher).map_err(|e| read_error(relative_path, e))?;
+            Ok(hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect())
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            io::copy(&mut file, &mut hasher).map_err(|e| read_error(relative_path, e))?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+/// Hash a file inside a granted workspace and return the lowercase hex digest, so the
+/// frontend can tell whether a file changed since it was last read.
+///
+/// Paths are checked the same way as for `read_workspace_file`, and cloud placeholders
+/// are rejected rather than downloaded. Large files are hashed on a blocking thread.
+#[tauri::command]
+pub async fn compute_file_hash(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    relative_path: String,
+    algorithm: HashAlgorithm,
+) -> Result<String, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let path = resolve(&app, &grants, &workspace_path, &relative_path)?;
+        if !path.is_file() {
+            return Err(NeoError::InvalidInput(format!("Not a file: {relative_path}")));
+        }
+        if cloud::is_dataless(&path) {
+            return Err(NeoError::Io(format!(
+                "{}: {relative_path}",
+                cloud::NOT_DOWNLOADED_ERROR
+            )));
+        }
+        hash_file(&path, &relative_path, algorithm)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to hash file: {e}")))?
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashOutcome {
+    pub path: String,
+    /// Why the item wasn't trashed; `None` on success.
+    pub error: Option<String>,
+}
+
+/// Move one file or folder inside a granted workspace to the trash.
+///
+/// Only the parent is canonicalized, so a symlink is trashed rather than its target.
+/// Workspace roots, `.neomemory/` and its manifest, forbidden paths and paths on the
+/// workspace's deny-list are refused.
+fn trash_item(app: &tauri::AppHandle, path: &str) -> Result<(), NeoError> {
+    let raw = Path::new(path);
+    let (Some(parent), Some(name)) = (raw.parent(), raw.file_name()) else {
+        return Err(NeoError::InvalidInput(format!("Invalid path: {path}")));
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
+    let target = parent.join(name);
+    if target.symlink_metadata().is_err() {
+        return Err(NeoError::NotFound(format!("File not found: {path}")));
+    }
+
+    let grants = app.state::<WorkspaceGrants>();
+    let Some(root) = grants.covering(&parent) else {
+        return Err(NeoError::PermissionDenied(format!(
+            "Path is not inside a granted workspace: {path}"
+        )));
+    };
+    let is_workspace_root =
+        grants.contains(&target) || workspaces::load(app).iter().any(|e| e.path == target);
+    if is_workspace_root {
+        return Err(NeoError::Failed(format!(
+            "Refusing to trash a workspace root: {path}"
+        )));
+    }
+    let is_memory_root = name == MEMORY_DIR
+        || (name == MANIFEST_FILE && parent.file_name().is_some_and(|p| p == MEMORY_DIR));
+    if is_memory_root {
+        return Err(NeoError::Failed(format!(
+            "Refusing to trash workspace memory: {path}"
+        )));
+    }
+    if app.fs_scope().is_forbidden(&target) {
+        return Err(NeoError::PermissionDenied(format!(
+            "Permission denied: {path}"
+        )));
+    }
+    let patterns = denylist::compile(&workspaces::denylist_for(app, &root))?;
+    if denylist::is_denied(&patterns, target.strip_prefix(&root).unwrap_or(&target)) {
+        return Err(NeoError::PermissionDenied(format!(
+            "{}: {path}",
+            denylist::DENIED_ERROR
+        )));
+    }
+
+    trash::delete(&target)
+        .map_err(|e| NeoError::Io(format!("Failed to move {path} to the trash: {e}")))
+}
+
+/// Move a file or folder inside a granted workspace to the system trash instead of
+/// deleting it outright.
+///
+/// Nothing is returned about where the item ended up, since the trash crate doesn't
+/// report it: the Finder, the Recycle Bin and the freedesktop trash each pick the
+/// item's name in the trash themselves. Restoring goes through the system's own UI.
+#[tauri::command]
+pub async fn move_to_trash(app: tauri::AppHandle, path: String) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || trash_item(&app, &path))
+        .await
+        .map_err(|e| NeoError::Io(format!("Failed to move to the trash: {e}")))?
+}
+
+/// Move several items to the trash, reporting each one's result rather than stopping
+/// at the first failure.
+#[tauri::command]
+pub async fn move_to_trash_batch(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<Vec<TrashOutcome>, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(|path| TrashOutcome {
+                error: trash_item(&app, &path).err().map(String::from),
+                path,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to move to the trash: {e}")))
+}
+
+/// Show a file or folder inside a granted workspace in Finder, Explorer or the
+/// system's file manager, selected where the file manager supports it.
+#[tauri::command]
+pub async fn reveal_in_file_manager(app: tauri::AppHandle, path: String) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let target = Path::new(&path)
+            .canonicalize()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => NeoError::NotFound(format!("File not found: {path}")),
+                _ => NeoError::InvalidInput(format!("Invalid path: {e}")),
+            })?;
+        let grants = app.state::<WorkspaceGrants>();
+        let granted = grants.covering(&target).is_some()
+            || target
+                .parent()
+                .is_some_and(|parent| grants.covering(parent).is_some());
+        if !granted {
+            return Err(NeoError::PermissionDenied(format!(
+                "Path is not inside a granted workspace: {path}"
+            )));
+        }
+        if app.fs_scope().is_forbidden(&target) {
+            return Err(NeoError::PermissionDenied(format!(
+                "Permission denied: {path}"
+            )));
+        }
+        app.opener()
+            .reveal_item_in_dir(&target)
+            .map_err(|e| NeoError::Failed(format!("Failed to reveal {path}: {e}")))
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to reveal path: {e}")))?
+}
@@ -0,0 +1,138 @@
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+
+use crate::error::NeoError;
+use crate::providers::NETWORK_ERROR;
+
+/// Time allowed to establish a connection to a provider.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shared HTTP client, so connection pools and TLS state are reused across requests.
+///
+/// Per-request deadlines are set by callers with `RequestBuilder::timeout`.
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client")
+    })
+}
+
+/// How provider requests are retried after a 429, a 5xx or a failure to connect.
+/// Every field is optional when passed from the frontend.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 1 turns retries off.
+    pub max_attempts: u32,
+    /// Longest backoff before the second attempt, doubled for each attempt after it.
+    /// The actual wait is picked at random below it, so clients don't retry in step.
+    pub base_delay_ms: u64,
+    /// Cap on any single wait, including one asked for with `Retry-After`.
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+/// A response, and how many attempts it took to get it.
+pub struct Retried {
+    pub response: Response,
+    pub attempts: u32,
+}
+
+/// Whether a status is worth retrying: rate limits and server errors.
+pub fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// "1 attempt", "3 attempts".
+pub fn attempts(count: u32) -> String {
+    match count {
+        1 => "1 attempt".to_string(),
+        n => format!("{n} attempts"),
+    }
+}
+
+/// The wait a response asks for in `Retry-After`, as seconds or an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Exponential backoff with full jitter before attempt `attempt + 1`.
+fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let ceiling = policy
+        .base_delay_ms
+        .saturating_mul(1 << (attempt - 1).min(20))
+        .min(policy.max_delay_ms);
+    Duration::from_millis(fastrand::u64(0..=ceiling))
+}
+
+/// Send a request, retrying it as `policy` allows while it fails to connect or gets
+/// a retryable status back. `service` names the other end in error messages.
+///
+/// Returns the first non-retryable response, or the last response once attempts run
+/// out, whatever its status. Fails with a `NETWORK_ERROR` that says how many attempts
+/// were made if none of them got a response. Requests whose body can't be replayed
+/// are sent once.
+pub async fn send_with_retry(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+    service: &str,
+) -> Result<Retried, NeoError> {
+    let max_attempts = policy.max_attempts.max(1);
+    let failed = |e: reqwest::Error, count: u32| {
+        NeoError::Network(format!(
+            "{NETWORK_ERROR}: failed to reach {service} after {}: {e}",
+            attempts(count)
+        ))
+    };
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let retry = if attempt < max_attempts {
+            request.try_clone()
+        } else {
+            None
+        };
+        let Some(retry) = retry else {
+            return match request.send().await {
+                Ok(response) => Ok(Retried {
+                    response,
+                    attempts: attempt,
+                }),
+                Err(e) => Err(failed(e, attempt)),
+            };
+        };
+        let delay = match retry.send().await {
+            Ok(response) if !is_retryable(response.status()) => {
+                return Ok(Retried {
+                    response,
+                    attempts: attempt,
+                })
+            }
+            Ok(response) => retry_after(&response).unwrap_or_else(|| backoff(policy, attempt)),
+            Err(e) if e.is_builder() => return Err(failed(e, attempt)),
+            Err(_) => backoff(policy, attempt),
+        };
+        tokio::time::sleep(delay.min(Duration::from_millis(policy.max_delay_ms))).await;
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Icons kept in memory before the least recently used one is evicted.
+const MEMORY_CAPACITY: usize = 256;
+
+struct CachedIcon {
+    /// The file the icon was rendered from.
+    source: PathBuf,
+    /// Modification time of `source` when the entry was rendered.
+    modified: SystemTime,
+    data_url: String,
+    /// Value of `Entries::tick` when the entry was last read or written.
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    icons: HashMap<String, CachedIcon>,
+    tick: u64,
+}
+
+/// Rendered icons keyed by app name and size, so repeated lookups skip the
+/// conversion work.
+#[derive(Default)]
+pub struct IconCache(Mutex<Entries>);
+
+impl IconCache {
+    /// Cached data URL for an icon whose icon source hasn't changed since it was
+    /// rendered. Only the remembered source file is checked, so a hit runs no lookup
+    /// subprocesses.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.0.lock().ok()?;
+        entries.tick += 1;
+        let tick = entries.tick;
+        let entry = entries.icons.get_mut(key)?;
+        let modified = std::fs::metadata(&entry.source)
+            .and_then(|m| m.modified())
+            .ok()?;
+        if modified != entry.modified {
+            return None;
+        }
+        entry.last_used = tick;
+        Some(entry.data_url.clone())
+    }
+
+    pub fn insert(&self, key: &str, source: PathBuf, modified: SystemTime, data_url: String) {
+        let Ok(mut entries) = self.0.lock() else {
+            return;
+        };
+        entries.tick += 1;
+        let last_used = entries.tick;
+        entries.icons.insert(
+            key.to_string(),
+            CachedIcon {
+                source,
+                modified,
+                data_url,
+                last_used,
+            },
+        );
+
+        if entries.icons.len() > MEMORY_CAPACITY {
+            let oldest = entries
+                .icons
+                .iter()
+                .min_by_key(|(_, icon)| icon.last_used)
+                .map(|(name, _)| name.clone());
+            if let Some(name) = oldest {
+                entries.icons.remove(&name);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.icons.clear();
+        }
+    }
+}
+
+/// File in the on-disk cache for an icon rendered at `size` from `source` at
+/// `modified`, so an updated app gets a new entry rather than the stale one.
+pub fn disk_path(
+    dir: &Path,
+    source: &Path,
+    modified: SystemTime,
+    size: u32,
+    extension: &str,
+) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    size.hash(&mut hasher);
+    dir.join(format!("{:016x}.{extension}", hasher.finish()))
+}
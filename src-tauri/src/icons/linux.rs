@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::{encode_png, AppInfo};
+use crate::error::NeoError;
+
+/// Fixed-size icon theme directories, by edge length.
+const ICON_SIZES: &[u32] = &[16, 22, 24, 32, 48, 64, 96, 128, 256, 512];
+
+/// Theme size directories in order of preference for a requested size: the closest
+/// larger size first so downscaling stays sharp, then smaller sizes, largest first.
+fn size_dirs(size: u32) -> Vec<String> {
+    let mut sizes = ICON_SIZES.to_vec();
+    sizes.sort_by_key(|&s| if s >= size { (0, s) } else { (1, u32::MAX - s) });
+    sizes.into_iter().map(|s| format!("{s}x{s}")).collect()
+}
+
+/// The fields Neo needs from a `.desktop` file's `[Desktop Entry]` group.
+#[derive(Default)]
+struct DesktopEntry {
+    name: Option<String>,
+    icon: Option<String>,
+    exec: Option<String>,
+    /// `NoDisplay=true` or `Hidden=true`: the entry shouldn't be shown in menus.
+    hidden: bool,
+}
+
+/// XDG base data directories, user directory first.
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => dirs.push(PathBuf::from(dir)),
+        None => dirs.extend(dirs::home_dir().map(|h| h.join(".local/share"))),
+    }
+    let system = env::var_os("XDG_DATA_DIRS")
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(env::split_paths(&system));
+    dirs
+}
+
+fn parse_desktop_entry(content: &str) -> DesktopEntry {
+    let mut entry = DesktopEntry::default();
+    let mut in_entry = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        // Localized keys such as `Name[de]` don't match these exact keys.
+        if let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "Name" => entry.name = Some(value.trim().to_string()),
+                "Icon" => entry.icon = Some(value.trim().to_string()),
+                "Exec" => entry.exec = Some(value.trim().to_string()),
+                "NoDisplay" | "Hidden" if value.trim() == "true" => entry.hidden = true,
+                _ => {}
+            }
+        }
+    }
+
+    entry
+}
+
+/// Every `.desktop` file in the XDG application directories, user entries first.
+fn desktop_files() -> impl Iterator<Item = PathBuf> {
+    data_dirs()
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(dir.join("applications")).ok())
+        .flat_map(|files| files.flatten().map(|f| f.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("desktop"))
+}
+
+/// Find the `.desktop` entry whose `Name=` or file name matches `app_name`.
+fn find_desktop_entry(app_name: &str) -> Result<DesktopEntry, NeoError> {
+    for path in desktop_files() {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let entry = parse_desktop_entry(&content);
+        let stem_matches = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|s| s.eq_ignore_ascii_case(app_name));
+        let name_matches = entry
+            .name
+            .as_deref()
+            .is_some_and(|n| n.eq_ignore_ascii_case(app_name));
+        if stem_matches || name_matches {
+            return Ok(entry);
+        }
+    }
+    Err(NeoError::NotFound(format!("App not found: {app_name}")))
+}
+
+/// List the desktop applications shown in menus. The desktop file ID (its file name
+/// without `.desktop`) is reported as the bundle ID; an entry in the user's data dir
+/// shadows a system entry with the same ID.
+pub fn list_apps() -> Vec<AppInfo> {
+    let mut seen = HashSet::new();
+    desktop_files()
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().into_owned();
+            if !seen.insert(id.clone()) {
+                return None;
+            }
+            let entry = parse_desktop_entry(&fs::read_to_string(&path).ok()?);
+            if entry.hidden {
+                return None;
+            }
+            Some(AppInfo {
+                name: entry.name?,
+                path: path.to_string_lossy().into_owned(),
+                bundle_id: Some(id),
+            })
+        })
+        .collect()
+}
+
+/// The program an `Exec=` value runs, with field codes such as `%U` and any
+/// arguments dropped.
+fn exec_program(exec: &str) -> Option<String> {
+    let exec = exec.trim_start();
+    let program = match exec.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?.replace("\\\\", "\\"),
+        None => exec.split_whitespace().next()?.to_string(),
+    };
+    (!program.is_empty() && !program.starts_with('%')).then_some(program)
+}
+
+/// Find the executable for a Linux desktop application from its `.desktop` entry.
+///
+/// Relative `Exec=` programs are resolved against `$PATH`.
+pub fn find_app_path(app_name: &str) -> Result<String, NeoError> {
+    let entry = find_desktop_entry(app_name)?;
+    let program = entry
+        .exec
+        .as_deref()
+        .and_then(exec_program)
+        .ok_or_else(|| NeoError::Failed(format!("No Exec declared for app: {app_name}")))?;
+
+    let program = Path::new(&program);
+    if program.is_absolute() {
+        return Ok(program.to_string_lossy().into_owned());
+    }
+    env::var_os("PATH")
+        .into_iter()
+        .flat_map(|p| env::split_paths(&p).collect::<Vec<_>>())
+        .map(|dir| dir.join(program))
+        .find(|p| p.is_file())
+        .map(|p| p.to_string_lossy().into_owned())
+        .ok_or_else(|| NeoError::NotFound(format!("App not found: {app_name}")))
+}
+
+/// Icon theme configured for GTK, if any.
+fn current_icon_theme() -> Option<String> {
+    let settings = dirs::config_dir()?.join("gtk-3.0/settings.ini");
+    fs::read_to_string(settings)
+        .ok()?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("gtk-icon-theme-name"))
+        .and_then(|rest| rest.trim_start().strip_prefix('='))
+        .map(|name| name.trim().trim_matches('"').to_string())
+}
+
+/// Whether a path points at an SVG icon, which is handed to the frontend as-is.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+/// Resolve an `Icon=` value to a PNG or SVG file through the XDG icon theme
+/// hierarchy: the current theme, then `hicolor`, then `/usr/share/pixmaps`.
+///
+/// Within a theme, fixed-size PNGs closest to `size` are preferred over the
+/// `scalable` SVG.
+fn resolve_icon(icon: &str, size: u32) -> Option<PathBuf> {
+    let path = Path::new(icon);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let mut bases: Vec<PathBuf> = dirs::home_dir().map(|h| h.join(".icons")).into_iter().collect();
+    bases.extend(data_dirs().into_iter().map(|d| d.join("icons")));
+
+    let themes = current_icon_theme()
+        .into_iter()
+        .chain(std::iter::once("hicolor".to_string()));
+    let png = format!("{icon}.png");
+    let svg = format!("{icon}.svg");
+    let size_dirs = size_dirs(size);
+
+    for theme in themes {
+        for base in &bases {
+            let theme_dir = base.join(&theme);
+            let candidates = size_dirs
+                .iter()
+                .map(|size| theme_dir.join(size).join("apps").join(&png))
+                .chain(std::iter::once(theme_dir.join("scalable/apps").join(&svg)));
+            for candidate in candidates {
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    let pixmaps = Path::new("/usr/share/pixmaps");
+    [pixmaps.join(&png), pixmaps.join(&svg)]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+/// Locate the icon file for a Linux desktop application.
+pub fn icon_source(app_name: &str, size: u32) -> Result<PathBuf, NeoError> {
+    let entry = find_desktop_entry(app_name)?;
+    let icon = entry
+        .icon
+        .ok_or_else(|| NeoError::Failed(format!("No icon declared for app: {app_name}")))?;
+    resolve_icon(&icon, size)
+        .ok_or_else(|| NeoError::NotFound(format!("Icon file not found: {icon}")))
+}
+
+/// Load an icon file and convert it to `size` x `size` PNG bytes. SVG icons are
+/// returned unchanged since they scale in the webview.
+pub fn render_icon(path: &Path, size: u32) -> Result<Vec<u8>, NeoError> {
+    if is_svg(path) {
+        return fs::read(path).map_err(|e| NeoError::Io(format!("Failed to read icon: {e}")));
+    }
+    let image = image::open(path).map_err(|e| NeoError::Io(format!("Failed to read icon: {e}")))?;
+    encode_png(image, size)
+}
+
+/// Start an application from a `.desktop` file, an executable path, or an app name,
+/// without waiting for it to exit.
+pub fn launch(app: &str, args: &[String]) -> Result<(), NeoError> {
+    let path = Path::new(app);
+    let program = if path.extension().is_some_and(|e| e == "desktop") && path.is_file() {
+        let content = fs::read_to_string(path)
+            .map_err(|e| NeoError::Io(format!("Failed to read {app}: {e}")))?;
+        let exec = parse_desktop_entry(&content)
+            .exec
+            .ok_or_else(|| NeoError::Failed(format!("No Exec declared for app: {app}")))?;
+        exec_program(&exec)
+            .ok_or_else(|| NeoError::Failed(format!("No Exec declared for app: {app}")))?
+    } else if path.is_absolute() && path.is_file() {
+        app.to_string()
+    } else {
+        find_app_path(app)?
+    };
+
+    Command::new(&program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| NeoError::Failed(format!("Failed to launch {app}: {e}")))
+}
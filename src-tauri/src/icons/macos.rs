@@ -0,0 +1,334 @@
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use base64::Engine;
+use icns::{IconFamily, PixelFormat};
+use image::{DynamicImage, RgbaImage};
+
+use super::{encode_png, AppInfo};
+use crate::error::NeoError;
+use crate::shell::run_with_timeout;
+
+/// Folders scanned for `.app` bundles, besides `~/Applications`.
+const APPLICATION_DIRS: &[&str] = &["/Applications", "/System/Applications"];
+
+/// How long each external tool may run. Spotlight can stall while it is indexing.
+const MDFIND_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULTS_TIMEOUT: Duration = Duration::from_secs(3);
+const SIPS_TIMEOUT: Duration = Duration::from_secs(3);
+const ICONUTIL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A transparent 32 x 32 PNG, rendered when an icon can't be converted because
+/// neither sips nor iconutil can be run, so the UI always has something to show.
+const PLACEHOLDER_ICON: &str = "iVBORw0KGgoAAAANSUhEUgAAACAAAAAgCAYAAABzenr0AAAAGklEQVR42u3BAQEAAACCIP+vbkhAAQAAAO8GECAAAcm1w7EAAAAASUVORK5CYII=";
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Quote a value for a Spotlight query. Backslashes and quotes are escaped, and so
+/// is `*`, which would otherwise act as a wildcard.
+fn mdfind_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | '"' | '*') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Find the .app bundle path for a given application name.
+fn find_app_path(app_name: &str) -> Result<String, NeoError> {
+    // Try mdfind with display name
+    let query = format!(
+        "kMDItemDisplayName == {} && kMDItemKind == 'Application'",
+        mdfind_quote(app_name)
+    );
+    if let Ok(output) = run_with_timeout(Command::new("mdfind").arg(&query), MDFIND_TIMEOUT) {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if let Some(path) = stdout.lines().find(|l| l.ends_with(".app")) {
+            return Ok(path.to_string());
+        }
+    }
+
+    // Try mdfind with filesystem name
+    let query = format!(
+        "kMDItemFSName == {} && kMDItemKind == 'Application'",
+        mdfind_quote(&format!("{app_name}.app"))
+    );
+    if let Ok(output) = run_with_timeout(Command::new("mdfind").arg(&query), MDFIND_TIMEOUT) {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if let Some(path) = stdout.lines().find(|l| l.ends_with(".app")) {
+            return Ok(path.to_string());
+        }
+    }
+
+    // Fallback: check well-known paths
+    let candidates = [
+        format!("/Applications/{}.app", app_name),
+        format!("/System/Applications/{}.app", app_name),
+        format!("/System/Applications/Utilities/{}.app", app_name),
+        format!("/System/Library/CoreServices/{}.app", app_name),
+    ];
+    candidates
+        .into_iter()
+        .find(|p| PathBuf::from(p).exists())
+        .ok_or_else(|| NeoError::NotFound(format!("App not found: {app_name}")))
+}
+
+/// Locate the `.icns` file for a macOS application.
+/// Uses mdfind with kMDItemDisplayName to locate the app bundle, then reads the
+/// icon file name from its Info.plist.
+pub fn icon_source(app_name: &str, _size: u32) -> Result<PathBuf, NeoError> {
+    let app_path = find_app_path(app_name)?;
+
+    // Read Info.plist to find the icon file name
+    let plist_path = format!("{app_path}/Contents/Info.plist");
+    let plist_output = run_with_timeout(
+        Command::new("defaults").args(["read", &plist_path, "CFBundleIconFile"]),
+        DEFAULTS_TIMEOUT,
+    )
+    .map_err(|e| NeoError::Io(format!("Failed to read plist: {e}")))?;
+
+    let mut icon_name = String::from_utf8_lossy(&plist_output.stdout).trim().to_string();
+    if icon_name.is_empty() {
+        icon_name = "AppIcon".to_string();
+    }
+    if !icon_name.ends_with(".icns") {
+        icon_name.push_str(".icns");
+    }
+
+    let icns_path = PathBuf::from(format!("{app_path}/Contents/Resources/{icon_name}"));
+    if !icns_path.exists() {
+        return Err(NeoError::NotFound(format!(
+            "Icon file not found: {}",
+            icns_path.display()
+        )));
+    }
+    Ok(icns_path)
+}
+
+/// Decode the largest image in an `.icns` file that this crate understands and scale
+/// it to `size` x `size` PNG bytes, for when sips isn't available.
+fn decode_icns(icns_path: &Path, size: u32) -> Result<Vec<u8>, NeoError> {
+    let file = fs::File::open(icns_path)
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", icns_path.display())))?;
+    let family = IconFamily::read(BufReader::new(file))
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", icns_path.display())))?;
+
+    let mut icon_types = family.available_icons();
+    icon_types.sort_by_key(|t| std::cmp::Reverse(t.pixel_width()));
+    for icon_type in icon_types {
+        // Some sizes are stored as JPEG 2000, which can't be decoded here.
+        let Ok(icon) = family.get_icon_with_type(icon_type) else {
+            continue;
+        };
+        let rgba = icon.convert_to(PixelFormat::RGBA);
+        let (width, height) = (rgba.width(), rgba.height());
+        if let Some(buffer) = RgbaImage::from_raw(width, height, rgba.into_data().into_vec()) {
+            return encode_png(DynamicImage::ImageRgba8(buffer), size);
+        }
+    }
+    Err(NeoError::Failed(format!(
+        "No readable image in {}",
+        icns_path.display()
+    )))
+}
+
+/// Pixel width of an iconset image from its name, such as `icon_512x512@2x.png`.
+fn iconset_width(name: &str) -> Option<u32> {
+    let name = name.strip_prefix("icon_")?.strip_suffix(".png")?;
+    let (dimensions, scale) = match name.strip_suffix("@2x") {
+        Some(dimensions) => (dimensions, 2),
+        None => (name, 1),
+    };
+    let (width, _) = dimensions.split_once('x')?;
+    Some(width.parse::<u32>().ok()? * scale)
+}
+
+/// Unpack an `.icns` file with iconutil and scale its largest PNG to `size` x `size`.
+/// Returns `Ok(None)` when iconutil can't be run.
+fn iconutil_png(icns_path: &Path, iconset: &Path, size: u32) -> Result<Option<Vec<u8>>, NeoError> {
+    let output = match run_with_timeout(
+        Command::new("iconutil")
+            .args(["-c", "iconset"])
+            .arg(icns_path)
+            .arg("-o")
+            .arg(iconset),
+        ICONUTIL_TIMEOUT,
+    ) {
+        Ok(output) => output,
+        Err(_) => return Ok(None),
+    };
+    if !output.status.success() {
+        return Err(NeoError::Failed(format!(
+            "iconutil failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let largest = fs::read_dir(iconset)
+        .map_err(|e| NeoError::Io(format!("Failed to read iconset: {e}")))?
+        .flatten()
+        .filter_map(|entry| {
+            let width = iconset_width(&entry.file_name().to_string_lossy())?;
+            Some((width, entry.path()))
+        })
+        .max_by_key(|(width, _)| *width)
+        .ok_or_else(|| NeoError::Failed(format!("No PNG images in {}", icns_path.display())))?;
+    let png = fs::read(&largest.1).map_err(|e| NeoError::Io(format!("Failed to read PNG: {e}")))?;
+    let image = image::load_from_memory(&png)
+        .map_err(|e| NeoError::Failed(format!("Failed to decode PNG: {e}")))?;
+    encode_png(image, size).map(Some)
+}
+
+/// Convert an `.icns` file to PNG bytes when sips can't be run: unpack it with
+/// iconutil, then try decoding it in process, and settle for a transparent
+/// placeholder if neither works.
+fn render_without_sips(icns_path: &Path, tmp: &Path, size: u32) -> Result<Vec<u8>, NeoError> {
+    let iconset = tmp.with_extension("iconset");
+    let unpacked = iconutil_png(icns_path, &iconset, size);
+    let _ = fs::remove_dir_all(&iconset);
+    if let Some(png) = unpacked? {
+        return Ok(png);
+    }
+    decode_icns(icns_path, size).or_else(|_| {
+        base64::engine::general_purpose::STANDARD
+            .decode(PLACEHOLDER_ICON)
+            .map_err(|e| NeoError::Failed(format!("Failed to decode placeholder icon: {e}")))
+    })
+}
+
+/// Whether PNG bytes are the placeholder rendered when no converter could run,
+/// which shouldn't be cached in place of the real icon.
+pub fn is_placeholder(png: &[u8]) -> bool {
+    base64::engine::general_purpose::STANDARD
+        .decode(PLACEHOLDER_ICON)
+        .is_ok_and(|placeholder| placeholder == png)
+}
+
+/// Convert an `.icns` file to `size` x `size` PNG bytes via sips, falling back to
+/// iconutil, then in-process decoding, when sips can't be run.
+pub fn render_icon(icns_path: &Path, size: u32) -> Result<Vec<u8>, NeoError> {
+    // Many bundles name their icon `AppIcon.icns`, so temp files need a unique name.
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let tmp_png =
+        std::env::temp_dir().join(format!("neo_icon_{}_{id}_{size}.png", std::process::id()));
+    let edge = size.to_string();
+
+    let sips_result = run_with_timeout(
+        Command::new("sips")
+            .args(["-s", "format", "png", "-z", edge.as_str(), edge.as_str()])
+            .arg(icns_path)
+            .arg("--out")
+            .arg(&tmp_png),
+        SIPS_TIMEOUT,
+    );
+    let sips_result = match sips_result {
+        Ok(output) => output,
+        // sips is missing or hung; a nonzero exit below is still reported as an error.
+        Err(_) => {
+            let _ = std::fs::remove_file(&tmp_png);
+            return render_without_sips(icns_path, &tmp_png, size);
+        }
+    };
+
+    if !sips_result.status.success() {
+        return Err(NeoError::Failed(format!(
+            "sips failed: {}",
+            String::from_utf8_lossy(&sips_result.stderr)
+        )));
+    }
+
+    let png_data =
+        std::fs::read(&tmp_png).map_err(|e| NeoError::Io(format!("Failed to read PNG: {e}")))?;
+    let _ = std::fs::remove_file(&tmp_png);
+
+    Ok(png_data)
+}
+
+/// Describe a `.app` bundle from its Info.plist, falling back to the bundle's file
+/// name when `CFBundleName` is missing.
+fn app_info(bundle: &Path) -> Option<AppInfo> {
+    let plist = plist::Value::from_file(bundle.join("Contents/Info.plist")).ok()?;
+    let info = plist.as_dictionary()?;
+    let string = |key: &str| info.get(key).and_then(|v| v.as_string()).map(str::to_string);
+
+    let name = string("CFBundleName")
+        .filter(|n| !n.is_empty())
+        .or_else(|| Some(bundle.file_stem()?.to_string_lossy().into_owned()))?;
+    Some(AppInfo {
+        name,
+        path: bundle.to_string_lossy().into_owned(),
+        bundle_id: string("CFBundleIdentifier"),
+    })
+}
+
+/// List the `.app` bundles in `/Applications`, `/System/Applications` and
+/// `~/Applications`, including one level of subfolders such as `Utilities`.
+pub fn list_apps() -> Vec<AppInfo> {
+    let mut dirs: Vec<PathBuf> = APPLICATION_DIRS.iter().map(PathBuf::from).collect();
+    dirs.extend(dirs::home_dir().map(|h| h.join("Applications")));
+
+    let mut bundles = Vec::new();
+    let mut pending: Vec<(PathBuf, usize)> = dirs.into_iter().map(|d| (d, 0)).collect();
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().is_some_and(|e| e == "app") {
+                bundles.push(path);
+            } else if depth == 0 && path.is_dir() {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+
+    bundles.iter().filter_map(|b| app_info(b)).collect()
+}
+
+/// Open an application from a `.app` bundle path or an app name, passing `args`
+/// through to it.
+pub fn launch(app: &str, args: &[String]) -> Result<(), NeoError> {
+    let bundle = if app.ends_with(".app") && Path::new(app).is_dir() {
+        app.to_string()
+    } else {
+        find_app_path(app)?
+    };
+
+    let mut open = Command::new("open");
+    open.arg("-a").arg(&bundle);
+    if !args.is_empty() {
+        open.arg("--args").args(args);
+    }
+    let output = run_with_timeout(&mut open, OPEN_TIMEOUT)?;
+    if !output.status.success() {
+        return Err(NeoError::Failed(format!(
+            "Failed to launch {app}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_spotlight_values() {
+        assert_eq!(mdfind_quote("Safari"), "'Safari'");
+        assert_eq!(mdfind_quote("Bob's App"), r"'Bob\'s App'");
+        assert_eq!(mdfind_quote(r#"a\b"c*"#), r#"'a\\b\"c\*'"#);
+        assert_eq!(
+            mdfind_quote("x' || kMDItemKind == '*"),
+            r"'x\' || kMDItemKind == \'\*'"
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! Application icon lookup, returned to the frontend as base64 data URLs.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use rayon::prelude::*;
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::error::NeoError;
+
+mod cache;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(target_os = "linux")]
+use linux as platform;
+#[cfg(target_os = "macos")]
+use macos as platform;
+#[cfg(windows)]
+use windows as platform;
+
+pub use cache::IconCache;
+
+/// An installed application the user can launch.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    pub name: String,
+    /// The `.app` bundle on macOS, `.desktop` file on Linux, or Start Menu shortcut
+    /// on Windows.
+    pub path: String,
+    /// `CFBundleIdentifier` on macOS, the desktop file ID on Linux.
+    pub bundle_id: Option<String>,
+}
+
+/// Edge length, in pixels, of the icons handed to the frontend unless a size is requested.
+const DEFAULT_ICON_SIZE: u32 = 32;
+/// Sizes outside this range are clamped to it, so a typo can't produce a huge data
+/// URL or an unreadably small icon.
+const MIN_ICON_SIZE: u32 = 16;
+const MAX_ICON_SIZE: u32 = 512;
+/// How long `get_app_icon` waits for a lookup unless the caller sets a timeout.
+const DEFAULT_LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn icon_size(size: Option<u32>) -> u32 {
+    size.unwrap_or(DEFAULT_ICON_SIZE)
+        .clamp(MIN_ICON_SIZE, MAX_ICON_SIZE)
+}
+
+/// Scale an image to `size` x `size` and encode it as PNG.
+fn encode_png(image: DynamicImage, size: u32) -> Result<Vec<u8>, NeoError> {
+    let image = if image.width() == size && image.height() == size {
+        image
+    } else {
+        image.resize_exact(size, size, FilterType::Lanczos3)
+    };
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| NeoError::Failed(format!("Failed to encode PNG: {e}")))?;
+    Ok(png)
+}
+
+/// Directory under the app cache dir holding rendered icons between launches.
+const DISK_CACHE_DIR: &str = "icons";
+
+fn disk_cache_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| dir.join(DISK_CACHE_DIR))
+}
+
+/// MIME type of the data URL built from an icon source.
+#[cfg(any(target_os = "macos", target_os = "linux", windows))]
+fn mime_type(source: &std::path::Path) -> &'static str {
+    #[cfg(target_os = "linux")]
+    if linux::is_svg(source) {
+        return "image/svg+xml";
+    }
+    let _ = source;
+    "image/png"
+}
+
+/// Look up one app's icon, serving it from the memory cache, then the disk cache,
+/// while the source file is unchanged.
+#[cfg(any(target_os = "macos", target_os = "linux", windows))]
+fn lookup(
+    cache: &IconCache,
+    disk: Option<&Path>,
+    app_name: &str,
+    size: u32,
+) -> Result<String, NeoError> {
+    let key = format!("{app_name}@{size}");
+    if let Some(hit) = cache.get(&key) {
+        return Ok(hit);
+    }
+
+    let source = platform::icon_source(app_name, size)?;
+    let modified = std::fs::metadata(&source)
+        .and_then(|m| m.modified())
+        .map_err(|e| NeoError::Io(format!("Failed to read icon file: {e}")))?;
+    let mime = mime_type(&source);
+
+    let cached_file = disk.map(|dir| {
+        let extension = if mime == "image/svg+xml" { "svg" } else { "png" };
+        cache::disk_path(dir, &source, modified, size, extension)
+    });
+    let data = match cached_file.as_deref().and_then(|f| std::fs::read(f).ok()) {
+        Some(data) => data,
+        None => {
+            let data = platform::render_icon(&source, size)?;
+            // A placeholder isn't cached, so the real icon is tried again next time.
+            #[cfg(target_os = "macos")]
+            if macos::is_placeholder(&data) {
+                let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
+                return Ok(format!("data:{mime};base64,{b64}"));
+            }
+            // The disk cache is best-effort; a failed write just means a re-render.
+            if let Some(file) = &cached_file {
+                if let Some(dir) = file.parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                let _ = std::fs::write(file, &data);
+            }
+            data
+        }
+    };
+
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
+    let data_url = format!("data:{mime};base64,{b64}");
+    cache.insert(&key, source, modified, data_url.clone());
+    Ok(data_url)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn lookup(
+    _cache: &IconCache,
+    _disk: Option<&Path>,
+    app_name: &str,
+    _size: u32,
+) -> Result<String, NeoError> {
+    Err(NeoError::Failed(format!(
+        "App icons are not supported on this platform: {app_name}"
+    )))
+}
+
+/// Run `lookup` on its own thread and give up on it after `timeout`. A lookup that
+/// finishes late still fills the cache for the next request.
+fn lookup_with_timeout(
+    app: tauri::AppHandle,
+    app_name: String,
+    size: u32,
+    timeout: Duration,
+) -> Result<String, NeoError> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let cache = app.state::<IconCache>();
+        let _ = tx.send(lookup(&cache, disk_cache_dir(&app).as_deref(), &app_name, size));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(NeoError::Failed(format!(
+            "Icon lookup timed out after {} ms",
+            timeout.as_millis()
+        ))),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(NeoError::Failed("Icon lookup failed".to_string()))
+        }
+    }
+}
+
+/// Get the icon for an installed application as a base64 data URL, PNG everywhere
+/// except for Linux themes that only ship an SVG.
+///
+/// - macOS: locates the `.app` bundle via Spotlight and converts its `.icns` with sips,
+///   or iconutil where sips is missing. If neither can run and the icon can't be
+///   decoded in process, a transparent 32 x 32 placeholder is returned instead.
+/// - Linux: finds the app's `.desktop` entry and resolves its `Icon=` through the
+///   XDG icon theme directories to a PNG or SVG.
+/// - Windows: extracts the first icon resource from the app's executable.
+///
+/// `size` is the edge length in pixels, 32 by default and clamped between 16 and 512.
+/// Results are cached in memory and under the app cache dir, keyed by app and size,
+/// until the icon source file's modification time changes. Lookups shell out on
+/// macOS, so they run on a blocking thread rather than the command thread, and
+/// concurrent requests don't wait on each other. A lookup that takes longer than
+/// `timeout_ms` (10 seconds by default), such as Spotlight stalling while it indexes,
+/// fails instead of hanging the call.
+#[tauri::command]
+pub async fn get_app_icon(
+    app: tauri::AppHandle,
+    app_name: String,
+    size: Option<u32>,
+    timeout_ms: Option<u64>,
+) -> Result<String, NeoError> {
+    let size = icon_size(size);
+    let timeout = timeout_ms.map_or(DEFAULT_LOOKUP_TIMEOUT, Duration::from_millis);
+    tauri::async_runtime::spawn_blocking(move || lookup_with_timeout(app, app_name, size, timeout))
+        .await
+        .map_err(|e| NeoError::Failed(format!("Failed to look up icon: {e}")))?
+}
+
+/// Get icons for several apps in one IPC round trip, looked up in parallel.
+///
+/// Returns a map of app name to data URL. Apps whose icon can't be found or
+/// converted, or whose lookup takes longer than `timeout_ms`, are left out of the map
+/// instead of failing the whole batch.
+#[tauri::command]
+pub async fn get_app_icon_batch(
+    app: tauri::AppHandle,
+    app_names: Vec<String>,
+    size: Option<u32>,
+    timeout_ms: Option<u64>,
+) -> Result<HashMap<String, String>, NeoError> {
+    let size = icon_size(size);
+    let timeout = timeout_ms.map_or(DEFAULT_LOOKUP_TIMEOUT, Duration::from_millis);
+    tauri::async_runtime::spawn_blocking(move || {
+        app_names
+            .into_par_iter()
+            .filter_map(|name| {
+                lookup_with_timeout(app.clone(), name.clone(), size, timeout)
+                    .ok()
+                    .map(|url| (name, url))
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to look up icons: {e}")))
+}
+
+/// Outcome of one app's lookup in `get_app_icons`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppIconResult {
+    pub app_name: String,
+    pub data_url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Get icons for several apps in one IPC round trip, reporting each app's result.
+///
+/// Lookups run in parallel on the bounded rayon pool, off the main thread, and share
+/// the cache with `get_app_icon`. A lookup that takes longer than `timeout_ms` is
+/// reported as an error for that app. Results are returned in the order requested.
+#[tauri::command]
+pub async fn get_app_icons(
+    app: tauri::AppHandle,
+    app_names: Vec<String>,
+    size: Option<u32>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<AppIconResult>, NeoError> {
+    let size = icon_size(size);
+    let timeout = timeout_ms.map_or(DEFAULT_LOOKUP_TIMEOUT, Duration::from_millis);
+    tauri::async_runtime::spawn_blocking(move || {
+        app_names
+            .into_par_iter()
+            .map(|name| {
+                let result = lookup_with_timeout(app.clone(), name.clone(), size, timeout);
+                match result {
+                    Ok(url) => AppIconResult {
+                        app_name: name,
+                        data_url: Some(url),
+                        error: None,
+                    },
+                    Err(e) => AppIconResult {
+                        app_name: name,
+                        data_url: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to look up icons: {e}")))
+}
+
+/// Drop every cached icon, in memory and on disk, so the next lookups re-render.
+#[tauri::command]
+pub fn clear_icon_cache(
+    app: tauri::AppHandle,
+    cache: State<'_, IconCache>,
+) -> Result<(), NeoError> {
+    cache.clear();
+    match disk_cache_dir(&app) {
+        Some(dir) => match std::fs::remove_dir_all(dir) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(NeoError::Io(format!("Failed to clear icon cache: {e}"))),
+        },
+        None => Ok(()),
+    }
+}
+
+/// List installed applications, sorted by name. Pair with `get_app_icon` for a
+/// launcher grid.
+#[tauri::command]
+pub async fn list_installed_apps() -> Result<Vec<AppInfo>, NeoError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        #[cfg(any(target_os = "macos", target_os = "linux", windows))]
+        let mut apps = platform::list_apps();
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+        let mut apps: Vec<AppInfo> = Vec::new();
+
+        apps.sort_by_cached_key(|a| a.name.to_lowercase());
+        apps
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to list apps: {e}")))
+}
+
+/// Launch an application by name or path, optionally with arguments.
+///
+/// Names are resolved the same way as for `get_app_icon`; paths may point at a
+/// `.app` bundle on macOS, a `.desktop` file or executable on Linux, or an
+/// executable or shortcut on Windows.
+#[tauri::command]
+pub async fn launch_app(
+    app_name_or_path: String,
+    args: Option<Vec<String>>,
+) -> Result<(), NeoError> {
+    let args = args.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(any(target_os = "macos", target_os = "linux", windows))]
+        {
+            platform::launch(&app_name_or_path, &args)
+        }
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+        {
+            let _ = args;
+            Err(NeoError::Failed(format!(
+                "Launching apps is not supported on this platform: {app_name_or_path}"
+            )))
+        }
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to launch app: {e}")))?
+}
@@ -0,0 +1,365 @@
+use std::env;
+use std::ffi::c_void;
+use std::fs;
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+
+use image::{DynamicImage, RgbaImage};
+use windows_sys::Win32::Graphics::Gdi::{
+    DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO,
+    BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP,
+};
+use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+use windows_sys::Win32::System::Registry::{
+    RegGetValueW, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_SZ,
+};
+use windows_sys::Win32::UI::Shell::{
+    ExtractIconExW, SHGetFileInfoW, ShellExecuteW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    DestroyIcon, GetIconInfo, HICON, ICONINFO, SW_SHOWNORMAL,
+};
+
+use super::{encode_png, AppInfo};
+use crate::error::NeoError;
+
+fn wide(s: &std::ffi::OsStr) -> Vec<u16> {
+    s.encode_wide().chain(Some(0)).collect()
+}
+
+/// Read the default value of an `App Paths` registry key, which maps an executable
+/// name to its full path for apps installed outside `PATH`.
+fn app_paths_entry(exe_name: &str) -> Option<PathBuf> {
+    let subkey = format!(r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\{exe_name}");
+    let subkey = wide(subkey.as_ref());
+
+    [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE].into_iter().find_map(|root| {
+        let mut buf = [0u16; 1024];
+        let mut len = std::mem::size_of_val(&buf) as u32;
+        // SAFETY: `subkey` is NUL-terminated; `buf` and `len` describe a writable buffer.
+        // Expandable strings are expanded by RegGetValueW.
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                subkey.as_ptr(),
+                std::ptr::null(),
+                RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ,
+                null_mut(),
+                buf.as_mut_ptr().cast(),
+                &mut len,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let chars = (len as usize / 2).saturating_sub(1);
+        let value = String::from_utf16_lossy(&buf[..chars]);
+        let path = PathBuf::from(value.trim().trim_matches('"'));
+        path.is_file().then_some(path)
+    })
+}
+
+/// Every shortcut in the user's and the shared Start Menu.
+fn shortcuts() -> Vec<PathBuf> {
+    let roots = [
+        env::var_os("APPDATA").map(PathBuf::from),
+        env::var_os("ProgramData").map(PathBuf::from),
+    ];
+    let mut pending: Vec<PathBuf> = roots
+        .into_iter()
+        .flatten()
+        .map(|d| d.join(r"Microsoft\Windows\Start Menu\Programs"))
+        .collect();
+    let mut found = Vec::new();
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("lnk"))
+            {
+                found.push(path);
+            }
+        }
+    }
+    found
+}
+
+/// Find a Start Menu shortcut whose file name matches an app's display name.
+fn find_shortcut(app_name: &str) -> Option<PathBuf> {
+    shortcuts().into_iter().find(|path| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|s| s.eq_ignore_ascii_case(app_name))
+    })
+}
+
+/// Locate the file whose icon represents an application, from a path or a name.
+///
+/// Names are looked up as `<name>.exe` in the registry `App Paths`, on `PATH` and in
+/// `<Program Files>\<name>\`, then as a Start Menu shortcut named after the app.
+fn find_executable(app_name: &str) -> Result<PathBuf, NeoError> {
+    let direct = Path::new(app_name);
+    if direct.is_file() {
+        return Ok(direct.to_path_buf());
+    }
+
+    let file = if app_name.to_ascii_lowercase().ends_with(".exe") {
+        app_name.to_string()
+    } else {
+        format!("{app_name}.exe")
+    };
+    if let Some(path) = app_paths_entry(&file) {
+        return Ok(path);
+    }
+
+    let on_path = env::var_os("PATH")
+        .map(|p| env::split_paths(&p).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let program_dirs = ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(PathBuf::from)
+        .flat_map(|dir| [dir.join(app_name), dir.join("Programs").join(app_name)]);
+
+    on_path
+        .into_iter()
+        .chain(program_dirs)
+        .map(|dir| dir.join(&file))
+        .find(|p| p.is_file())
+        .or_else(|| find_shortcut(app_name))
+        .ok_or_else(|| NeoError::NotFound(format!("App not found: {app_name}")))
+}
+
+fn dib_header(width: i32, height: i32) -> BITMAPINFO {
+    BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative height requests a top-down bitmap.
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Read a bitmap as 32-bit BGRA rows.
+unsafe fn read_bitmap(bitmap: HBITMAP, width: i32, height: i32) -> Option<Vec<u8>> {
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    let mut header = dib_header(width, height);
+    let dc = GetDC(null_mut());
+    let lines = GetDIBits(
+        dc,
+        bitmap,
+        0,
+        height as u32,
+        pixels.as_mut_ptr().cast(),
+        &mut header,
+        DIB_RGB_COLORS,
+    );
+    ReleaseDC(null_mut(), dc);
+    (lines != 0).then_some(pixels)
+}
+
+/// Convert an icon's color and mask bitmaps into an RGBA image.
+unsafe fn icon_image(info: &ICONINFO) -> Result<DynamicImage, NeoError> {
+    if info.hbmColor.is_null() {
+        return Err(NeoError::Failed(
+            "Monochrome icons are not supported".to_string(),
+        ));
+    }
+
+    let mut bitmap = BITMAP::default();
+    let read = GetObjectW(
+        info.hbmColor,
+        std::mem::size_of::<BITMAP>() as i32,
+        (&mut bitmap as *mut BITMAP).cast::<c_void>(),
+    );
+    if read == 0 {
+        return Err(NeoError::Io("Failed to read icon bitmap".to_string()));
+    }
+    let (width, height) = (bitmap.bmWidth, bitmap.bmHeight);
+
+    let mut pixels = read_bitmap(info.hbmColor, width, height)
+        .ok_or_else(|| NeoError::Io("Failed to read icon pixels".to_string()))?;
+
+    // Older icons carry no alpha channel; their transparency lives in the mask,
+    // where white pixels are transparent.
+    let has_alpha = pixels.chunks_exact(4).any(|px| px[3] != 0);
+    let mask = if has_alpha {
+        None
+    } else {
+        read_bitmap(info.hbmMask, width, height)
+    };
+
+    for (i, px) in pixels.chunks_exact_mut(4).enumerate() {
+        px.swap(0, 2); // BGRA -> RGBA
+        if let Some(mask) = &mask {
+            px[3] = if mask[i * 4] == 0 { 255 } else { 0 };
+        }
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| NeoError::Failed("Icon bitmap has an unexpected size".to_string()))
+}
+
+/// Convert an icon handle to an image and destroy the handle.
+fn take_icon(hicon: HICON) -> Result<DynamicImage, NeoError> {
+    // SAFETY: `hicon` is a valid icon handle owned here; the bitmaps returned by
+    // GetIconInfo are owned by the caller and deleted below.
+    unsafe {
+        let mut info = ICONINFO::default();
+        let result = if GetIconInfo(hicon, &mut info) == 0 {
+            Err(NeoError::Io("Failed to read icon".to_string()))
+        } else {
+            let image = icon_image(&info);
+            if !info.hbmColor.is_null() {
+                DeleteObject(info.hbmColor);
+            }
+            DeleteObject(info.hbmMask);
+            image
+        };
+        DestroyIcon(hicon);
+        result
+    }
+}
+
+/// Extract the first icon resource embedded in an executable.
+fn extract_icon(exe: &Path) -> Result<DynamicImage, NeoError> {
+    let wide = wide(exe.as_os_str());
+    let mut hicon: HICON = null_mut();
+
+    // SAFETY: `wide` is NUL-terminated and outlives the call; one large icon is requested.
+    let count = unsafe { ExtractIconExW(wide.as_ptr(), 0, &mut hicon, null_mut(), 1) };
+    if count == 0 || hicon.is_null() {
+        return Err(NeoError::NotFound(format!(
+            "Icon resource not found: {}",
+            exe.display()
+        )));
+    }
+    take_icon(hicon)
+}
+
+/// Get the icon the shell shows for a file, which for a shortcut is its target's.
+fn shell_icon(path: &Path) -> Result<DynamicImage, NeoError> {
+    let wide = wide(path.as_os_str());
+    let mut info = SHFILEINFOW::default();
+
+    // SAFETY: `wide` is NUL-terminated; `info` is sized by the passed length.
+    let ok = unsafe {
+        SHGetFileInfoW(
+            wide.as_ptr(),
+            0,
+            &mut info,
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_ICON | SHGFI_LARGEICON,
+        )
+    };
+    if ok == 0 || info.hIcon.is_null() {
+        return Err(NeoError::NotFound(format!(
+            "Icon resource not found: {}",
+            path.display()
+        )));
+    }
+    take_icon(info.hIcon)
+}
+
+/// Locate the executable or Start Menu shortcut whose icon represents a Windows
+/// application.
+pub fn icon_source(app_name: &str, _size: u32) -> Result<PathBuf, NeoError> {
+    find_executable(app_name)
+}
+
+/// Extract an executable's or shortcut's icon, rescaled to `size` x `size` PNG bytes.
+pub fn render_icon(path: &Path, size: u32) -> Result<Vec<u8>, NeoError> {
+    let is_shortcut = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("lnk"));
+    let image = if is_shortcut {
+        shell_icon(path)?
+    } else {
+        extract_icon(path)?
+    };
+    encode_png(image, size)
+}
+
+/// List the apps in the Start Menu, named after their shortcuts.
+pub fn list_apps() -> Vec<AppInfo> {
+    shortcuts()
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some(AppInfo {
+                name,
+                path: path.to_string_lossy().into_owned(),
+                bundle_id: None,
+            })
+        })
+        .collect()
+}
+
+/// Quote a command-line argument the way `CommandLineToArgvW` parses it back.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are doubled, and the quote itself escaped.
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        quoted.push(c);
+    }
+    // So are backslashes before the closing quote.
+    quoted.extend(std::iter::repeat_n('\\', backslashes));
+    quoted.push('"');
+    quoted
+}
+
+/// Open an application from an executable or shortcut path or an app name through
+/// the shell, passing `args` as its command line.
+pub fn launch(app: &str, args: &[String]) -> Result<(), NeoError> {
+    let target = find_executable(app)?;
+    let file = wide(target.as_os_str());
+    let operation = wide("open".as_ref());
+    let parameters = args.iter().map(|a| quote_arg(a)).collect::<Vec<_>>().join(" ");
+    let parameters = wide(parameters.as_ref());
+
+    // SAFETY: all strings are NUL-terminated and outlive the call.
+    let result = unsafe {
+        ShellExecuteW(
+            null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            parameters.as_ptr(),
+            std::ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values of 32 or less are error codes.
+    if result as usize <= 32 {
+        return Err(NeoError::Failed(format!(
+            "Failed to launch {app}: ShellExecute error {}",
+            result as usize
+        )));
+    }
+    Ok(())
+}
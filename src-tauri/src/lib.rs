@@ -1,162 +1,889 @@
+use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use base64::Engine;
+use serde::Serialize;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_fs::FsExt;
 
-/// Get the Gemini API key from environment variables
+mod backups;
+mod browser;
+mod bundle;
+mod chat;
+mod clipboard;
+mod cloud;
+mod compaction;
+mod conversations;
+mod denylist;
+mod dotenv;
+mod encryption;
+mod error;
+mod files;
+mod http;
+mod icons;
+mod lock;
+mod logging;
+mod memories;
+mod memory;
+mod migrations;
+mod paths;
+mod portable;
+mod providers;
+mod proxy;
+mod quota;
+mod roots;
+mod screenshot;
+mod search;
+mod secrets;
+mod shell;
+mod sse;
+mod stats;
+mod system;
+mod tokens;
+mod watcher;
+mod workspaces;
+
+use chat::ChatStreams;
+use clipboard::ClipboardState;
+use dotenv::{DotenvWarning, WorkspaceEnv, DOTENV_WARNING_EVENT, ENV_FILE};
+use error::NeoError;
+use icons::IconCache;
+use lock::WorkspaceLocks;
+use memory::WorkspaceInfo;
+use watcher::WorkspaceWatchers;
+use workspaces::{
+    GrantStatus, GrantedWorkspace, PathResult, WorkspaceAllowed, WorkspaceEntry, WorkspaceGrants,
+    WORKSPACE_ALLOWED_EVENT,
+};
+
+/// Where an API key was found, in order of precedence.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum KeySource {
+    Keychain,
+    WorkspaceDotenv,
+    Env,
+    ConfigDotenv,
+    None,
+}
+
+/// Read an API key, preferring values loaded from the workspace `.env` over the
+/// process environment, and both over the app config dir's `.env`.
+fn read_api_key(workspace_env: &WorkspaceEnv, var_name: &str) -> Option<(String, KeySource)> {
+    if let Some(key) = workspace_env.get(var_name) {
+        return Some((key, KeySource::WorkspaceDotenv));
+    }
+    if let Ok(key) = env::var(var_name) {
+        return Some((key, KeySource::Env));
+    }
+    workspace_env
+        .get_config(var_name)
+        .map(|key| (key, KeySource::ConfigDotenv))
+}
+
+/// Resolve the API key for a named provider, and where it came from.
+///
+/// The OS keychain is checked first, then the workspace `.env`, the process
+/// environment and the app config dir's `.env` via the `providers` table.
+fn find_provider_key(
+    workspace_env: &WorkspaceEnv,
+    provider: &str,
+) -> Result<(String, KeySource), NeoError> {
+    let var_name = providers::require(provider)?;
+
+    let keychain_error = match secrets::get(provider) {
+        Ok(Some(key)) => return Ok((key, KeySource::Keychain)),
+        Ok(None) => None,
+        Err(e) => Some(e),
+    };
+
+    if let Some(found) = read_api_key(workspace_env, var_name) {
+        return Ok(found);
+    }
+
+    Err(match keychain_error {
+        Some(e) => {
+            let message = format!("{e} (and {var_name} is not set in environment)");
+            e.with_message(message)
+        }
+        None => NeoError::NotFound(format!(
+            "No API key found for provider '{provider}' in the keychain or environment (set {var_name})"
+        )),
+    })
+}
+
+/// Resolve the API key for a named provider; see `find_provider_key`.
+fn read_provider_key(workspace_env: &WorkspaceEnv, provider: &str) -> Result<String, NeoError> {
+    find_provider_key(workspace_env, provider).map(|(key, _)| key)
+}
+
+/// Report where a provider's API key would be read from: `keychain`,
+/// `workspace_dotenv`, `env`, `config_dotenv`, in that order of precedence, or `none`
 #[tauri::command]
-fn get_gemini_api_key() -> Result<String, String> {
-    env::var("GEMINI_API_KEY").map_err(|_| "GEMINI_API_KEY not set in environment".to_string())
+fn get_api_key_source(
+    workspace_env: State<'_, WorkspaceEnv>,
+    provider: String,
+) -> Result<KeySource, NeoError> {
+    providers::require(&provider)?;
+    Ok(find_provider_key(&workspace_env, &provider).map_or(KeySource::None, |(_, source)| source))
 }
 
-/// Get the OpenRouter API key from environment variables
+/// Get the API key for any known provider (e.g. "gemini", "openrouter", "anthropic", "openai")
 #[tauri::command]
-fn get_openrouter_api_key() -> Result<String, String> {
-    env::var("OPENROUTER_API_KEY").map_err(|_| "OPENROUTER_API_KEY not set in environment".to_string())
+fn get_api_key(
+    workspace_env: State<'_, WorkspaceEnv>,
+    provider: String,
+) -> Result<String, NeoError> {
+    read_provider_key(&workspace_env, &provider)
 }
 
-/// Allow Neo to access a user-selected workspace directory.
+/// Get the API key for a provider from the OS keychain only, ignoring the environment
+#[tauri::command]
+fn get_api_key_secure(provider: String) -> Result<String, NeoError> {
+    providers::require(&provider)?;
+    secrets::get(&provider)?.ok_or_else(|| {
+        NeoError::NotFound(format!(
+            "No API key saved in the keychain for provider '{provider}'"
+        ))
+    })
+}
+
+/// Save an API key for a known provider in the OS keychain
 ///
-/// Tauri's filesystem plugin is scope-based: even if read/write commands are allowed,
-/// paths are denied unless they are inside the application's FS scope.
+/// Surrounding whitespace is trimmed; keys that are empty, contain whitespace or lack
+/// the provider's prefix (`sk-or-` for OpenRouter, ...) are rejected with
+/// `invalidInput`.
+#[tauri::command]
+fn set_api_key(provider: String, key: String) -> Result<(), NeoError> {
+    providers::require(&provider)?;
+    let key = key.trim();
+    providers::check_key_shape(&provider, key)?;
+    secrets::set(&provider, key)
+}
+
+/// Whether an API key is available for a provider from any source, without
+/// returning it
+#[tauri::command]
+fn has_api_key(
+    workspace_env: State<'_, WorkspaceEnv>,
+    provider: String,
+) -> Result<bool, NeoError> {
+    providers::require(&provider)?;
+    Ok(read_provider_key(&workspace_env, &provider).is_ok())
+}
+
+/// Check whether a provider accepts an API key before it is saved
+#[tauri::command]
+async fn validate_api_key(provider: String, key: String) -> Result<bool, NeoError> {
+    providers::validate_key(&provider, key.trim()).await
+}
+
+/// Remove a provider's API key from the OS keychain
+#[tauri::command]
+fn delete_api_key(provider: String) -> Result<(), NeoError> {
+    providers::require(&provider)?;
+    secrets::clear(&provider)
+}
+
+/// Get the Gemini API key from the keychain, workspace `.env`, or environment variables
+#[tauri::command]
+fn get_gemini_api_key(workspace_env: State<'_, WorkspaceEnv>) -> Result<String, NeoError> {
+    read_provider_key(&workspace_env, "gemini")
+}
+
+/// Get the OpenRouter API key from the keychain, workspace `.env`, or environment variables
+#[tauri::command]
+fn get_openrouter_api_key(workspace_env: State<'_, WorkspaceEnv>) -> Result<String, NeoError> {
+    read_provider_key(&workspace_env, "openrouter")
+}
+
+/// Get the Anthropic API key from the keychain, workspace `.env`, or environment variables
+#[tauri::command]
+fn get_anthropic_api_key(workspace_env: State<'_, WorkspaceEnv>) -> Result<String, NeoError> {
+    read_provider_key(&workspace_env, "anthropic")
+}
+
+/// Prefix of Neo's own environment variables, listed by `get_env_vars` by default.
+const NEO_ENV_PREFIX: &str = "NEO_";
+/// Value shown instead of a secret in `get_env_vars`.
+const REDACTED: &str = "***";
+
+/// Whether an environment variable looks like it holds a credential.
+fn is_secret_var(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    name.ends_with("_API_KEY")
+        || ["SECRET", "TOKEN", "PASSWORD"]
+            .iter()
+            .any(|word| name.contains(word))
+}
+
+/// List environment variables for debugging model integrations.
 ///
-/// This command is called right after the user selects a folder, so the app can
-/// read/write `.neomemory/` inside that workspace.
+/// With `prefixFilter`, every variable whose name starts with it (case-insensitive) is
+/// returned; without it, only Neo's `NEO_*` variables and the provider key variables.
+/// Values loaded from the workspace `.env` take precedence, as they do for the key
+/// getters. API keys and other credentials are shown as `***`, so the result is safe
+/// to paste into a bug report.
 #[tauri::command]
-fn allow_workspace_dir(app: tauri::AppHandle, path: String) -> Result<(), String> {
-    let raw = PathBuf::from(path);
+fn get_env_vars(
+    workspace_env: State<'_, WorkspaceEnv>,
+    prefix_filter: Option<String>,
+) -> HashMap<String, String> {
+    let matches = |name: &str| match &prefix_filter {
+        Some(prefix) => name
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+        None => {
+            name.starts_with(NEO_ENV_PREFIX)
+                || providers::PROVIDERS.iter().any(|(_, var)| *var == name)
+        }
+    };
+
+    workspace_env
+        .config_snapshot()
+        .into_iter()
+        .chain(env::vars())
+        .chain(workspace_env.snapshot())
+        .filter(|(name, _)| matches(name))
+        .map(|(name, value)| {
+            let value = if is_secret_var(&name) {
+                REDACTED.to_string()
+            } else {
+                value
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Report a `.env` file with lines that couldn't be parsed, without failing.
+fn warn_dotenv(app: &tauri::AppHandle, dir: &Path, problems: Vec<String>) {
+    if problems.is_empty() {
+        return;
+    }
+    let path = dir.join(ENV_FILE).to_string_lossy().into_owned();
+    tracing::warn!(path, problems = problems.len(), "malformed .env file");
+    let _ = app.emit(DOTENV_WARNING_EVENT, DotenvWarning { path, problems });
+}
+
+/// Load the `.env` file in the app config dir, whose keys are used when no other
+/// source has one.
+fn load_config_env(app: &tauri::AppHandle) {
+    let Ok(dir) = app.path().app_config_dir() else {
+        return;
+    };
+    let workspace_env = app.state::<WorkspaceEnv>();
+    match dotenv::load(&dir) {
+        Ok(Some(parsed)) => {
+            workspace_env.replace_config(parsed.vars);
+            warn_dotenv(app, &dir, parsed.problems);
+        }
+        Ok(None) => workspace_env.replace_config(HashMap::new()),
+        Err(e) => warn_dotenv(app, &dir, vec![e.to_string()]),
+    }
+}
+
+/// Load the `.env` file at the root of a granted workspace so the key getters can see
+/// its values, and reload the one in the app config dir.
+///
+/// Apps launched from Finder don't inherit the shell environment, so this gives users
+/// a way to provide keys per workspace. Lines that can't be parsed are skipped and
+/// reported in a `dotenv-warning` event. Parsed values are always stored; an error is
+/// returned afterwards if any provider key is still unavailable from every source.
+/// The values are dropped when the workspace's grant is revoked.
+#[tauri::command]
+fn load_workspace_env(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    workspace_env: State<'_, WorkspaceEnv>,
+    path: String,
+) -> Result<(), NeoError> {
+    let root = grants.require(&path)?;
+    load_config_env(&app);
+    let parsed = dotenv::load(&root)?
+        .ok_or_else(|| NeoError::NotFound(format!("No {ENV_FILE} file in {}", root.display())))?;
+    workspace_env.replace(&root, parsed.vars);
+    warn_dotenv(&app, &root, parsed.problems);
+
+    let missing: Vec<&str> = providers::PROVIDERS
+        .iter()
+        .filter(|(provider, _)| read_provider_key(&workspace_env, provider).is_err())
+        .map(|(_, var)| *var)
+        .collect();
+    if !missing.is_empty() {
+        return Err(NeoError::NotFound(format!(
+            "Missing API keys: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Validate a user-selected directory and add it to the FS scope.
+///
+/// The scope is always granted on the canonical target. Folders that resolve outside
+/// the allowed roots (home, unless the user added others), including through a
+/// symlink inside one, are only accepted with `allow_external`, and never when they
+/// are a system location or contain the home directory itself. A picked path that
+/// reaches outside the roots through a symlink gets its own error so the user can
+/// tell why a folder "in home" was refused.
+///
+/// A folder picked through a symlink is granted under the symlinked path as well:
+/// the scope canonicalizes paths that exist, but matches ones that don't yet, like a
+/// file about to be created, as written. `grantedPaths` lists both.
+///
+/// A folder already covered by an existing grant (itself or a recursively granted
+/// ancestor) is left alone, and recursively granting a parent of existing grants
+/// absorbs them. A non-recursive grant only covers the folder's direct children; it
+/// is widened if the same folder is later granted recursively.
+fn grant_workspace(
+    app: &tauri::AppHandle,
+    grants: &WorkspaceGrants,
+    path: String,
+    allow_external: bool,
+    recursive: bool,
+) -> Result<GrantedWorkspace, NeoError> {
+    let raw = PathBuf::from(&path);
     let canonical = raw
         .canonicalize()
-        .map_err(|e| format!("Invalid path: {e}"))?;
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
 
     if !canonical.is_dir() {
-        return Err("Selected path is not a directory".to_string());
+        return Err(NeoError::Failed(
+            "Selected path is not a directory".to_string(),
+        ));
     }
 
-    // Basic safety: only allow paths inside the user's home directory when available.
-    if let Ok(home) = env::var("HOME") {
-        let home = PathBuf::from(home);
-        if !canonical.starts_with(&home) {
-            return Err("Selected folder must be inside your home directory".to_string());
+    // Basic safety: only allow paths inside the user's home directory or another
+    // allowed root. If home can't be determined the check fails closed rather than
+    // being skipped.
+    let home = paths::home_dir()
+        .ok_or_else(|| NeoError::Failed("Could not determine your home directory".to_string()))?;
+    let roots = roots::load(app)?;
+    if !allow_external {
+        if let Some(link) = paths::escaping_symlink(&raw, &roots) {
+            return Err(NeoError::PermissionDenied(format!(
+                "Path contains a symlink escaping the allowed roots: {}",
+                link.display()
+            )));
+        }
+    }
+    if roots::containing(&roots, &canonical).is_none() {
+        if !allow_external {
+            return Err(NeoError::PermissionDenied(
+                "Selected folder must be inside your home directory or another allowed root (set allowExternal to grant folders elsewhere)"
+                    .to_string(),
+            ));
+        }
+        if paths::is_within(&home, &canonical) {
+            return Err(NeoError::PermissionDenied(
+                "Selected folder contains your home directory".to_string(),
+            ));
+        }
+        if paths::is_system_path(&canonical) {
+            return Err(NeoError::PermissionDenied(
+                "Selected folder is a system location and cannot be granted".to_string(),
+            ));
         }
     }
 
     let scope = app.fs_scope();
-    // true => recursive
+    // Forbidden patterns always win over allowed ones, so a revoked folder can't be
+    // re-granted until the scope is rebuilt on the next launch.
+    if scope.is_forbidden(&canonical) {
+        return Err(NeoError::PermissionDenied(
+            "This folder was revoked earlier in this session; restart Neo to grant it again"
+                .to_string(),
+        ));
+    }
+    let cloud_provider = cloud::provider(&canonical);
+    // Several workspaces can be granted at once; granting one again, or one inside
+    // an existing grant, is a no-op.
+    if let Some(covering) = grants.covering(&canonical) {
+        // Only an exact grant can be non-recursive, since it never covers a child.
+        let covering_recursive = grants.is_recursive(&covering).unwrap_or(true);
+        if covering_recursive || !recursive {
+            return Ok(GrantedWorkspace {
+                path,
+                canonical_path: canonical,
+                status: GrantStatus::AlreadyCovered,
+                covered_by: Some(covering),
+                absorbed: Vec::new(),
+                recursive: covering_recursive,
+                granted_paths: Vec::new(),
+                cloud_provider,
+            });
+        }
+    }
+    // A symlink swapped since the checks above would change what the path points at.
+    if raw.canonicalize().ok().as_ref() != Some(&canonical) {
+        return Err(NeoError::Failed(
+            "Selected folder changed while it was being granted".to_string(),
+        ));
+    }
     scope
-        .allow_directory(canonical, true)
-        .map_err(|e| format!("Failed to allow directory: {e}"))?;
-    Ok(())
+        .allow_directory(&canonical, recursive)
+        .map_err(|e| NeoError::Failed(format!("Failed to allow directory: {e}")))?;
+    denylist::apply(app, &canonical, &workspaces::denylist_for(app, &canonical))?;
+    let absorbed = if recursive {
+        grants.absorb_descendants(&canonical)
+    } else {
+        Vec::new()
+    };
+    grants.record(&canonical, recursive);
+
+    let mut granted_paths = vec![canonical.clone()];
+    let link = paths::normalize(&raw);
+    let through_link = raw.is_absolute()
+        && link != canonical
+        && link.canonicalize().ok().as_ref() == Some(&canonical);
+    if through_link {
+        scope
+            .allow_directory(&link, recursive)
+            .map_err(|e| NeoError::Failed(format!("Failed to allow directory: {e}")))?;
+        grants.add_link(&canonical, &link);
+        granted_paths.push(link);
+    }
+
+    Ok(GrantedWorkspace {
+        path,
+        canonical_path: canonical,
+        status: if absorbed.is_empty() {
+            GrantStatus::Granted
+        } else {
+            GrantStatus::Merged
+        },
+        covered_by: None,
+        absorbed,
+        recursive,
+        granted_paths,
+        cloud_provider,
+    })
 }
 
-/// Find the .app bundle path for a given application name.
-fn find_app_path(app_name: &str) -> Result<String, String> {
-    use std::process::Command;
+/// Allow Neo to access a user-selected workspace directory.
+///
+/// Tauri's filesystem plugin is scope-based: even if read/write commands are allowed,
+/// paths are denied unless they are inside the application's FS scope.
+///
+/// This command is called right after the user selects a folder, so the app can
+/// read/write `.neomemory/` inside that workspace. The result says whether the folder
+/// was newly granted, already covered, or merged with grants inside it.
+///
+/// With `recursive: false` only the folder's top level is granted, for flows like
+/// attaching a single folder of documents. Grants are recursive by default.
+///
+/// Once the folder is added to the scope, a `workspace-allowed` event with its
+/// canonical `path` is emitted, so listeners can set up watchers and indexes without
+/// another round-trip through the frontend. Folders that were already covered don't
+/// emit it.
+///
+/// Resolving the path can stall on slow or network mounts, so the work runs on a
+/// blocking thread.
+#[tauri::command]
+async fn allow_workspace_dir(
+    app: tauri::AppHandle,
+    path: String,
+    allow_external: Option<bool>,
+    recursive: Option<bool>,
+) -> Result<GrantedWorkspace, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        allow_dir(
+            &app,
+            path,
+            allow_external.unwrap_or(false),
+            recursive.unwrap_or(true),
+        )
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to grant workspace: {e}")))?
+}
 
-    // Try mdfind with display name
-    let query = format!(
-        "kMDItemDisplayName == '{}' && kMDItemKind == 'Application'",
-        app_name
-    );
-    if let Ok(output) = Command::new("mdfind").arg(&query).output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if let Some(path) = stdout.lines().find(|l| l.ends_with(".app")) {
-            return Ok(path.to_string());
-        }
+/// Grant one folder for `allow_workspace_dir` or `allow_workspace_dirs`, remember it,
+/// and announce it.
+fn allow_dir(
+    app: &tauri::AppHandle,
+    path: String,
+    allow_external: bool,
+    recursive: bool,
+) -> Result<GrantedWorkspace, NeoError> {
+    let grants = app.state::<WorkspaceGrants>();
+    let granted = grant_workspace(app, &grants, path, allow_external, recursive)?;
+    workspaces::remember(app, &granted.canonical_path, granted.recursive)?;
+    if granted.status != GrantStatus::AlreadyCovered {
+        let _ = app.emit(
+            WORKSPACE_ALLOWED_EVENT,
+            WorkspaceAllowed {
+                path: granted.canonical_path.to_string_lossy().into_owned(),
+            },
+        );
     }
+    Ok(granted)
+}
 
-    // Try mdfind with filesystem name
-    let query = format!(
-        "kMDItemFSName == '{}.app' && kMDItemKind == 'Application'",
-        app_name
-    );
-    if let Ok(output) = Command::new("mdfind").arg(&query).output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if let Some(path) = stdout.lines().find(|l| l.ends_with(".app")) {
-            return Ok(path.to_string());
-        }
-    }
+/// Grant several folders in one call, as `allow_workspace_dir` would one by one,
+/// for restoring a session.
+///
+/// Each folder's result is reported separately, in the order given, so one bad path
+/// doesn't keep the others from being granted.
+#[tauri::command]
+async fn allow_workspace_dirs(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    allow_external: Option<bool>,
+    recursive: Option<bool>,
+) -> Result<Vec<PathResult>, NeoError> {
+    let allow_external = allow_external.unwrap_or(false);
+    let recursive = recursive.unwrap_or(true);
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(
+                |path| match allow_dir(&app, path.clone(), allow_external, recursive) {
+                    Ok(granted) => PathResult {
+                        path,
+                        granted: Some(granted),
+                        error: None,
+                    },
+                    Err(e) => PathResult {
+                        path,
+                        granted: None,
+                        error: Some(e.to_string()),
+                    },
+                },
+            )
+            .collect()
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to grant workspaces: {e}")))
+}
 
-    // Fallback: check well-known paths
-    let candidates = [
-        format!("/Applications/{}.app", app_name),
-        format!("/System/Applications/{}.app", app_name),
-        format!("/System/Applications/Utilities/{}.app", app_name),
-        format!("/System/Library/CoreServices/{}.app", app_name),
-    ];
-    candidates
-        .into_iter()
-        .find(|p| PathBuf::from(p).exists())
-        .ok_or_else(|| format!("App not found: {app_name}"))
+/// Grant access to a workspace and add it to the registry under a display name.
+#[tauri::command]
+async fn register_workspace(
+    app: tauri::AppHandle,
+    path: String,
+    name: String,
+    allow_external: Option<bool>,
+) -> Result<WorkspaceEntry, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let canonical =
+            grant_workspace(&app, &grants, path, allow_external.unwrap_or(false), true)?
+                .canonical_path;
+        let name = name.trim();
+        let name = (!name.is_empty()).then(|| name.to_string());
+        workspaces::upsert(&app, &canonical, name, true)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to register workspace: {e}")))?
 }
 
-/// Get the icon for a macOS application as a base64 PNG data URL.
-/// Uses mdfind with kMDItemDisplayName to locate the app bundle, then extracts
-/// and converts the icon via sips.
+/// Grant access to a workspace and scaffold its `.neomemory/` directory.
+///
+/// Safe to call on every open: an existing layout is completed rather than replaced,
+/// and its manifest is returned.
 #[tauri::command]
-fn get_app_icon(app_name: String) -> Result<String, String> {
-    use std::process::Command;
+async fn init_workspace(
+    app: tauri::AppHandle,
+    path: String,
+    allow_external: Option<bool>,
+) -> Result<WorkspaceInfo, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let canonical =
+            grant_workspace(&app, &grants, path, allow_external.unwrap_or(false), true)?
+                .canonical_path;
+        workspaces::remember(&app, &canonical, true)?;
+        let info = memory::init(&canonical)?;
+        workspaces::set_workspace_id(&app, &canonical, &info.manifest.workspace_id)?;
+        logging::set_active_workspace(&canonical);
+        Ok(info)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to initialize workspace: {e}")))?
+}
 
-    let app_path = find_app_path(&app_name)?;
+/// Ask the user for a folder with the native picker, then grant it and scaffold its
+/// `.neomemory/` like `init_workspace`. Returns the canonical path.
+///
+/// Fails with `cancelled` when the user closes the picker, which the UI can ignore.
+#[tauri::command]
+async fn pick_and_allow_workspace(app: tauri::AppHandle) -> Result<String, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let picked = app
+            .dialog()
+            .file()
+            .set_title("Open Workspace")
+            .blocking_pick_folder()
+            .ok_or_else(|| NeoError::Cancelled("No folder was picked".to_string()))?;
+        let path = picked
+            .into_path()
+            .map_err(|e| NeoError::InvalidInput(format!("Invalid folder: {e}")))?;
 
-    // Read Info.plist to find the icon file name
-    let plist_path = format!("{app_path}/Contents/Info.plist");
-    let plist_output = Command::new("defaults")
-        .args(["read", &plist_path, "CFBundleIconFile"])
-        .output()
-        .map_err(|e| format!("Failed to read plist: {e}"))?;
+        let grants = app.state::<WorkspaceGrants>();
+        let canonical =
+            grant_workspace(&app, &grants, path.to_string_lossy().into_owned(), false, true)?
+                .canonical_path;
+        workspaces::remember(&app, &canonical, true)?;
+        let info = memory::init(&canonical)?;
+        workspaces::set_workspace_id(&app, &canonical, &info.manifest.workspace_id)?;
+        logging::set_active_workspace(&canonical);
+        Ok(canonical.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to pick workspace: {e}")))?
+}
 
-    let mut icon_name = String::from_utf8_lossy(&plist_output.stdout).trim().to_string();
-    if icon_name.is_empty() {
-        icon_name = "AppIcon".to_string();
-    }
-    if !icon_name.ends_with(".icns") {
-        icon_name.push_str(".icns");
+/// Revoke Neo's access to a workspace directory previously granted by `allow_workspace_dir`.
+///
+/// The path is canonicalized the same way as when granting so the pair matches;
+/// folders that have since been deleted are matched by their raw path. Symlinked
+/// paths granted along with the folder are revoked with it. The plugin
+/// scope has no way to drop an allow entry, so the directory is forbidden for the
+/// rest of the session, as deeply as it was granted, and removed from the registry so
+/// it isn't restored.
+///
+/// Returns the workspaces that remain granted.
+#[tauri::command]
+fn revoke_workspace_dir(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    path: String,
+) -> Result<Vec<String>, NeoError> {
+    let raw = PathBuf::from(path);
+    let canonical = raw.canonicalize().unwrap_or(raw);
+
+    let granted = grants.is_recursive(&canonical);
+    let registered = workspaces::forget(&app, &canonical)?;
+    if granted.is_none() && !registered {
+        return Err(NeoError::NotFound("Folder was never granted".to_string()));
     }
 
-    let icns_path = format!("{app_path}/Contents/Resources/{icon_name}");
-    if !PathBuf::from(&icns_path).exists() {
-        return Err(format!("Icon file not found: {icns_path}"));
+    if let Some(recursive) = granted {
+        let scope = app.fs_scope();
+        for path in std::iter::once(canonical.clone()).chain(grants.links(&canonical)) {
+            scope
+                .forbid_directory(&path, recursive)
+                .map_err(|e| NeoError::Failed(format!("Failed to revoke directory: {e}")))?;
+        }
+        grants.remove(&canonical);
     }
+    app.state::<WorkspaceEnv>().forget(&canonical);
+    app.state::<WorkspaceLocks>().release(&canonical);
+    watcher::unwatch_workspace(
+        app.state::<WorkspaceWatchers>(),
+        canonical.to_string_lossy().into_owned(),
+    );
 
-    // Convert icns to 32x32 PNG using sips
-    let tmp_dir = std::env::temp_dir();
-    let tmp_png = tmp_dir.join(format!("neo_icon_{}.png", app_name.replace(' ', "_")));
+    Ok(workspaces::get_persisted_workspaces(app))
+}
+
+/// Point a registered workspace whose folder was moved or renamed at its new location.
+///
+/// The new folder must hold a `.neomemory/manifest.json` whose workspace ID matches
+/// the one recorded for the old entry. The new folder is granted the same way the
+/// old one was, the old grant is revoked, and the registry entry keeps its name and
+/// deny-list.
+#[tauri::command]
+async fn relink_workspace(
+    app: tauri::AppHandle,
+    old_path: String,
+    new_path: String,
+    allow_external: Option<bool>,
+) -> Result<WorkspaceEntry, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        relink(&app, old_path, new_path, allow_external.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to relink workspace: {e}")))?
+}
 
-    let sips_result = Command::new("sips")
-        .args([
-            "-s", "format", "png",
-            "-z", "32", "32",
-            &icns_path,
-            "--out",
-            tmp_png.to_str().unwrap(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run sips: {e}"))?;
+fn relink(
+    app: &tauri::AppHandle,
+    old_path: String,
+    new_path: String,
+    allow_external: bool,
+) -> Result<WorkspaceEntry, NeoError> {
+    let grants = app.state::<WorkspaceGrants>();
+    let old = workspaces::resolve(old_path);
+    let entry = workspaces::load(app)
+        .into_iter()
+        .find(|e| e.path == old)
+        .ok_or_else(|| NeoError::NotFound("Workspace is not registered".to_string()))?;
+    let expected = entry.workspace_id.ok_or_else(|| {
+        NeoError::InvalidInput(
+            "Workspace has no recorded ID; open it from its new location instead".to_string(),
+        )
+    })?;
 
-    if !sips_result.status.success() {
-        return Err(format!(
-            "sips failed: {}",
-            String::from_utf8_lossy(&sips_result.stderr)
+    let new = PathBuf::from(&new_path)
+        .canonicalize()
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
+    if paths::is_within(&new, &old) || paths::is_within(&old, &new) {
+        return Err(NeoError::Failed(
+            "The new location overlaps the old one".to_string(),
+        ));
+    }
+    if memory::workspace_id(&new)? != expected {
+        return Err(NeoError::Failed(
+            "Selected folder is a different workspace".to_string(),
         ));
     }
 
-    let png_data = std::fs::read(&tmp_png).map_err(|e| format!("Failed to read PNG: {e}"))?;
-    let _ = std::fs::remove_file(&tmp_png);
-
-    let b64 = base64::engine::general_purpose::STANDARD.encode(&png_data);
-    Ok(format!("data:image/png;base64,{b64}"))
+    grant_workspace(app, &grants, new_path, allow_external, entry.recursive)?;
+    if let Some(recursive) = grants.is_recursive(&old) {
+        app.fs_scope()
+            .forbid_directory(&old, recursive)
+            .map_err(|e| NeoError::Failed(format!("Failed to revoke directory: {e}")))?;
+        grants.remove(&old);
+    }
+    app.state::<WorkspaceLocks>().release(&old);
+    watcher::unwatch_workspace(
+        app.state::<WorkspaceWatchers>(),
+        old.to_string_lossy().into_owned(),
+    );
+    workspaces::relink(app, &old, &new)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![get_gemini_api_key, get_openrouter_api_key, allow_workspace_dir, get_app_icon])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(WorkspaceEnv::default())
+        .manage(WorkspaceGrants::default())
+        .manage(IconCache::default())
+        .manage(WorkspaceWatchers::default())
+        .manage(ClipboardState::default())
+        .manage(WorkspaceLocks::default())
+        .manage(ChatStreams::default())
+        .setup(|app| {
+            secrets::init(app.handle());
+            load_config_env(app.handle());
+            // Restoring walks each workspace for its deny-list, so it happens off the
+            // main thread; grants appear as each workspace is done.
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || workspaces::restore(&handle));
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<WorkspaceWatchers>().clear();
+                window.state::<WorkspaceLocks>().clear();
+            }
+        })
+        .invoke_handler(logging::log_invocations(tauri::generate_handler![
+            get_api_key,
+            get_api_key_secure,
+            get_api_key_source,
+            set_api_key,
+            delete_api_key,
+            has_api_key,
+            validate_api_key,
+            get_gemini_api_key,
+            get_openrouter_api_key,
+            get_anthropic_api_key,
+            load_workspace_env,
+            get_env_vars,
+            allow_workspace_dir,
+            allow_workspace_dirs,
+            revoke_workspace_dir,
+            roots::get_allowed_roots,
+            roots::add_allowed_root,
+            roots::remove_allowed_root,
+            relink_workspace,
+            backups::list_memory_backups,
+            backups::restore_memory_backup,
+            browser::open_url_in_browser,
+            bundle::export_workspace_config,
+            bundle::import_workspace_config,
+            chat::openrouter_chat_stream,
+            chat::cancel_chat_stream,
+            clipboard::get_clipboard_text,
+            clipboard::set_clipboard_text,
+            cloud::materialize_file,
+            compaction::compact_memory,
+            conversations::list_workspace_conversations,
+            conversations::compress_conversation,
+            conversations::delete_workspace_conversation,
+            conversations::restore_workspace_conversation,
+            conversations::empty_workspace_trash,
+            conversations::import_conversation_from_markdown,
+            conversations::export_conversation_to_markdown,
+            encryption::enable_memory_encryption,
+            encryption::disable_memory_encryption,
+            encryption::unlock_memory,
+            files::resolve_workspace_path,
+            files::read_workspace_file,
+            files::write_workspace_file,
+            files::compute_file_hash,
+            files::move_to_trash,
+            files::move_to_trash_batch,
+            files::reveal_in_file_manager,
+            lock::open_workspace,
+            lock::close_workspace,
+            lock::force_unlock_workspace,
+            logging::get_log_tail,
+            register_workspace,
+            init_workspace,
+            pick_and_allow_workspace,
+            workspaces::get_persisted_workspaces,
+            workspaces::list_allowed_workspaces,
+            workspaces::list_allowed_workspace_dirs,
+            workspaces::get_allowed_directories,
+            workspaces::list_workspaces,
+            workspaces::touch_workspace,
+            workspaces::remove_workspace,
+            workspaces::get_workspace_denylist,
+            workspaces::set_workspace_denylist,
+            memories::save_memory,
+            memories::load_memory,
+            memories::list_memories,
+            memories::delete_memory,
+            memories::search_memories,
+            memory::init_neomemory,
+            memory::clone_workspace_memory,
+            memory::read_memory,
+            memory::write_memory,
+            memory::get_provider_config,
+            memory::set_provider_config,
+            migrations::migrate_workspace,
+            portable::export_memory,
+            portable::import_memory,
+            proxy::proxy_ai_request,
+            quota::get_memory_quota,
+            quota::set_memory_quota,
+            screenshot::take_screenshot,
+            search::index_memory,
+            search::search_memory,
+            search::search_workspace_files,
+            shell::execute_shell_command,
+            sse::parse_sse_chunk,
+            stats::workspace_stats,
+            system::get_app_info,
+            system::get_system_info,
+            tokens::count_tokens_estimate,
+            watcher::watch_workspace,
+            watcher::unwatch_workspace,
+            icons::get_app_icon,
+            icons::get_app_icon_batch,
+            icons::get_app_icons,
+            icons::clear_icon_cache,
+            icons::list_installed_apps,
+            icons::launch_app
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Quitting from the menu or dock may not destroy every window first.
+            if let tauri::RunEvent::Exit = event {
+                app.state::<WorkspaceWatchers>().clear();
+                app.state::<WorkspaceLocks>().clear();
+            }
+        });
 }
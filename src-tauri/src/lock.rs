@@ -0,0 +1,351 @@
+//! `.neomemory/.lock`, so two Neo instances don't write the same workspace's memory
+//! at once and silently lose each other's changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tauri::State;
+
+use crate::error::NeoError;
+use crate::logging;
+use crate::memory::{self, MANIFEST_FILE, MEMORY_DIR};
+use crate::migrations;
+use crate::workspaces::{self, WorkspaceGrants};
+
+pub const LOCK_FILE: &str = ".lock";
+/// Held with an OS advisory lock while memory is written; see `with_write_lock`.
+pub const WRITE_LOCK_FILE: &str = ".write.lock";
+
+/// How often a held lock's heartbeat is refreshed.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// A lock whose heartbeat is older than this is treated as abandoned.
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LockInfo {
+    pid: u32,
+    /// Unix timestamp in milliseconds.
+    heartbeat: u64,
+}
+
+impl LockInfo {
+    fn current() -> Self {
+        LockInfo {
+            pid: std::process::id(),
+            heartbeat: workspaces::now_millis(),
+        }
+    }
+
+    fn to_json(&self) -> Result<String, NeoError> {
+        serde_json::to_string(self)
+            .map_err(|e| NeoError::Failed(format!("Failed to serialize lock: {e}")))
+    }
+}
+
+fn lock_path(root: &Path) -> PathBuf {
+    memory::memory_dir(root).join(LOCK_FILE)
+}
+
+/// Whether a `.neomemory/` entry is a lock file or one of the lock's temp files,
+/// which change on every heartbeat and aren't worth reporting.
+pub fn is_lock_file(name: &str) -> bool {
+    name == LOCK_FILE || name == WRITE_LOCK_FILE || name.starts_with(&format!(".{LOCK_FILE}.tmp-"))
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn is_running(pid: u32) -> bool {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).is_some()
+}
+
+/// Describe the other live instance holding the lock at `path`, if any.
+fn live_holder(path: &Path) -> Option<String> {
+    let stale_after = STALE_AFTER.as_millis() as u64;
+    match read_lock(path) {
+        Some(info) => {
+            let fresh = workspaces::now_millis().saturating_sub(info.heartbeat) < stale_after;
+            (fresh && info.pid != std::process::id() && is_running(info.pid))
+                .then(|| format!("another Neo instance (process {})", info.pid))
+        }
+        // Unreadable, or another instance is still writing it: judge by its age.
+        None => {
+            let age = fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+            (age < STALE_AFTER).then(|| "another Neo instance".to_string())
+        }
+    }
+}
+
+fn is_owned(path: &Path) -> bool {
+    read_lock(path).is_some_and(|info| info.pid == std::process::id())
+}
+
+fn heartbeat(path: PathBuf, stop: mpsc::Receiver<()>) {
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(HEARTBEAT_INTERVAL) {
+        // Someone force-unlocked the workspace; don't recreate the lock behind them.
+        if !is_owned(&path) {
+            return;
+        }
+        if let Ok(json) = LockInfo::current().to_json() {
+            let _ = memory::write_atomic(&path, json.as_bytes());
+        }
+    }
+}
+
+/// A held workspace lock. Dropping it stops the heartbeat and removes the lock file.
+struct LockGuard {
+    path: PathBuf,
+    stop: Option<mpsc::Sender<()>>,
+    heartbeat: Option<thread::JoinHandle<()>>,
+}
+
+impl LockGuard {
+    fn start(path: PathBuf) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let thread_path = path.clone();
+        let heartbeat = thread::spawn(move || heartbeat(thread_path, stopped));
+        LockGuard {
+            path,
+            stop: Some(stop),
+            heartbeat: Some(heartbeat),
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        // Stop the heartbeat first so it can't rewrite the file after it's removed.
+        drop(self.stop.take());
+        if let Some(heartbeat) = self.heartbeat.take() {
+            let _ = heartbeat.join();
+        }
+        if is_owned(&self.path) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn acquire(root: &Path) -> Result<LockGuard, NeoError> {
+    let path = lock_path(root);
+    fs::create_dir_all(memory::memory_dir(root))
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")))?;
+    let json = LockInfo::current().to_json()?;
+
+    // Once to take a free lock, and once more after clearing a stale one.
+    for _ in 0..2 {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(json.as_bytes()).map_err(|e| {
+                    NeoError::Io(format!("Failed to write {MEMORY_DIR}/{LOCK_FILE}: {e}"))
+                })?;
+                return Ok(LockGuard::start(path));
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if let Some(holder) = live_holder(&path) {
+                    return Err(NeoError::AlreadyOpen(format!(
+                        "Workspace is already open in {holder}"
+                    )));
+                }
+                let _ = fs::remove_file(&path);
+            }
+            Err(e) => {
+                return Err(NeoError::Io(format!(
+                    "Failed to create {MEMORY_DIR}/{LOCK_FILE}: {e}"
+                )))
+            }
+        }
+    }
+    Err(NeoError::AlreadyOpen(
+        "Workspace is being opened by another Neo instance".to_string(),
+    ))
+}
+
+/// Locks held by this instance, keyed by canonical workspace path.
+#[derive(Default)]
+pub struct WorkspaceLocks(Mutex<HashMap<PathBuf, LockGuard>>);
+
+impl WorkspaceLocks {
+    /// Release the lock on a workspace, if this instance holds it.
+    pub fn release(&self, root: &Path) {
+        if let Ok(mut locks) = self.0.lock() {
+            locks.remove(root);
+        }
+    }
+
+    /// Run `f` while this instance holds the workspace's lock, taking the lock just for
+    /// the call if the workspace wasn't opened with `open_workspace`.
+    pub fn while_held<T>(&self, root: &Path, f: impl FnOnce() -> T) -> Result<T, NeoError> {
+        let held = self
+            .0
+            .lock()
+            .map_err(|_| NeoError::Failed("Lock state is unavailable".to_string()))?
+            .contains_key(root);
+        let _guard = if held { None } else { Some(acquire(root)?) };
+        Ok(f())
+    }
+
+    /// Release every lock.
+    pub fn clear(&self) {
+        if let Ok(mut locks) = self.0.lock() {
+            locks.clear();
+        }
+    }
+}
+
+/// In-process write locks, keyed by workspace root.
+static WRITERS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+/// Run `f`, which writes to a workspace's memory, once no other write to it is under
+/// way, so two writes can't interleave.
+///
+/// Writes from this process wait on an in-process mutex; writes from other processes
+/// that take the same advisory lock (`flock` on Unix, `LockFileEx` on Windows) wait on
+/// `.neomemory/.write.lock`. Memory files themselves are replaced by renames, which
+/// would swap out a lock held on them, so the lock lives in a file of its own. Must
+/// not be nested for the same workspace.
+pub fn with_write_lock<T>(root: &Path, f: impl FnOnce() -> T) -> Result<T, NeoError> {
+    let local = WRITERS
+        .lock()
+        .map_err(|_| NeoError::Failed("Write lock state is unavailable".to_string()))?
+        .entry(root.to_path_buf())
+        .or_default()
+        .clone();
+    // A panic during an earlier write doesn't make the next one unsafe.
+    let _local = local
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let memory = memory::memory_dir(root);
+    fs::create_dir_all(&memory)
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")))?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(memory.join(WRITE_LOCK_FILE))
+        .map_err(|e| {
+            NeoError::Io(format!(
+                "Failed to create {MEMORY_DIR}/{WRITE_LOCK_FILE}: {e}"
+            ))
+        })?;
+    file.lock().map_err(|e| {
+        NeoError::Io(format!(
+            "Failed to lock {MEMORY_DIR}/{WRITE_LOCK_FILE}: {e}"
+        ))
+    })?;
+    // The OS lock is released when `file` is dropped.
+    Ok(f())
+}
+
+/// Lock a granted workspace for this instance.
+///
+/// Fails with `alreadyOpen` while another running Neo instance holds the lock. Locks
+/// left behind by a crashed instance, or whose heartbeat stopped, are taken over.
+/// Opening a workspace this instance already holds is a no-op.
+///
+/// Memory written by an older version of Neo is migrated to the current schema once
+/// the lock is held; memory from a newer version is refused.
+#[tauri::command]
+pub fn open_workspace(
+    grants: State<'_, WorkspaceGrants>,
+    locks: State<'_, WorkspaceLocks>,
+    path: String,
+) -> Result<(), NeoError> {
+    let root = grants.require(&path)?;
+    let mut held = locks
+        .0
+        .lock()
+        .map_err(|_| NeoError::Failed("Lock state is unavailable".to_string()))?;
+    if !held.contains_key(&root) {
+        let guard = acquire(&root)?;
+        if memory::memory_dir(&root).join(MANIFEST_FILE).is_file() {
+            migrations::migrate(&root)?;
+        }
+        held.insert(root.clone(), guard);
+    }
+    logging::set_active_workspace(&root);
+    Ok(())
+}
+
+/// Release this instance's lock on a workspace. Closing a workspace that isn't locked
+/// is a no-op.
+#[tauri::command]
+pub fn close_workspace(locks: State<'_, WorkspaceLocks>, path: String) {
+    let root = workspaces::resolve(path);
+    locks.release(&root);
+    logging::clear_active_workspace(&root);
+}
+
+/// Remove a workspace's lock file whoever holds it, for a lock that is stuck.
+#[tauri::command]
+pub fn force_unlock_workspace(
+    grants: State<'_, WorkspaceGrants>,
+    locks: State<'_, WorkspaceLocks>,
+    path: String,
+) -> Result<(), NeoError> {
+    let root = grants.require(&path)?;
+    locks.release(&root);
+    match fs::remove_file(lock_path(&root)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(NeoError::Io(format!(
+            "Failed to remove {MEMORY_DIR}/{LOCK_FILE}: {e}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_writes_to_a_workspace() {
+        let root = std::env::temp_dir().join(format!("neo-lock-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let counter = root.join("counter");
+        fs::write(&counter, "0").unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let (root, counter) = (root.clone(), counter.clone());
+                thread::spawn(move || {
+                    with_write_lock(&root, || {
+                        let n: u32 = fs::read_to_string(&counter).unwrap().parse().unwrap();
+                        thread::sleep(Duration::from_millis(5));
+                        fs::write(&counter, (n + 1).to_string()).unwrap();
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(fs::read_to_string(&counter).unwrap(), "8");
+        assert!(memory::memory_dir(&root).join(WRITE_LOCK_FILE).is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn recognizes_lock_files() {
+        assert!(is_lock_file(LOCK_FILE));
+        assert!(is_lock_file(WRITE_LOCK_FILE));
+        assert!(is_lock_file(&memory::temp_name(LOCK_FILE)));
+        assert!(!is_lock_file("memory.json"));
+        assert!(!is_lock_file(&memory::temp_name("memory.json")));
+    }
+}
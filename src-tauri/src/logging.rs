@@ -0,0 +1,315 @@
+//! Structured logging to `.neomemory/neo.log`, as JSON lines, so failures can be
+//! inspected after the fact from an in-app log viewer.
+//!
+//! An event goes to the log of the open workspace it names in a `workspace` field,
+//! its own or its command's, and otherwise to the active workspace, the one opened
+//! last. Commands name the workspace they were called with.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json::Value;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::{Runtime, State};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::error::NeoError;
+use crate::memory;
+use crate::workspaces::{self, WorkspaceGrants};
+
+pub const LOG_FILE: &str = "neo.log";
+/// The previous log, kept once `neo.log` grows past `MAX_LOG_BYTES`.
+pub const ROTATED_LOG_FILE: &str = "neo.log.1";
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Arguments whose string values are logged: paths, ids and names of things, never
+/// content. Every other string is logged by length only, so memory, messages and
+/// queries stay out of the log, which isn't encrypted.
+const LOGGED_ARGS: &[&str] = &[
+    "workspace",
+    "workspacePath",
+    "sourceWorkspace",
+    "destWorkspace",
+    "path",
+    "paths",
+    "relativePath",
+    "relative",
+    "sourcePath",
+    "destPath",
+    "oldPath",
+    "newPath",
+    "id",
+    "conversationId",
+    "backupId",
+    "requestId",
+    "provider",
+    "model",
+    "command",
+    "appName",
+    "appNames",
+    "appNameOrPath",
+];
+/// Arguments naming the workspace a command works in, in the order they are checked.
+const WORKSPACE_ARGS: &[&str] = &["workspacePath", "workspace", "sourceWorkspace"];
+/// Logged strings longer than this are logged by length only.
+const MAX_LOGGED_STRING: usize = 200;
+const REDACTED: &str = "***";
+
+/// Roots of the open workspaces, the active one last.
+static OPEN_WORKSPACES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+/// Held while an event is appended, so concurrent writers rotate a log once and never
+/// lose a line to the rename.
+static APPENDING: Mutex<()> = Mutex::new(());
+
+/// Whether a `.neomemory/` entry is the log or its rotated copy.
+pub fn is_log_file(name: &str) -> bool {
+    name == LOG_FILE || name == ROTATED_LOG_FILE
+}
+
+/// Make `root` the active workspace, logging events that name no workspace to its
+/// `.neomemory/neo.log` from now on.
+pub fn set_active_workspace(root: &Path) {
+    if let Ok(mut open) = OPEN_WORKSPACES.lock() {
+        open.retain(|other| other != root);
+        open.push(root.to_path_buf());
+    }
+}
+
+/// Stop logging to `root`. The workspace opened before it becomes active again.
+pub fn clear_active_workspace(root: &Path) {
+    if let Ok(mut open) = OPEN_WORKSPACES.lock() {
+        open.retain(|other| other != root);
+    }
+}
+
+/// The workspace a formatted event names, from its own fields or its command's span.
+fn named_workspace(event: &[u8]) -> Option<PathBuf> {
+    let event: Value = serde_json::from_slice(event).ok()?;
+    let named = event
+        .pointer("/fields/workspace")
+        .or_else(|| event.pointer("/span/workspace"))?
+        .as_str()?;
+    Some(workspaces::resolve(named.to_string()))
+}
+
+/// The open workspace whose log receives an event. An event naming a workspace that
+/// isn't open is dropped rather than logged to another workspace.
+fn log_root(open: &[PathBuf], named: Option<&Path>) -> Option<PathBuf> {
+    match named {
+        Some(named) => open.iter().find(|root| root.as_path() == named).cloned(),
+        None => open.last().cloned(),
+    }
+}
+
+/// Appends each formatted event to its workspace's log, rotating the log first once
+/// it is too large. Events are dropped while no workspace is open.
+struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let named = named_workspace(buf);
+        let Some(root) = OPEN_WORKSPACES
+            .lock()
+            .ok()
+            .and_then(|open| log_root(&open, named.as_deref()))
+        else {
+            return Ok(buf.len());
+        };
+        let memory = memory::memory_dir(&root);
+        if !memory.is_dir() {
+            return Ok(buf.len());
+        }
+        let _appending = APPENDING
+            .lock()
+            .map_err(|_| io::Error::other("Log state is unavailable"))?;
+        let path = memory.join(LOG_FILE);
+        if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+            fs::rename(&path, memory.join(ROTATED_LOG_FILE))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct MakeLogWriter;
+
+impl<'a> MakeWriter<'a> for MakeLogWriter {
+    type Writer = LogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogWriter
+    }
+}
+
+/// Install the global subscriber. Called once from `run()`.
+pub fn init() {
+    let _ = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::INFO)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_writer(MakeLogWriter)
+        .try_init();
+}
+
+fn is_secret_arg(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["key", "passphrase", "password", "secret", "token"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+/// A command argument as logged: secrets redacted, and strings summarized unless the
+/// argument is in `LOGGED_ARGS`. Numbers and booleans are kept.
+fn loggable(name: &str, value: &Value) -> Value {
+    match value {
+        _ if is_secret_arg(name) => Value::from(REDACTED),
+        Value::String(s) if LOGGED_ARGS.contains(&name) && s.len() <= MAX_LOGGED_STRING => {
+            value.clone()
+        }
+        Value::String(s) => Value::from(format!("<{} bytes>", s.len())),
+        Value::Array(items) => Value::Array(items.iter().map(|v| loggable(name, v)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(name, value)| (name.clone(), loggable(name, value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// The workspace a command was called with, from the first of `WORKSPACE_ARGS` it has.
+fn workspace_arg(args: &Value) -> Option<&str> {
+    WORKSPACE_ARGS
+        .iter()
+        .find_map(|name| args.get(name).and_then(Value::as_str))
+}
+
+/// Wrap the command handler so every command is logged with its arguments on entry.
+/// Failures are logged as their error is turned into the response; see
+/// `From<NeoError> for InvokeError`. Both name the command's workspace, though a
+/// failure only does for a command that finishes on the calling thread; async
+/// commands fail on another one, and their failures go to the active workspace.
+pub fn log_invocations<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let (args, workspace) = match invoke.message.payload() {
+            InvokeBody::Json(args) => (
+                loggable("", args).to_string(),
+                workspace_arg(args).map(str::to_string),
+            ),
+            InvokeBody::Raw(bytes) => (format!("<{} bytes>", bytes.len()), None),
+        };
+        let command = invoke.message.command().to_string();
+        let span = tracing::info_span!("command", workspace = workspace.as_deref());
+        span.in_scope(|| {
+            tracing::info!(command, workspace = workspace.as_deref(), args, "command");
+            handler(invoke)
+        })
+    }
+}
+
+/// Last `lines` entries of a workspace's log, oldest first, reaching into the
+/// rotated log if `neo.log` has fewer.
+#[tauri::command]
+pub fn get_log_tail(
+    grants: State<'_, WorkspaceGrants>,
+    workspace_path: String,
+    lines: usize,
+) -> Result<Vec<String>, NeoError> {
+    let memory = memory::memory_dir(&grants.require(&workspace_path)?);
+    let mut tail: Vec<String> = Vec::new();
+    for name in [LOG_FILE, ROTATED_LOG_FILE] {
+        if tail.len() >= lines {
+            break;
+        }
+        let content = match fs::read_to_string(memory.join(name)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(NeoError::Io(format!("Failed to read {name}: {e}"))),
+        };
+        let wanted = lines - tail.len();
+        let older: Vec<&str> = content.lines().rev().take(wanted).collect();
+        tail.extend(older.into_iter().map(str::to_string));
+    }
+    tail.reverse();
+    Ok(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn logs_paths_but_not_content() {
+        let args = json!({
+            "workspace": "/home/me/project",
+            "content": "meeting notes",
+            "tags": ["secret-project"],
+            "limit": 5,
+        });
+        assert_eq!(
+            loggable("", &args),
+            json!({
+                "workspace": "/home/me/project",
+                "content": "<13 bytes>",
+                "tags": ["<14 bytes>"],
+                "limit": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_secrets_and_nested_content() {
+        let args = json!({
+            "apiKey": "sk-123",
+            "messages": [{ "role": "user", "content": "hi" }],
+        });
+        assert_eq!(
+            loggable("", &args),
+            json!({
+                "apiKey": REDACTED,
+                "messages": [{ "role": "<4 bytes>", "content": "<2 bytes>" }],
+            })
+        );
+    }
+
+    #[test]
+    fn reads_the_workspace_an_event_or_its_command_names() {
+        let named = |event: Value| named_workspace(event.to_string().as_bytes());
+        let entry = json!({
+            "fields": { "message": "command", "workspace": "/nowhere/a" },
+            "span": { "name": "command" },
+        });
+        assert_eq!(named(entry), Some(PathBuf::from("/nowhere/a")));
+        let failure = json!({
+            "fields": { "message": "command failed" },
+            "span": { "workspace": "/nowhere/b", "name": "command" },
+        });
+        assert_eq!(named(failure), Some(PathBuf::from("/nowhere/b")));
+        assert_eq!(named(json!({ "fields": { "message": "evicted" } })), None);
+    }
+
+    #[test]
+    fn logs_to_the_named_workspace_or_the_active_one() {
+        let open = [PathBuf::from("/nowhere/a"), PathBuf::from("/nowhere/b")];
+        assert_eq!(
+            log_root(&open, Some(Path::new("/nowhere/a"))),
+            Some(PathBuf::from("/nowhere/a"))
+        );
+        assert_eq!(log_root(&open, None), Some(PathBuf::from("/nowhere/b")));
+        assert_eq!(log_root(&open, Some(Path::new("/nowhere/c"))), None);
+        assert_eq!(log_root(&[], None), None);
+    }
+}
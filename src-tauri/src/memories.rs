@@ -0,0 +1,393 @@
+//! Individual memory entries stored as JSON files under `.neomemory/memories/`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+
+use crate::encryption;
+use crate::error::NeoError;
+use crate::lock;
+use crate::memory::{self, MEMORIES_DIR, MEMORY_DIR};
+use crate::paths;
+use crate::quota;
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Longest accepted memory id.
+const MAX_ID_LEN: usize = 128;
+/// Hits returned by `search_memories` unless the caller sets a limit.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+/// Characters of context kept on each side of a match in a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+/// Weight of a query term found in a tag, relative to one occurrence in the content.
+const TAG_WEIGHT: usize = 3;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Memory {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds.
+    pub updated_at: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryHit {
+    pub id: String,
+    /// Content around the first match, or the start of the content if only tags matched.
+    pub snippet: String,
+    /// Occurrences of the query terms, with tag matches weighted higher.
+    pub score: usize,
+    pub tags: Vec<String>,
+    /// Unix timestamp in milliseconds.
+    pub updated_at: u64,
+}
+
+/// Memory ids become file names, so they are limited to letters, digits, `-`, `_`
+/// and `.`. That rules out path separators, null bytes and leading slashes; ids
+/// starting with a dot or containing `..` are rejected too.
+pub fn validate_id(id: &str) -> Result<(), NeoError> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && !id.starts_with('.')
+        && !id.contains("..")
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(NeoError::InvalidInput(format!("Invalid memory id: {id}")))
+    }
+}
+
+fn memories_dir(workspace: &Path) -> PathBuf {
+    memory::memory_dir(workspace).join(MEMORIES_DIR)
+}
+
+/// File of a memory, the only way memory commands turn an id into a path.
+///
+/// The id is validated, and the final path is resolved through any symlinks and
+/// checked to still lie inside the workspace's `.neomemory/`.
+pub fn safe_memory_path(workspace: &Path, id: &str) -> Result<PathBuf, NeoError> {
+    validate_id(id)?;
+    let memory = paths::resolve_in_workspace(workspace, Path::new(MEMORY_DIR))?;
+    let relative = Path::new(MEMORY_DIR)
+        .join(MEMORIES_DIR)
+        .join(format!("{id}.json"));
+    let path = paths::resolve_in_workspace(workspace, &relative)?;
+    if !paths::is_within(&path, &memory) {
+        return Err(NeoError::InvalidInput(format!(
+            "Memory path escapes {MEMORY_DIR}: {id}"
+        )));
+    }
+    Ok(path)
+}
+
+fn read_memory(path: &Path, id: &str) -> Result<Memory, NeoError> {
+    let content = fs::read(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => NeoError::NotFound(format!("Memory not found: {id}")),
+        _ => NeoError::Failed(format!("Failed to read memory {id}: {e}")),
+    })?;
+    let content = encryption::open(content)?;
+    serde_json::from_slice(&content)
+        .map_err(|e| NeoError::Failed(format!("Failed to parse memory {id}: {e}")))
+}
+
+fn write_entry(
+    workspace: &Path,
+    path: &Path,
+    id: String,
+    content: String,
+    tags: Option<Vec<String>>,
+) -> Result<Memory, NeoError> {
+    let now = workspaces::now_millis();
+    let existing = match read_memory(path, &id) {
+        Ok(memory) => Some(memory),
+        Err(NeoError::NotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+    let memory = Memory {
+        id,
+        content,
+        tags: tags
+            .or_else(|| existing.as_ref().map(|m| m.tags.clone()))
+            .unwrap_or_default(),
+        created_at: existing.as_ref().map_or(now, |m| m.created_at),
+        updated_at: now,
+    };
+
+    fs::create_dir_all(memories_dir(workspace))
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}/{MEMORIES_DIR}: {e}")))?;
+    let serialized = serde_json::to_string_pretty(&memory)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize memory: {e}")))?;
+    quota::make_room(workspace, path, serialized.len() as u64)?;
+    encryption::write(workspace, path, serialized.as_bytes())?;
+    search::refresh(workspace, path);
+    Ok(memory)
+}
+
+/// Create or replace a memory. An existing memory keeps its creation time, and its
+/// tags unless new ones are given. The write runs on a blocking thread.
+#[tauri::command]
+pub async fn save_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    id: String,
+    content: String,
+    tags: Option<Vec<String>>,
+) -> Result<Memory, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let workspace = app.state::<WorkspaceGrants>().require(&workspace)?;
+        let path = safe_memory_path(&workspace, &id)?;
+        lock::with_write_lock(&workspace, || {
+            write_entry(&workspace, &path, id, content, tags)
+        })?
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to save memory: {e}")))?
+}
+
+/// Load a memory by id.
+#[tauri::command]
+pub fn load_memory(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    id: String,
+) -> Result<Memory, NeoError> {
+    let workspace = grants.require(&workspace)?;
+    let path = safe_memory_path(&workspace, &id)?;
+    read_memory(&path, &id)
+}
+
+/// List a workspace's memories, most recently updated first. Files that aren't valid
+/// memories are skipped.
+#[tauri::command]
+pub fn list_memories(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+) -> Result<Vec<Memory>, NeoError> {
+    let workspace = grants.require(&workspace)?;
+    let mut memories: Vec<Memory> = fs::read_dir(memories_dir(&workspace))
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let id = name.to_str()?.strip_suffix(".json")?;
+            let path = safe_memory_path(&workspace, id).ok()?;
+            read_memory(&path, id).ok()
+        })
+        .collect();
+    memories.sort_by_key(|m| std::cmp::Reverse(m.updated_at));
+    Ok(memories)
+}
+
+/// Delete a memory by id, on a blocking thread.
+#[tauri::command]
+pub async fn delete_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    id: String,
+) -> Result<(), NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let workspace = app.state::<WorkspaceGrants>().require(&workspace)?;
+        let path = safe_memory_path(&workspace, &id)?;
+        lock::with_write_lock(&workspace, || fs::remove_file(&path))?.map_err(|e| {
+            match e.kind() {
+                io::ErrorKind::NotFound => NeoError::NotFound(format!("Memory not found: {id}")),
+                _ => NeoError::Failed(format!("Failed to delete memory {id}: {e}")),
+            }
+        })?;
+        search::refresh(&workspace, &path);
+        Ok(())
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to delete memory: {e}")))?
+}
+
+/// Up to `SNIPPET_CONTEXT` characters on each side of the byte offset `at`.
+fn snippet(content: &str, at: usize) -> String {
+    let start = content[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let end = content[at..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(content.len(), |(i, _)| at + i);
+
+    let mut snippet = content[start..end].trim().replace('\n', " ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < content.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Byte offset in `content` of the first place that reads as the lowercase `term`
+/// once lowercased. Lowercasing can change byte lengths, so offsets into the
+/// lowercased text don't line up with the original.
+fn find_ignoring_case(content: &str, term: &str) -> Option<usize> {
+    content.char_indices().map(|(i, _)| i).find(|&i| {
+        let mut lowered = content[i..].chars().flat_map(char::to_lowercase);
+        term.chars().all(|c| lowered.next() == Some(c))
+    })
+}
+
+/// Score a memory against lowercase query terms, or `None` if no term matches.
+fn score(memory: &Memory, terms: &[String]) -> Option<MemoryHit> {
+    let content = memory.content.to_lowercase();
+    let tags: Vec<String> = memory.tags.iter().map(|t| t.to_lowercase()).collect();
+
+    let mut score = 0;
+    let mut first_match: Option<usize> = None;
+    for term in terms {
+        score += content.matches(term.as_str()).count();
+        score += TAG_WEIGHT * tags.iter().filter(|t| t.contains(term.as_str())).count();
+        if let Some(at) = find_ignoring_case(&memory.content, term) {
+            first_match = Some(first_match.map_or(at, |first| first.min(at)));
+        }
+    }
+    if score == 0 {
+        return None;
+    }
+
+    let at = first_match.unwrap_or(0);
+    Some(MemoryHit {
+        id: memory.id.clone(),
+        snippet: snippet(&memory.content, at),
+        score,
+        tags: memory.tags.clone(),
+        updated_at: memory.updated_at,
+    })
+}
+
+/// Search memory content and tags for any of the whitespace-separated terms in
+/// `query`, ignoring case. Hits are ordered by score, then recency, and capped at
+/// `limit` (20 by default).
+///
+/// Memories are read one at a time and only their hits are kept, so large memory
+/// sets aren't loaded at once.
+#[tauri::command]
+pub fn search_memories(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<MemoryHit>, NeoError> {
+    let workspace = grants.require(&workspace)?;
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let rank = |hits: &mut Vec<MemoryHit>| {
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(b.updated_at.cmp(&a.updated_at)));
+        hits.truncate(limit);
+    };
+    let mut hits = Vec::new();
+    let entries = fs::read_dir(memories_dir(&workspace))
+        .into_iter()
+        .flatten()
+        .flatten();
+    for entry in entries {
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+            continue;
+        };
+        let Ok(memory) = safe_memory_path(&workspace, id).and_then(|path| read_memory(&path, id))
+        else {
+            continue;
+        };
+        if let Some(hit) = score(&memory, &terms) {
+            hits.push(hit);
+            if hits.len() >= limit * 2 {
+                rank(&mut hits);
+            }
+        }
+    }
+    rank(&mut hits);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(content: &str) -> Memory {
+        Memory {
+            id: "m".to_string(),
+            content: content.to_string(),
+            tags: Vec::new(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn finds_matches_in_the_original_text() {
+        // Lowercasing `İ` and `ẞ` shifts every later byte offset while keeping the
+        // total length the same.
+        assert_eq!(find_ignoring_case("İ fé ẞ", "é"), Some(4));
+        assert_eq!(find_ignoring_case("Hello World", "world"), Some(6));
+        assert_eq!(find_ignoring_case("Hello", "bye"), None);
+    }
+
+    #[test]
+    fn snippets_never_split_a_character() {
+        let hit = score(&memory("İ fé ẞ"), &["é".to_string()]).unwrap();
+        assert_eq!(hit.snippet, "İ fé ẞ");
+        assert_eq!(hit.score, 1);
+    }
+
+    #[test]
+    fn accepts_only_plain_ids() {
+        for id in ["notes", "a.b-c_1", &"x".repeat(MAX_ID_LEN)] {
+            assert!(validate_id(id).is_ok(), "{id}");
+        }
+        for id in [
+            "",
+            ".hidden",
+            "a..b",
+            "a/b",
+            "a\\b",
+            "é",
+            &"x".repeat(MAX_ID_LEN + 1),
+        ] {
+            assert!(validate_id(id).is_err(), "{id}");
+        }
+    }
+
+    #[test]
+    fn keeps_memory_paths_inside_neomemory() {
+        let root = std::env::temp_dir().join(format!("neo-memories-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(memory::memory_dir(&root).join(MEMORIES_DIR)).unwrap();
+        let root = root.canonicalize().unwrap();
+
+        let path = safe_memory_path(&root, "notes").unwrap();
+        assert_eq!(path, memories_dir(&root).join("notes.json"));
+        assert!(safe_memory_path(&root, "../notes").is_err());
+
+        #[cfg(unix)]
+        {
+            let outside = root.join("outside");
+            fs::create_dir_all(&outside).unwrap();
+            std::os::unix::fs::symlink(&outside, memories_dir(&root).join("linked.json")).unwrap();
+            assert!(safe_memory_path(&root, "linked").is_err());
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,694 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{Manager, State};
+
+use crate::backups;
+use crate::conversations;
+use crate::encryption::{self, EncryptionInfo};
+use crate::error::NeoError;
+use crate::lock;
+use crate::logging;
+use crate::migrations::{self, AppliedMigration};
+use crate::providers;
+use crate::quota::{self, EvictedFile};
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Per-workspace directory where Neo keeps its memory.
+pub const MEMORY_DIR: &str = ".neomemory";
+pub const CONVERSATIONS_DIR: &str = "conversations";
+pub const FILES_DIR: &str = "files";
+pub const JOURNAL_DIR: &str = "journal";
+pub const LOGS_DIR: &str = "logs";
+pub const BACKUPS_DIR: &str = "backups";
+pub const MEMORIES_DIR: &str = "memories";
+/// Deleted conversations, kept until the trash is emptied.
+pub const TRASH_DIR: &str = ".trash";
+pub const CONFIG_FILE: &str = "config.json";
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const MEMORY_FILE: &str = "memory.json";
+pub const MEMORY_BACKUP_FILE: &str = "memory.json.bak";
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// Keeps `.neomemory/` out of the workspace's git history.
+const GITIGNORE: &str = "# Created by Neo; memory stays local to this machine.\n*\n";
+
+/// Current version of the `config.json` schema.
+pub const CONFIG_VERSION: u32 = 1;
+/// Current version of the `manifest.json` schema.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Index of summarized files, shared with the frontend's memory service.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub version: u32,
+    /// Stable ID of the workspace, used to recognize it after its folder is moved.
+    /// Manifests written before IDs existed get one on the next `init`.
+    #[serde(default)]
+    pub workspace_id: String,
+    /// Workspace ID of the workspace this memory was cloned from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloned_from: Option<String>,
+    /// Unix timestamp in milliseconds.
+    #[serde(default)]
+    pub created_at: u64,
+    /// Unix timestamp in milliseconds of the last sync, 0 if never synced.
+    #[serde(default)]
+    pub last_sync: u64,
+    /// Summaries keyed by workspace-relative path; owned by the frontend.
+    #[serde(default)]
+    pub entries: Map<String, Value>,
+    /// Set while the memory files are encrypted at rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionInfo>,
+    /// Version of the `.neomemory/` file layout; see `migrations`.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_migrations: Vec<AppliedMigration>,
+}
+
+fn legacy_schema_version() -> u32 {
+    migrations::LEGACY_SCHEMA_VERSION
+}
+
+impl Manifest {
+    fn new() -> Self {
+        Manifest {
+            version: MANIFEST_VERSION,
+            workspace_id: uuid::Uuid::new_v4().to_string(),
+            cloned_from: None,
+            created_at: workspaces::now_millis(),
+            last_sync: 0,
+            entries: Map::new(),
+            encryption: None,
+            schema_version: migrations::SCHEMA_VERSION,
+            applied_migrations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInfo {
+    pub path: String,
+    pub neomemory_path: String,
+    /// `.neomemory/` did not exist and was just created.
+    pub is_new: bool,
+    pub manifest: Manifest,
+}
+
+/// Prefix of the error returned when the manifest can't be parsed, so the UI can
+/// offer a repair.
+pub const CORRUPT_MANIFEST_ERROR: &str = "Corrupt manifest";
+
+pub fn memory_dir(workspace: &Path) -> PathBuf {
+    workspace.join(MEMORY_DIR)
+}
+
+/// Name of a hidden temp file next to `file_name`, unique within this process too,
+/// so two writers to the same path never share one.
+pub fn temp_name(file_name: &str) -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    format!(".{file_name}.tmp-{}-{id}", std::process::id())
+}
+
+/// Write a file by writing a sibling temp file, flushing it to disk and renaming it
+/// over the target, so readers never see a partial write and a crash leaves either
+/// the old or the new content.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), NeoError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    replace_with(path, content, &options)
+}
+
+/// `write_atomic` for a file only the current user may read. The temp file is
+/// created with mode 0600, so the content is never readable by others, not even
+/// before the rename.
+#[cfg(target_os = "linux")]
+pub fn write_atomic_private(path: &Path, content: &[u8]) -> Result<(), NeoError> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true).mode(0o600);
+    replace_with(path, content, &options)
+}
+
+fn replace_with(path: &Path, content: &[u8], options: &fs::OpenOptions) -> Result<(), NeoError> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| NeoError::InvalidInput(format!("Invalid file path: {}", path.display())))?;
+    let tmp = path.with_file_name(temp_name(&file_name.to_string_lossy()));
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(content)?;
+        file.sync_all()
+    });
+    written.and_then(|()| fs::rename(&tmp, path)).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        NeoError::Io(format!("Failed to write {}: {e}", path.display()))
+    })?;
+    // Persist the rename itself; Windows has no way to sync a directory.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+fn write_json_if_missing(path: &Path, value: &impl Serialize) -> Result<(), NeoError> {
+    if path.exists() {
+        return Ok(());
+    }
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize {}: {e}", path.display())))?;
+    write_atomic(path, content.as_bytes())
+}
+
+/// Create any missing part of the `.neomemory/` layout, leaving existing files alone.
+fn scaffold(memory: &Path) -> Result<(), NeoError> {
+    for dir in [CONVERSATIONS_DIR, FILES_DIR, JOURNAL_DIR, LOGS_DIR, MEMORIES_DIR] {
+        fs::create_dir_all(memory.join(dir))
+            .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}/{dir}: {e}")))?;
+    }
+    write_json_if_missing(
+        &memory.join(CONFIG_FILE),
+        &serde_json::json!({ "version": CONFIG_VERSION }),
+    )?;
+    write_json_if_missing(&memory.join(MEMORY_FILE), &serde_json::json!({}))?;
+    write_json_if_missing(&memory.join(MANIFEST_FILE), &Manifest::new())?;
+
+    let gitignore = memory.join(GITIGNORE_FILE);
+    if !gitignore.exists() {
+        write_atomic(&gitignore, GITIGNORE.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Create the `.neomemory/` layout inside a workspace, leaving existing files alone.
+///
+/// A fresh layout is built in a staging directory and renamed into place, so a crash
+/// never leaves a half-initialized `.neomemory/`. An existing manifest is returned
+/// as-is; one that can't be parsed is reported as `corruptManifest`.
+pub fn init(workspace: &Path) -> Result<WorkspaceInfo, NeoError> {
+    let memory = memory_dir(workspace);
+    let is_new = !memory.exists();
+
+    if is_new {
+        let staging = workspace.join(format!("{MEMORY_DIR}.tmp-{}", std::process::id()));
+        let staged = scaffold(&staging).and_then(|()| {
+            fs::rename(&staging, &memory)
+                .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")))
+        });
+        if staged.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        // Another window may have created it first; fill in whatever is missing below.
+        if staged.is_err() && !memory.is_dir() {
+            staged?;
+        }
+    }
+    scaffold(&memory)?;
+    conversations::ensure_index(workspace)?;
+
+    let manifest_path = memory.join(MANIFEST_FILE);
+    let content = fs::read_to_string(&manifest_path)
+        .map_err(|e| NeoError::Io(format!("Failed to read {MANIFEST_FILE}: {e}")))?;
+    let mut manifest = serde_json::from_str::<Manifest>(&content).map_err(|e| {
+        NeoError::CorruptManifest(format!(
+            "{CORRUPT_MANIFEST_ERROR}: {MEMORY_DIR}/{MANIFEST_FILE}: {e}"
+        ))
+    })?;
+    if manifest.workspace_id.is_empty() {
+        manifest.workspace_id = uuid::Uuid::new_v4().to_string();
+        // Only the new key is added, so fields this version doesn't know are kept.
+        let mut raw: Map<String, Value> = serde_json::from_str(&content).unwrap_or_default();
+        raw.insert("workspaceId".to_string(), Value::from(manifest.workspace_id.clone()));
+        let updated = serde_json::to_string_pretty(&raw)
+            .map_err(|e| NeoError::Failed(format!("Failed to serialize {MANIFEST_FILE}: {e}")))?;
+        write_atomic(&manifest_path, updated.as_bytes())?;
+    }
+
+    Ok(WorkspaceInfo {
+        path: workspace.to_string_lossy().into_owned(),
+        neomemory_path: memory.to_string_lossy().into_owned(),
+        is_new,
+        manifest,
+    })
+}
+
+/// Entries of `.neomemory/` that are not worth carrying over to a clone.
+pub const TRANSIENT_DIRS: &[&str] = &[
+    LOGS_DIR,
+    BACKUPS_DIR,
+    "cache",
+    lock::LOCK_FILE,
+    lock::WRITE_LOCK_FILE,
+    logging::LOG_FILE,
+    logging::ROTATED_LOG_FILE,
+];
+
+/// Leftover temp files from `write_atomic`.
+pub fn is_temp_file(name: &str) -> bool {
+    name.starts_with('.') && name.contains(".tmp-")
+}
+
+/// Copy a directory tree, skipping `skip` at its top level, temp files and symlinks.
+pub fn copy_tree(from: &Path, to: &Path, skip: &[&str]) -> Result<(), NeoError> {
+    fs::create_dir_all(to)
+        .map_err(|e| NeoError::Io(format!("Failed to create {}: {e}", to.display())))?;
+    let entries = fs::read_dir(from)
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", from.display())))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if skip.contains(&name_str.as_ref()) || is_temp_file(&name_str) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let target = to.join(&name);
+        if file_type.is_dir() {
+            copy_tree(&entry.path(), &target, &[])?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target).map_err(|e| {
+                NeoError::Io(format!("Failed to copy {}: {e}", entry.path().display()))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Give a copied manifest its own workspace ID and record where it came from,
+/// keeping every other field.
+fn rewrite_cloned_manifest(memory: &Path, source_id: Option<String>) -> Result<String, NeoError> {
+    let path = memory.join(MANIFEST_FILE);
+    let mut raw: Map<String, Value> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| match serde_json::to_value(Manifest::new()) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        });
+    let id = uuid::Uuid::new_v4().to_string();
+    raw.insert("workspaceId".to_string(), Value::from(id.clone()));
+    raw.insert("createdAt".to_string(), Value::from(workspaces::now_millis()));
+    match source_id {
+        Some(source_id) => raw.insert("clonedFrom".to_string(), Value::from(source_id)),
+        None => raw.remove("clonedFrom"),
+    };
+    let content = serde_json::to_string_pretty(&raw)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize {MANIFEST_FILE}: {e}")))?;
+    write_atomic(&path, content.as_bytes())?;
+    Ok(id)
+}
+
+/// Copy `source/.neomemory` into a staging directory next to `dest` and swap it into
+/// place, so a crash never leaves a half-copied memory.
+fn clone_memory(source: &Path, dest: &Path, overwrite: bool) -> Result<(), NeoError> {
+    let source_memory = memory_dir(source);
+    if !source_memory.is_dir() {
+        return Err(NeoError::Failed(format!(
+            "Source workspace has no {MEMORY_DIR}"
+        )));
+    }
+    let dest_memory = memory_dir(dest);
+    if dest_memory.exists() && !overwrite {
+        return Err(NeoError::Failed(format!(
+            "Destination already has a {MEMORY_DIR}; pass overwrite to replace it"
+        )));
+    }
+
+    let pid = std::process::id();
+    let staging = dest.join(format!("{MEMORY_DIR}.tmp-{pid}"));
+    let _ = fs::remove_dir_all(&staging);
+    let staged = copy_tree(&source_memory, &staging, TRANSIENT_DIRS)
+        .and_then(|()| rewrite_cloned_manifest(&staging, workspace_id(source).ok()));
+    if let Err(e) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    swap_into_place(&staging, dest)
+}
+
+/// Replace `workspace/.neomemory` with a fully built `staging` directory next to it.
+/// If the swap fails the previous `.neomemory` is put back, and `staging` is removed
+/// either way.
+pub fn swap_into_place(staging: &Path, workspace: &Path) -> Result<(), NeoError> {
+    let memory = memory_dir(workspace);
+    let previous = workspace.join(format!("{MEMORY_DIR}.old-{}", std::process::id()));
+    let replacing = memory.exists();
+    if replacing {
+        fs::rename(&memory, &previous).map_err(|e| {
+            let _ = fs::remove_dir_all(staging);
+            NeoError::Io(format!("Failed to replace {MEMORY_DIR}: {e}"))
+        })?;
+    }
+    if let Err(e) = fs::rename(staging, &memory) {
+        if replacing {
+            let _ = fs::rename(&previous, &memory);
+        }
+        let _ = fs::remove_dir_all(staging);
+        return Err(NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")));
+    }
+    if replacing {
+        let _ = fs::remove_dir_all(&previous);
+    }
+    Ok(())
+}
+
+/// Seed a workspace's `.neomemory/` from another granted workspace, such as the
+/// project it was forked from.
+///
+/// Logs and caches aren't copied, and the copy gets its own workspace ID with
+/// `clonedFrom` pointing at the source. An existing `.neomemory/` in the destination
+/// is only replaced with `overwrite`. The copy runs on a blocking thread.
+#[tauri::command]
+pub async fn clone_workspace_memory(
+    app: tauri::AppHandle,
+    source_workspace: String,
+    dest_workspace: String,
+    overwrite: Option<bool>,
+) -> Result<WorkspaceInfo, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants = app.state::<WorkspaceGrants>();
+        let source = grants.require(&source_workspace)?;
+        let dest = grants.require(&dest_workspace)?;
+        if source == dest {
+            return Err(NeoError::Failed(
+                "Source and destination are the same workspace".to_string(),
+            ));
+        }
+
+        clone_memory(&source, &dest, overwrite.unwrap_or(false))?;
+        let info = init(&dest)?;
+        workspaces::set_workspace_id(&app, &dest, &info.manifest.workspace_id)?;
+        Ok(info)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to clone workspace memory: {e}")))?
+}
+
+/// Change fields of a workspace's manifest, keeping the ones this version doesn't know.
+pub fn update_manifest(
+    workspace: &Path,
+    update: impl FnOnce(&mut Map<String, Value>),
+) -> Result<(), NeoError> {
+    let path = memory_dir(workspace).join(MANIFEST_FILE);
+    let content = fs::read_to_string(&path)
+        .map_err(|e| NeoError::Io(format!("Failed to read {MEMORY_DIR}/{MANIFEST_FILE}: {e}")))?;
+    let mut raw: Map<String, Value> = serde_json::from_str(&content).map_err(|e| {
+        NeoError::CorruptManifest(format!(
+            "{CORRUPT_MANIFEST_ERROR}: {MEMORY_DIR}/{MANIFEST_FILE}: {e}"
+        ))
+    })?;
+    update(&mut raw);
+    let updated = serde_json::to_string_pretty(&raw)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize {MANIFEST_FILE}: {e}")))?;
+    write_atomic(&path, updated.as_bytes())
+}
+
+/// Read the stable workspace ID from a workspace's manifest.
+pub fn workspace_id(workspace: &Path) -> Result<String, NeoError> {
+    let content = fs::read_to_string(memory_dir(workspace).join(MANIFEST_FILE))
+        .map_err(|e| NeoError::Io(format!("Failed to read {MEMORY_DIR}/{MANIFEST_FILE}: {e}")))?;
+    let manifest = serde_json::from_str::<Manifest>(&content).map_err(|e| {
+        NeoError::CorruptManifest(format!(
+            "{CORRUPT_MANIFEST_ERROR}: {MEMORY_DIR}/{MANIFEST_FILE}: {e}"
+        ))
+    })?;
+    if manifest.workspace_id.is_empty() {
+        return Err(NeoError::Failed(
+            "Workspace manifest has no workspace ID".to_string(),
+        ));
+    }
+    Ok(manifest.workspace_id)
+}
+
+/// Create `.neomemory/` inside an already granted workspace. Idempotent; see `init`.
+#[tauri::command]
+pub fn init_neomemory(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+) -> Result<(), NeoError> {
+    let workspace = grants.require(&workspace)?;
+    let info = init(&workspace)?;
+    workspaces::set_workspace_id(&app, &workspace, &info.manifest.workspace_id)
+}
+
+/// The workspace's model provider settings, stored alongside `version` in
+/// `.neomemory/config.json`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfig {
+    pub active_provider: String,
+    /// Model to use per provider, keyed by provider name.
+    #[serde(default)]
+    pub model_overrides: HashMap<String, String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        ProviderConfig {
+            active_provider: providers::PROVIDERS[0].0.to_string(),
+            model_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Read `config.json` as a JSON object, treating a missing or malformed file as empty.
+pub fn read_config(workspace: &Path) -> Map<String, Value> {
+    fs::read_to_string(memory_dir(workspace).join(CONFIG_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|value| match value {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// The provider config saved in `config.json`, if there is a valid one.
+pub fn load_provider_config(workspace: &Path) -> Option<ProviderConfig> {
+    serde_json::from_value(Value::Object(read_config(workspace))).ok()
+}
+
+/// Check that every provider a config names is known.
+pub fn validate_provider_config(config: &ProviderConfig) -> Result<(), NeoError> {
+    providers::require(&config.active_provider)?;
+    for provider in config.model_overrides.keys() {
+        providers::require(provider)?;
+    }
+    Ok(())
+}
+
+/// Replace `config.json` with `config`, adding `version` if it is missing. The file
+/// is replaced atomically, like memory files.
+pub fn write_config(workspace: &Path, config: &Map<String, Value>) -> Result<(), NeoError> {
+    let mut stored = config.clone();
+    stored
+        .entry("version")
+        .or_insert_with(|| Value::from(CONFIG_VERSION));
+    let memory = memory_dir(workspace);
+    fs::create_dir_all(&memory)
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")))?;
+    let content = serde_json::to_string_pretty(&stored)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize config: {e}")))?;
+    write_atomic(&memory.join(CONFIG_FILE), content.as_bytes())
+}
+
+/// Write a provider config into `config.json`, keeping any other keys in the file.
+pub fn save_provider_config(workspace: &Path, config: &ProviderConfig) -> Result<(), NeoError> {
+    let mut stored = read_config(workspace);
+    let fields = serde_json::to_value(config)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize config: {e}")))?;
+    if let Value::Object(fields) = fields {
+        stored.extend(fields);
+    }
+    write_config(workspace, &stored)
+}
+
+/// Get the workspace's provider config, or the default if it was never saved or the
+/// file is unreadable.
+#[tauri::command]
+pub fn get_provider_config(
+    grants: State<'_, WorkspaceGrants>,
+    workspace_path: String,
+) -> Result<ProviderConfig, NeoError> {
+    let workspace = grants.require(&workspace_path)?;
+    Ok(load_provider_config(&workspace).unwrap_or_default())
+}
+
+/// Save the workspace's provider config, keeping any other keys in `config.json`.
+#[tauri::command]
+pub fn set_provider_config(
+    grants: State<'_, WorkspaceGrants>,
+    workspace_path: String,
+    config: ProviderConfig,
+) -> Result<(), NeoError> {
+    let workspace = grants.require(&workspace_path)?;
+    validate_provider_config(&config)?;
+    save_provider_config(&workspace, &config)
+}
+
+/// Largest `memory.json` that `read_memory` and `write_memory` accept unless the
+/// caller raises the limit.
+const DEFAULT_MAX_MEMORY_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The contents of `memory.json`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryFile {
+    pub json: String,
+    /// Whether `memory.json` couldn't be read and this came from `memory.json.bak`.
+    pub recovered: bool,
+}
+
+/// Read a memory file, checking its size and that it is valid JSON.
+fn read_memory_file(path: &Path, max_bytes: u64) -> Result<String, NeoError> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let metadata =
+        fs::metadata(path).map_err(|e| NeoError::Io(format!("Failed to read {name}: {e}")))?;
+    if metadata.len() > max_bytes {
+        return Err(NeoError::InvalidInput(format!(
+            "{name} is too large: {} bytes (limit {max_bytes})",
+            metadata.len()
+        )));
+    }
+    let json = encryption::read_to_string(path)?;
+    serde_json::from_str::<Value>(&json)
+        .map_err(|e| NeoError::Failed(format!("Corrupt {name}: {e}")))?;
+    Ok(json)
+}
+
+/// Read the workspace's `.neomemory/memory.json`.
+///
+/// If it is missing or corrupt, for example after a crash in the middle of a write
+/// by an older version, the backup kept by `write_memory` is returned instead, with
+/// `recovered` set. `max_bytes` defaults to 10 MB. The file is read on a blocking
+/// thread.
+#[tauri::command]
+pub async fn read_memory(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    max_bytes: Option<u64>,
+) -> Result<MemoryFile, NeoError> {
+    let memory = memory_dir(&grants.require(&workspace)?);
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_MEMORY_BYTES);
+    tauri::async_runtime::spawn_blocking(move || read_memory_or_backup(&memory, max_bytes))
+        .await
+        .map_err(|e| NeoError::Io(format!("Failed to read memory: {e}")))?
+}
+
+fn read_memory_or_backup(memory: &Path, max_bytes: u64) -> Result<MemoryFile, NeoError> {
+    match read_memory_file(&memory.join(MEMORY_FILE), max_bytes) {
+        Ok(json) => Ok(MemoryFile {
+            json,
+            recovered: false,
+        }),
+        Err(error) => match read_memory_file(&memory.join(MEMORY_BACKUP_FILE), max_bytes) {
+            Ok(json) => Ok(MemoryFile {
+                json,
+                recovered: true,
+            }),
+            Err(_) => Err(error),
+        },
+    }
+}
+
+/// Replace the workspace's `.neomemory/memory.json`.
+///
+/// The new content must be valid JSON of at most `max_bytes` (10 MB by default). It
+/// is written to a temp file, flushed to disk and renamed into place, and the
+/// previous version is kept as `memory.json.bak` as long as it was readable. Writes
+/// also schedule a snapshot into `.neomemory/backups/`; see `backups`.
+///
+/// Writes are checked against the workspace's memory quota, and return the files
+/// evicted to make room, if any. They take the workspace's write lock, so concurrent
+/// writes land one after the other, on a blocking thread.
+#[tauri::command]
+pub async fn write_memory(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    json: String,
+    max_bytes: Option<u64>,
+) -> Result<Vec<EvictedFile>, NeoError> {
+    let root = grants.require(&workspace)?;
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_MEMORY_BYTES);
+    if json.len() as u64 > max_bytes {
+        return Err(NeoError::InvalidInput(format!(
+            "Memory is too large: {} bytes (limit {max_bytes})",
+            json.len()
+        )));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        serde_json::from_str::<Value>(&json)
+            .map_err(|e| NeoError::InvalidInput(format!("Invalid memory JSON: {e}")))?;
+        lock::with_write_lock(&root, || replace_memory(&root, &json))?
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to write memory: {e}")))?
+}
+
+fn replace_memory(root: &Path, json: &str) -> Result<Vec<EvictedFile>, NeoError> {
+    let memory = memory_dir(root);
+    fs::create_dir_all(&memory)
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}: {e}")))?;
+    let path = memory.join(MEMORY_FILE);
+    let evicted = quota::make_room(root, &path, json.len() as u64)?;
+    // Never replace a good backup with a file that is already corrupt.
+    if let Ok(previous) = read_memory_file(&path, u64::MAX) {
+        encryption::write(root, &memory.join(MEMORY_BACKUP_FILE), previous.as_bytes())?;
+    }
+    encryption::write(root, &path, json.as_bytes())?;
+    search::refresh(root, &path);
+    backups::schedule(root);
+    Ok(evicted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neo-memory-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_files_without_leaving_temp_files() {
+        let dir = dir("atomic");
+        let path = dir.join("memory.json");
+        write_atomic(&path, b"{}").unwrap();
+        write_atomic(&path, b"[]").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"[]");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn private_files_are_only_readable_by_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = dir("private");
+        let path = dir.join("keys");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_atomic_private(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+//! Versioned upgrades of the `.neomemory/` layout. Each migration is a Rust function
+//! that rewrites the workspace's JSON files from one schema version to the next, and
+//! `migrate` runs the ones a workspace is missing, in order.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::Manager;
+
+use crate::conversations;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::lock::{self, WorkspaceLocks};
+use crate::memory::{self, BACKUPS_DIR, MANIFEST_FILE, MEMORIES_DIR, MEMORY_DIR};
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Schema version of the `.neomemory/` files this version of Neo writes.
+pub const SCHEMA_VERSION: u32 = 2;
+/// Schema version of manifests written before versioning existed.
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+struct Migration {
+    /// Schema version the workspace is at once this migration has run.
+    to: u32,
+    name: &'static str,
+    apply: fn(&Path) -> Result<(), NeoError>,
+}
+
+/// Every migration, ordered by `to`, starting at `LEGACY_SCHEMA_VERSION + 1` and
+/// ending at `SCHEMA_VERSION`.
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    name: "backfill-memory-fields",
+    apply: backfill_memory_fields,
+}];
+
+/// A migration recorded in the manifest's `appliedMigrations`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    /// Unix timestamp in milliseconds.
+    pub applied_at: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Names of the migrations that ran, in order.
+    pub applied: Vec<String>,
+    /// Copy of `.neomemory/` from before the migration, if one was needed.
+    pub backup_path: Option<String>,
+}
+
+/// Schema version recorded in a workspace's manifest.
+pub fn stored_version(workspace: &Path) -> Result<u32, NeoError> {
+    let content = fs::read_to_string(memory::memory_dir(workspace).join(MANIFEST_FILE))
+        .map_err(|e| NeoError::Io(format!("Failed to read {MEMORY_DIR}/{MANIFEST_FILE}: {e}")))?;
+    let manifest: Value = serde_json::from_str(&content).map_err(|e| {
+        NeoError::CorruptManifest(format!(
+            "Corrupt manifest: {MEMORY_DIR}/{MANIFEST_FILE}: {e}"
+        ))
+    })?;
+    Ok(manifest["schemaVersion"]
+        .as_u64()
+        .map_or(LEGACY_SCHEMA_VERSION, |v| v as u32))
+}
+
+/// Refuse a workspace written by a newer version of Neo, whose files this version
+/// could misread or clobber.
+pub fn check_supported(version: u32) -> Result<(), NeoError> {
+    if version > SCHEMA_VERSION {
+        return Err(NeoError::Failed(format!(
+            "Workspace memory uses schema version {version}, but this version of Neo only \
+             supports up to {SCHEMA_VERSION}; update Neo to open it"
+        )));
+    }
+    Ok(())
+}
+
+/// Copy `.neomemory/` to `.neomemory/backups/pre-migration-v{from}-{timestamp}/`,
+/// leaving out logs, caches and earlier backups.
+fn back_up(workspace: &Path, from: u32) -> Result<PathBuf, NeoError> {
+    let memory = memory::memory_dir(workspace);
+    let backup = memory.join(BACKUPS_DIR).join(format!(
+        "pre-migration-v{from}-{}",
+        workspaces::now_millis()
+    ));
+    let copied = memory::copy_tree(&memory, &backup, memory::TRANSIENT_DIRS);
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&backup);
+        return Err(NeoError::Io(format!(
+            "Failed to back up {MEMORY_DIR} before migrating: {e}"
+        )));
+    }
+    Ok(backup)
+}
+
+/// Bring a workspace's files up to `SCHEMA_VERSION`, backing up `.neomemory/` first.
+///
+/// Each step is recorded in the manifest as soon as it succeeds, so a failure part
+/// way through resumes from the failed step next time. Callers must hold the
+/// workspace's lock.
+pub fn migrate(workspace: &Path) -> Result<MigrationReport, NeoError> {
+    let from = stored_version(workspace)?;
+    check_supported(from)?;
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.to > from).collect();
+    let mut report = MigrationReport {
+        from_version: from,
+        to_version: from,
+        applied: Vec::new(),
+        backup_path: None,
+    };
+    if pending.is_empty() {
+        return Ok(report);
+    }
+
+    let backup = back_up(workspace, from)?;
+    report.backup_path = Some(backup.to_string_lossy().into_owned());
+    for migration in pending {
+        (migration.apply)(workspace)
+            .map_err(|e| NeoError::Failed(format!("Migration {} failed: {e}", migration.name)))?;
+        let applied = AppliedMigration {
+            version: migration.to,
+            name: migration.name.to_string(),
+            applied_at: workspaces::now_millis(),
+        };
+        let applied = serde_json::to_value(applied)
+            .map_err(|e| NeoError::Failed(format!("Failed to serialize migration record: {e}")))?;
+        memory::update_manifest(workspace, |raw| {
+            raw.insert("schemaVersion".to_string(), Value::from(migration.to));
+            let list = raw
+                .entry("appliedMigrations")
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if let Some(list) = list.as_array_mut() {
+                list.push(applied);
+            }
+        })?;
+        report.to_version = migration.to;
+        report.applied.push(migration.name.to_string());
+    }
+    Ok(report)
+}
+
+/// Upgrade a granted workspace's memory files to the schema this version of Neo
+/// writes. Workspaces that are already current are left alone.
+#[tauri::command]
+pub async fn migrate_workspace(
+    app: tauri::AppHandle,
+    workspace: String,
+) -> Result<MigrationReport, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace)?;
+        if !memory::memory_dir(&root).is_dir() {
+            return Err(NeoError::Failed(format!("Workspace has no {MEMORY_DIR}")));
+        }
+        app.state::<WorkspaceLocks>()
+            .while_held(&root, || lock::with_write_lock(&root, || migrate(&root)))??
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to migrate workspace: {e}")))?
+}
+
+/// v1 → v2: memory entries under `memories/` must have `id`, `tags`, `createdAt` and
+/// `updatedAt`. Older frontends left some of them out, which made those entries
+/// unreadable; the id comes from the file name and the timestamps from its mtime.
+fn backfill_memory_fields(workspace: &Path) -> Result<(), NeoError> {
+    let dir = memory::memory_dir(workspace).join(MEMORIES_DIR);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let content = encryption::read_to_string(&path)?;
+        // Files that aren't JSON objects weren't readable before either; leave them.
+        let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(&content) else {
+            continue;
+        };
+        if !backfill(&mut object, &stem, conversations::modified_millis(&path)) {
+            continue;
+        }
+        let updated = serde_json::to_string_pretty(&object).map_err(|e| {
+            NeoError::Failed(format!("Failed to serialize {}: {e}", path.display()))
+        })?;
+        encryption::write(workspace, &path, updated.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Fill in the missing fields of one memory entry. Returns whether anything changed.
+fn backfill(object: &mut Map<String, Value>, id: &str, modified: u64) -> bool {
+    let mut changed = false;
+    let mut ensure = |key: &str, value: Value| {
+        if !object.contains_key(key) {
+            object.insert(key.to_string(), value);
+            changed = true;
+        }
+    };
+    ensure("id", Value::from(id));
+    ensure("content", Value::from(""));
+    ensure("tags", Value::Array(Vec::new()));
+    ensure("createdAt", Value::from(modified));
+    ensure("updatedAt", Value::from(modified));
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memories::Memory;
+
+    const MODIFIED: u64 = 1_700_000_000_000;
+
+    fn backfilled(fixture: &str, id: &str) -> (bool, Map<String, Value>) {
+        let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(fixture) else {
+            panic!("fixture is not a JSON object: {fixture}");
+        };
+        let changed = backfill(&mut object, id, MODIFIED);
+        (changed, object)
+    }
+
+    #[test]
+    fn backfills_v1_entries_into_readable_memories() {
+        for fixture in [
+            r#"{"content": "uses tabs"}"#,
+            r#"{"content": "uses tabs", "tags": ["style"]}"#,
+            r#"{"id": "prefs", "content": "uses tabs", "createdAt": 5}"#,
+        ] {
+            let (changed, object) = backfilled(fixture, "prefs");
+            assert!(changed);
+            let memory: Memory = serde_json::from_value(Value::Object(object)).unwrap();
+            assert_eq!(memory.id, "prefs");
+            assert_eq!(memory.content, "uses tabs");
+            assert_eq!(memory.updated_at, MODIFIED);
+        }
+    }
+
+    #[test]
+    fn keeps_fields_that_are_already_there() {
+        let fixture = r#"{"id": "other", "content": "x", "tags": ["a"], "createdAt": 1, "updatedAt": 2, "extra": true}"#;
+        let (changed, object) = backfilled(fixture, "file-stem");
+        assert!(!changed);
+        assert_eq!(
+            Value::Object(object),
+            serde_json::from_str::<Value>(fixture).unwrap()
+        );
+    }
+
+    #[test]
+    fn migrations_run_in_order_up_to_the_current_schema() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.to).collect();
+        let expected: Vec<u32> = (LEGACY_SCHEMA_VERSION + 1..=SCHEMA_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn refuses_newer_schemas() {
+        assert!(check_supported(LEGACY_SCHEMA_VERSION).is_ok());
+        assert!(check_supported(SCHEMA_VERSION).is_ok());
+        assert!(check_supported(SCHEMA_VERSION + 1).is_err());
+    }
+}
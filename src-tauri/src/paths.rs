@@ -0,0 +1,271 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::NeoError;
+
+/// Normalize a path for prefix comparison.
+///
+/// On Windows, `canonicalize` returns verbatim paths (`\\?\C:\...`, `\\?\UNC\...`)
+/// that never `starts_with` their plain form, and NTFS compares names
+/// case-insensitively, so the prefix is stripped and the path lowercased.
+#[cfg(windows)]
+fn comparable(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy();
+    let path = match path.strip_prefix(r"\\?\UNC\") {
+        Some(rest) => format!(r"\\{rest}"),
+        None => path.strip_prefix(r"\\?\").unwrap_or(&path).to_string(),
+    };
+    PathBuf::from(path.to_lowercase())
+}
+
+#[cfg(not(windows))]
+fn comparable(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Whether `path` is `root` or lies beneath it.
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    comparable(path).starts_with(comparable(root))
+}
+
+/// Lexically resolve `.` and `..` without touching the filesystem, so a path like
+/// `~/../../etc` can't pass a prefix check.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// The first symlink between an allowed root and `path` whose target lies outside
+/// every root, if any.
+///
+/// Checking each component rather than only the final target keeps the grant from
+/// depending on a link outside the roots that could be swapped after it was checked.
+/// Paths that aren't under a root are not checked.
+pub fn escaping_symlink(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let path = normalize(path);
+    let (root, relative) = roots
+        .iter()
+        .find_map(|root| Some((root, path.strip_prefix(root).ok()?)))?;
+    let inside_roots = |target: &Path| {
+        roots.iter().any(|root| {
+            let canonical = root.canonicalize().unwrap_or_else(|_| root.clone());
+            is_within(target, root) || is_within(target, &canonical)
+        })
+    };
+
+    let mut current = root.clone();
+    for component in relative.components() {
+        current.push(component);
+        let is_link = current
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink());
+        if !is_link {
+            continue;
+        }
+        let inside = current
+            .canonicalize()
+            .is_ok_and(|target| inside_roots(&target));
+        if !inside {
+            return Some(current);
+        }
+    }
+    None
+}
+
+/// Resolve `relative` inside `workspace` and verify the final target is still under
+/// the workspace root once every symlink is followed.
+///
+/// Paths that don't exist yet (e.g. a file about to be written) are resolved through
+/// their deepest existing ancestor. Broken symlinks and symlink cycles are errors.
+pub fn resolve_in_workspace(workspace: &Path, relative: &Path) -> Result<PathBuf, NeoError> {
+    let root = workspace
+        .canonicalize()
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid workspace: {e}")))?;
+    let joined = root.join(relative);
+
+    let mut existing = joined.as_path();
+    let mut missing = Vec::new();
+    // `symlink_metadata` so a dangling symlink counts as existing and fails below
+    // instead of being written through.
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => {
+                return Err(NeoError::InvalidInput(format!(
+                    "Invalid path: {}",
+                    relative.display()
+                )))
+            }
+        }
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|e| NeoError::Failed(format!("Failed to resolve {}: {e}", relative.display())))?;
+    resolved.extend(missing.iter().rev());
+
+    if !is_within(&resolved, &root) {
+        return Err(NeoError::PermissionDenied(format!(
+            "Path escapes the workspace: {}",
+            relative.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Resolve the user's home directory on every platform.
+///
+/// On Windows `USERPROFILE` is checked first. Otherwise `dirs::home_dir()` reads `HOME`
+/// on Unix (falling back to the passwd entry) and the profile known folder on Windows.
+/// The result is canonicalized so it has the same shape as canonicalized workspace paths.
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let home = std::env::var_os("USERPROFILE")
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::home_dir)?;
+    #[cfg(not(windows))]
+    let home = dirs::home_dir()?;
+
+    Some(home.canonicalize().unwrap_or(home))
+}
+
+/// System locations that can never be granted or made an allowed root, even with
+/// `allow_external`.
+/// A folder is rejected if it is one of these or lives beneath one.
+#[cfg(not(windows))]
+const SYSTEM_PATHS: &[&str] = &[
+    "/bin",
+    "/boot",
+    "/dev",
+    "/etc",
+    "/lib",
+    "/Library",
+    "/private/etc",
+    "/private/var",
+    "/proc",
+    "/sbin",
+    "/sys",
+    "/System",
+    "/usr",
+    "/var",
+];
+#[cfg(windows)]
+const SYSTEM_PATHS: &[&str] = &[
+    "C:\\Windows",
+    "C:\\Program Files",
+    "C:\\Program Files (x86)",
+    "C:\\ProgramData",
+];
+
+/// Whether a canonical path is a filesystem root or a system location.
+pub fn is_system_path(canonical: &Path) -> bool {
+    if canonical.parent().is_none() {
+        return true;
+    }
+    SYSTEM_PATHS.iter().any(|sys| {
+        let sys = Path::new(sys);
+        let sys = sys.canonicalize().unwrap_or_else(|_| sys.to_path_buf());
+        is_within(canonical, &sys)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh directory holding a `workspace/` and an `outside/` folder, canonicalized.
+    fn sandbox(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("neo-paths-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("workspace/src")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        (dir.clone(), dir.join("workspace"), dir.join("outside"))
+    }
+
+    #[test]
+    fn resolves_paths_that_dont_exist_yet() {
+        let (dir, workspace, _) = sandbox("missing");
+        let resolved = resolve_in_workspace(&workspace, Path::new("src/new/file.rs")).unwrap();
+        assert_eq!(resolved, workspace.join("src/new/file.rs"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn resolves_a_canonical_home_outside_system_paths() {
+        let home = home_dir().expect("the test environment has a home directory");
+        assert_eq!(home.canonicalize().unwrap(), home);
+        assert!(!is_system_path(&home));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_roots_and_system_locations() {
+        for path in ["/", "/etc", "/usr/local/bin", "/var/tmp"] {
+            let path = Path::new(path);
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            assert!(is_system_path(&path), "{}", path.display());
+        }
+        assert!(!is_system_path(Path::new("/etcetera")));
+    }
+
+    #[test]
+    fn rejects_parent_dir_escapes() {
+        let (dir, workspace, _) = sandbox("parent");
+        assert!(resolve_in_workspace(&workspace, Path::new("../outside/file")).is_err());
+        assert!(resolve_in_workspace(&workspace, Path::new("src/new/../../../x")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_only_within_the_workspace() {
+        use std::os::unix::fs::symlink;
+        let (dir, workspace, outside) = sandbox("symlinks");
+        symlink(workspace.join("src"), workspace.join("inner")).unwrap();
+        symlink(&outside, workspace.join("escape")).unwrap();
+        symlink(dir.join("gone"), workspace.join("dangling")).unwrap();
+        symlink(workspace.join("loop"), workspace.join("loop")).unwrap();
+
+        let inner = resolve_in_workspace(&workspace, Path::new("inner/main.rs")).unwrap();
+        assert_eq!(inner, workspace.join("src/main.rs"));
+        assert!(resolve_in_workspace(&workspace, Path::new("escape/file")).is_err());
+        assert!(resolve_in_workspace(&workspace, Path::new("dangling")).is_err());
+        assert!(resolve_in_workspace(&workspace, Path::new("dangling/file")).is_err());
+        assert!(resolve_in_workspace(&workspace, Path::new("loop/file")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_symlinks_leading_out_of_the_roots() {
+        use std::os::unix::fs::symlink;
+        let (dir, workspace, outside) = sandbox("escaping");
+        symlink(&outside, workspace.join("escape")).unwrap();
+        symlink(workspace.join("src"), workspace.join("inner")).unwrap();
+        let roots = [workspace.clone()];
+
+        assert_eq!(
+            escaping_symlink(&workspace.join("escape/deeper"), &roots),
+            Some(workspace.join("escape"))
+        );
+        assert_eq!(escaping_symlink(&workspace.join("inner/x"), &roots), None);
+        assert_eq!(escaping_symlink(&outside, &roots), None);
+        let wider = std::slice::from_ref(&dir);
+        assert_eq!(escaping_symlink(&workspace.join("escape"), wider), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
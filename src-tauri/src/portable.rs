@@ -0,0 +1,765 @@
+//! Portable exports of a workspace's `.neomemory/`, for sharing what Neo has learned
+//! with people and tools that don't use Neo, and imports that merge a JSON export
+//! back into a workspace.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::conversations;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::files;
+use crate::lock::{self, WorkspaceLocks};
+use crate::memories::{self, Memory};
+use crate::memory::{
+    self, CONVERSATIONS_DIR, MANIFEST_FILE, MEMORIES_DIR, MEMORY_DIR, MEMORY_FILE,
+};
+use crate::quota::{self, EvictedFile};
+use crate::search;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Current version of the JSON export schema.
+pub const EXPORT_VERSION: u32 = 1;
+/// Bytes sniffed to decide whether a linked file is binary.
+const SNIFF_BYTES: usize = 8192;
+/// Largest export `import_memory` will read.
+const MAX_IMPORT_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub path: String,
+    pub entries: usize,
+    pub conversations: usize,
+    pub attachments: usize,
+}
+
+/// A binary file linked from a memory entry, copied next to the export.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// The link target as written in the entry.
+    pub reference: String,
+    /// Where the copy is, relative to the export file.
+    pub path: String,
+}
+
+/// Copies linked binary files into `<export name>-attachments/` beside the export.
+struct Attachments<'a> {
+    app: &'a tauri::AppHandle,
+    grants: &'a WorkspaceGrants,
+    root: &'a Path,
+    /// The attachments folder's name, which is also its path relative to the export.
+    dir_name: String,
+    dir: PathBuf,
+    copied: HashMap<PathBuf, String>,
+    list: Vec<Attachment>,
+}
+
+impl Attachments<'_> {
+    /// The relative link to use for `target`, copying the file on first sight.
+    /// `None` for links that aren't binary files inside the workspace.
+    fn link(&mut self, target: &str) -> Result<Option<String>, NeoError> {
+        if target.is_empty() || target.starts_with('#') || target.contains(':') {
+            return Ok(None);
+        }
+        let root = self.root.to_string_lossy();
+        let Ok(source) = files::resolve(self.app, self.grants, &root, target) else {
+            return Ok(None);
+        };
+        if let Some(link) = self.copied.get(&source) {
+            return Ok(Some(link.clone()));
+        }
+        if !source.is_file() || !is_binary(&source) {
+            return Ok(None);
+        }
+
+        let file_name = source.file_name().unwrap_or_default().to_string_lossy();
+        let mut name = file_name.to_string();
+        let mut n = 1;
+        // Files from an earlier export to the same place are overwritten.
+        while self
+            .copied
+            .values()
+            .any(|link| link.ends_with(&format!("/{name}")))
+        {
+            n += 1;
+            name = format!("{n}-{file_name}");
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| NeoError::Io(format!("Failed to create {}: {e}", self.dir.display())))?;
+        fs::copy(&source, self.dir.join(&name))
+            .map_err(|e| NeoError::Io(format!("Failed to copy attachment {target}: {e}")))?;
+
+        let link = format!("{}/{name}", self.dir_name);
+        self.copied.insert(source, link.clone());
+        self.list.push(Attachment {
+            reference: target.to_string(),
+            path: link.clone(),
+        });
+        Ok(Some(link))
+    }
+
+    /// Copy every file linked from `content` and return it with the links rewritten.
+    fn rewrite(&mut self, content: &str) -> Result<String, NeoError> {
+        let mut out = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find("](") {
+            let (before, after) = rest.split_at(start + 2);
+            out.push_str(before);
+            let Some(end) = after.find(')') else {
+                rest = after;
+                break;
+            };
+            let target = &after[..end];
+            match self.link(target.trim().trim_matches(['<', '>']))? {
+                Some(link) => out.push_str(&link),
+                None => out.push_str(target),
+            }
+            rest = &after[end..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+fn is_binary(path: &Path) -> bool {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    let read =
+        File::open(path).and_then(|file| file.take(SNIFF_BYTES as u64).read_to_end(&mut head));
+    read.is_ok() && (head.contains(&0) || std::str::from_utf8(&head).is_err())
+}
+
+fn sorted_json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|path| path.is_file() && conversations::is_conversation_file(path))
+        .collect();
+    files.sort();
+    files
+}
+
+fn memory_entries(memory: &Path) -> impl Iterator<Item = Memory> {
+    sorted_json_files(&memory.join(MEMORIES_DIR))
+        .into_iter()
+        .filter_map(|path| serde_json::from_str(&encryption::read_to_string(&path).ok()?).ok())
+}
+
+/// Conversation files that are JSON objects, with the file name as `id` where they
+/// have none, like the conversation index.
+fn conversation_values(memory: &Path) -> impl Iterator<Item = Value> {
+    sorted_json_files(&memory.join(CONVERSATIONS_DIR))
+        .into_iter()
+        .filter_map(|path| {
+            let mut conversation: Value =
+                serde_json::from_str(&encryption::read_to_string(&path).ok()?).ok()?;
+            let object = conversation.as_object_mut()?;
+            if !object.contains_key("id") {
+                let stem = path.file_stem()?.to_string_lossy().into_owned();
+                object.insert("id".to_string(), Value::from(stem));
+            }
+            Some(conversation)
+        })
+}
+
+fn read_value(path: &Path) -> Value {
+    encryption::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn json_error(e: serde_json::Error) -> NeoError {
+    NeoError::Io(format!("Failed to write export: {e}"))
+}
+
+/// Write the export as one JSON object, one entry and conversation at a time.
+fn write_json(
+    out: &mut impl Write,
+    root: &Path,
+    attachments: &mut Attachments,
+    summary: &mut ExportSummary,
+) -> Result<(), NeoError> {
+    let memory = memory::memory_dir(root);
+    let io = |e: std::io::Error| NeoError::Io(format!("Failed to write export: {e}"));
+
+    write!(
+        out,
+        "{{\"version\":{EXPORT_VERSION},\"exportedAt\":{},\"manifest\":",
+        workspaces::now_millis()
+    )
+    .map_err(io)?;
+    serde_json::to_writer(&mut *out, &read_value(&memory.join(MANIFEST_FILE)))
+        .map_err(json_error)?;
+    out.write_all(b",\"memory\":").map_err(io)?;
+    serde_json::to_writer(&mut *out, &read_value(&memory.join(MEMORY_FILE))).map_err(json_error)?;
+
+    out.write_all(b",\"entries\":[").map_err(io)?;
+    for entry in memory_entries(&memory) {
+        attachments.rewrite(&entry.content)?;
+        if summary.entries > 0 {
+            out.write_all(b",").map_err(io)?;
+        }
+        serde_json::to_writer(&mut *out, &entry).map_err(json_error)?;
+        summary.entries += 1;
+    }
+    out.write_all(b"],\"conversations\":[").map_err(io)?;
+    for conversation in conversation_values(&memory) {
+        if summary.conversations > 0 {
+            out.write_all(b",").map_err(io)?;
+        }
+        serde_json::to_writer(&mut *out, &conversation).map_err(json_error)?;
+        summary.conversations += 1;
+    }
+    out.write_all(b"],\"attachments\":").map_err(io)?;
+    serde_json::to_writer(&mut *out, &attachments.list).map_err(json_error)?;
+    out.write_all(b"}").map_err(io)
+}
+
+/// Plain text of a message's content: a string, or the text parts of a multi-part
+/// message.
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Null => String::new(),
+        other => format!("```json\n{other}\n```"),
+    }
+}
+
+pub fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Write the export as one Markdown document: a summary of `memory.json`, then the
+/// memory entries, then one section per conversation.
+fn write_markdown(
+    out: &mut impl Write,
+    root: &Path,
+    attachments: &mut Attachments,
+    summary: &mut ExportSummary,
+) -> Result<(), NeoError> {
+    let memory = memory::memory_dir(root);
+    let io = |e: std::io::Error| NeoError::Io(format!("Failed to write export: {e}"));
+    let name = root.file_name().unwrap_or_default().to_string_lossy();
+
+    writeln!(out, "# Neo memory: {name}\n").map_err(io)?;
+    writeln!(out, "## Memory summary\n").map_err(io)?;
+    match read_value(&memory.join(MEMORY_FILE)) {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let text = match value {
+                    Value::String(text) => text,
+                    other => format!(
+                        "```json\n{}\n```",
+                        serde_json::to_string_pretty(&other).map_err(json_error)?
+                    ),
+                };
+                writeln!(out, "### {key}\n\n{text}\n").map_err(io)?;
+            }
+        }
+        _ => writeln!(out, "_Nothing recorded yet._\n").map_err(io)?,
+    }
+
+    let mut entries = memory_entries(&memory).peekable();
+    if entries.peek().is_some() {
+        writeln!(out, "## Memories\n").map_err(io)?;
+    }
+    for entry in entries {
+        let content = attachments.rewrite(&entry.content)?;
+        writeln!(out, "### {}\n\n{}\n", entry.id, content.trim_end()).map_err(io)?;
+        if !entry.tags.is_empty() {
+            writeln!(out, "_Tags: {}_\n", entry.tags.join(", ")).map_err(io)?;
+        }
+        summary.entries += 1;
+    }
+
+    let mut conversations = conversation_values(&memory).peekable();
+    if conversations.peek().is_some() {
+        writeln!(out, "## Conversations\n").map_err(io)?;
+    }
+    for conversation in conversations {
+        let title = conversation
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or("Untitled");
+        writeln!(out, "### {title}\n").map_err(io)?;
+        let messages = conversation.get("messages").and_then(Value::as_array);
+        for message in messages.into_iter().flatten() {
+            let role = message
+                .get("role")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            let text = message_text(message.get("content").unwrap_or(&Value::Null));
+            writeln!(out, "**{}:**\n\n{}\n", capitalize(role), text.trim_end()).map_err(io)?;
+        }
+        summary.conversations += 1;
+    }
+    Ok(())
+}
+
+/// Resolve an export or import file, which must be inside a granted folder.
+fn granted_file(
+    app: &tauri::AppHandle,
+    grants: &WorkspaceGrants,
+    path: &str,
+) -> Result<PathBuf, NeoError> {
+    let path = Path::new(path);
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err(NeoError::InvalidInput(format!(
+            "Invalid path: {}",
+            path.display()
+        )));
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {}: {e}", path.display())))?;
+    let Some(root) = grants.covering(&parent) else {
+        return Err(NeoError::PermissionDenied(format!(
+            "Workspace has not been granted: {}",
+            parent.display()
+        )));
+    };
+    let relative = parent
+        .strip_prefix(&root)
+        .unwrap_or(Path::new(""))
+        .join(file_name);
+    let resolved = files::resolve(
+        app,
+        grants,
+        &root.to_string_lossy(),
+        &relative.to_string_lossy(),
+    )?;
+    if resolved.is_dir() {
+        return Err(NeoError::InvalidInput(format!(
+            "Invalid path: {} is a folder",
+            path.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+fn export(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    format: ExportFormat,
+    dest_path: &str,
+) -> Result<ExportSummary, NeoError> {
+    let grants = app.state::<WorkspaceGrants>();
+    let root = grants.require(workspace)?;
+    if !memory::memory_dir(&root).is_dir() {
+        return Err(NeoError::Failed(format!("Workspace has no {MEMORY_DIR}")));
+    }
+    let dest = granted_file(app, &grants, dest_path)?;
+    let stem = dest.file_stem().unwrap_or_default().to_string_lossy();
+    let dir_name = format!("{stem}-attachments");
+    let mut attachments = Attachments {
+        app,
+        grants: &grants,
+        root: &root,
+        dir: dest.with_file_name(&dir_name),
+        dir_name,
+        copied: HashMap::new(),
+        list: Vec::new(),
+    };
+    let mut summary = ExportSummary {
+        path: dest.to_string_lossy().into_owned(),
+        entries: 0,
+        conversations: 0,
+        attachments: 0,
+    };
+
+    // Streamed into a temp file, so a failed export never leaves a partial one behind.
+    let file_name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dest.with_file_name(memory::temp_name(&file_name));
+    let written = File::create(&tmp)
+        .map_err(|e| NeoError::Io(format!("Failed to create {}: {e}", tmp.display())))
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            match format {
+                ExportFormat::Json => write_json(&mut out, &root, &mut attachments, &mut summary),
+                ExportFormat::Markdown => {
+                    write_markdown(&mut out, &root, &mut attachments, &mut summary)
+                }
+            }?;
+            let file = out
+                .into_inner()
+                .map_err(|e| NeoError::Io(format!("Failed to write export: {e}")))?;
+            file.sync_all()
+                .map_err(|e| NeoError::Io(format!("Failed to write export: {e}")))
+        })
+        .and_then(|()| {
+            fs::rename(&tmp, &dest)
+                .map_err(|e| NeoError::Io(format!("Failed to write {}: {e}", dest.display())))
+        });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    summary.attachments = attachments.list.len();
+    Ok(summary)
+}
+
+/// Export a workspace's memory to `destPath`, which must be inside a granted folder.
+///
+/// `json` writes one self-contained file with the manifest, `memory.json`, every
+/// memory entry and every conversation; `markdown` writes one readable document.
+/// Binary files linked from memory entries are copied into a
+/// `<name>-attachments/` folder beside the export and linked relatively. The export
+/// is streamed to disk, so its size doesn't matter.
+#[tauri::command]
+pub async fn export_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    format: ExportFormat,
+    dest_path: String,
+) -> Result<ExportSummary, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || export(&app, &workspace, format, &dest_path))
+        .await
+        .map_err(|e| NeoError::Failed(format!("Failed to export memory: {e}")))?
+}
+
+/// The parts of a JSON export that `import_memory` reads.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryExport {
+    version: u32,
+    #[serde(default)]
+    memory: Value,
+    #[serde(default)]
+    entries: Vec<Memory>,
+    #[serde(default)]
+    conversations: Vec<Value>,
+}
+
+/// What to do with an imported item whose ID the workspace already has.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    SkipExisting,
+    Overwrite,
+    /// Import it under a new ID. Keys of `memory.json` are kept as they are.
+    Duplicate,
+}
+
+/// Items of an import, as `memories/<id>`, `conversations/<id>` or `memory.json#<key>`.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub skipped: Vec<String>,
+    pub overwritten: Vec<String>,
+    /// Items imported under a new ID, as `(old, new)`.
+    pub renamed: Vec<(String, String)>,
+    /// Files evicted to keep the workspace within its memory quota.
+    pub evicted: Vec<EvictedFile>,
+}
+
+fn conversation_path(memory: &Path, id: &str) -> Option<PathBuf> {
+    memories::validate_id(id).ok()?;
+    Some(memory.join(CONVERSATIONS_DIR).join(format!("{id}.json")))
+}
+
+/// Write an imported value, within the workspace's memory quota like any other write.
+fn write_value(
+    root: &Path,
+    path: &Path,
+    value: &impl Serialize,
+    summary: &mut ImportSummary,
+) -> Result<(), NeoError> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize {}: {e}", path.display())))?;
+    let evicted = quota::make_room(root, path, content.len() as u64)?;
+    summary.evicted.extend(evicted);
+    encryption::write(root, path, content.as_bytes())?;
+    search::refresh(root, path);
+    Ok(())
+}
+
+fn import_memory_json(
+    root: &Path,
+    imported: Value,
+    strategy: MergeStrategy,
+    summary: &mut ImportSummary,
+) -> Result<(), NeoError> {
+    let Value::Object(imported) = imported else {
+        return Ok(());
+    };
+    let path = memory::memory_dir(root).join(MEMORY_FILE);
+    let mut current = match read_value(&path) {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    for (key, value) in imported {
+        let item = format!("{MEMORY_FILE}#{key}");
+        if !current.contains_key(&key) {
+            summary.added.push(item);
+        } else if strategy == MergeStrategy::Overwrite {
+            summary.overwritten.push(item);
+        } else {
+            summary.skipped.push(item);
+            continue;
+        }
+        current.insert(key, value);
+    }
+    write_value(root, &path, &current, summary)
+}
+
+fn import_entries(
+    root: &Path,
+    entries: Vec<Memory>,
+    strategy: MergeStrategy,
+    summary: &mut ImportSummary,
+) -> Result<(), NeoError> {
+    fs::create_dir_all(memory::memory_dir(root).join(MEMORIES_DIR))
+        .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}/{MEMORIES_DIR}: {e}")))?;
+    for mut entry in entries {
+        let item = format!("{MEMORIES_DIR}/{}", entry.id);
+        let Ok(mut path) = memories::safe_memory_path(root, &entry.id) else {
+            summary.skipped.push(item);
+            continue;
+        };
+        if !path.exists() {
+            summary.added.push(item);
+        } else {
+            match strategy {
+                MergeStrategy::SkipExisting => {
+                    summary.skipped.push(item);
+                    continue;
+                }
+                MergeStrategy::Overwrite => summary.overwritten.push(item),
+                MergeStrategy::Duplicate => {
+                    let base = entry.id.clone();
+                    let mut n = 1;
+                    while path.exists() {
+                        n += 1;
+                        entry.id = format!("{base}-{n}");
+                        path = memories::safe_memory_path(root, &entry.id).map_err(|_| {
+                            NeoError::InvalidInput(format!("Invalid memory id: {}", entry.id))
+                        })?;
+                    }
+                    summary
+                        .renamed
+                        .push((item, format!("{MEMORIES_DIR}/{}", entry.id)));
+                }
+            }
+        }
+        write_value(root, &path, &entry, summary)?;
+    }
+    Ok(())
+}
+
+fn import_conversations(
+    root: &Path,
+    conversations: Vec<Value>,
+    strategy: MergeStrategy,
+    summary: &mut ImportSummary,
+) -> Result<(), NeoError> {
+    let memory = memory::memory_dir(root);
+    fs::create_dir_all(memory.join(CONVERSATIONS_DIR)).map_err(|e| {
+        NeoError::Io(format!(
+            "Failed to create {MEMORY_DIR}/{CONVERSATIONS_DIR}: {e}"
+        ))
+    })?;
+    for mut conversation in conversations {
+        let id = conversation
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let item = format!("{CONVERSATIONS_DIR}/{id}");
+        let Some(mut path) = conversation_path(&memory, id) else {
+            summary.skipped.push(item);
+            continue;
+        };
+        if !path.exists() {
+            summary.added.push(item);
+        } else {
+            match strategy {
+                MergeStrategy::SkipExisting => {
+                    summary.skipped.push(item);
+                    continue;
+                }
+                MergeStrategy::Overwrite => summary.overwritten.push(item),
+                MergeStrategy::Duplicate => {
+                    let new_id = uuid::Uuid::new_v4().to_string();
+                    path = memory
+                        .join(CONVERSATIONS_DIR)
+                        .join(format!("{new_id}.json"));
+                    summary
+                        .renamed
+                        .push((item, format!("{CONVERSATIONS_DIR}/{new_id}")));
+                    conversation["id"] = Value::from(new_id);
+                }
+            }
+        }
+        write_value(root, &path, &conversation, summary)?;
+        conversations::record_write(root, &path)?;
+    }
+    Ok(())
+}
+
+fn import(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    source_path: &str,
+    strategy: MergeStrategy,
+) -> Result<ImportSummary, NeoError> {
+    let grants = app.state::<WorkspaceGrants>();
+    let root = grants.require(workspace)?;
+    let source = granted_file(app, &grants, source_path)?;
+    let size = fs::metadata(&source)
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", source.display())))?
+        .len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(NeoError::InvalidInput(format!(
+            "Export is too large: {size} bytes (limit {MAX_IMPORT_BYTES})"
+        )));
+    }
+    let file = File::open(&source)
+        .map_err(|e| NeoError::Io(format!("Failed to read {}: {e}", source.display())))?;
+    let export: MemoryExport = serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid memory export: {e}")))?;
+    if export.version == 0 || export.version > EXPORT_VERSION {
+        return Err(NeoError::InvalidInput(format!(
+            "Unsupported memory export version {} (expected at most {EXPORT_VERSION})",
+            export.version
+        )));
+    }
+    memory::init(&root)?;
+
+    let merge = || -> Result<ImportSummary, NeoError> {
+        let mut summary = ImportSummary::default();
+        import_memory_json(&root, export.memory, strategy, &mut summary)?;
+        import_entries(&root, export.entries, strategy, &mut summary)?;
+        import_conversations(&root, export.conversations, strategy, &mut summary)?;
+        Ok(summary)
+    };
+    app.state::<WorkspaceLocks>()
+        .while_held(&root, || lock::with_write_lock(&root, merge))??
+}
+
+/// Merge a JSON export made by `export_memory` into a workspace's memory.
+///
+/// Memory entries and conversations already in the workspace are kept, replaced or
+/// imported under a new ID according to `strategy` (`skip_existing`, `overwrite`
+/// or `duplicate`); top-level keys of `memory.json` are merged the same way, except
+/// that `duplicate` keeps existing keys. Exports above 100 MB or of a newer schema
+/// version are refused. The merge runs under the workspace lock, and the result
+/// lists what was added, skipped, overwritten and renamed.
+#[tauri::command]
+pub async fn import_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    source_path: String,
+    strategy: MergeStrategy,
+) -> Result<ImportSummary, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || import(&app, &workspace, &source_path, strategy))
+        .await
+        .map_err(|e| NeoError::Failed(format!("Failed to import memory: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("neo-portable-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(memory::memory_dir(&root)).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    fn entry(id: &str, content: &str) -> Memory {
+        Memory {
+            id: id.to_string(),
+            content: content.to_string(),
+            tags: Vec::new(),
+            created_at: 1,
+            updated_at: 1,
+        }
+    }
+
+    fn content(root: &Path, id: &str) -> String {
+        let path = memories::safe_memory_path(root, id).unwrap();
+        let value = read_value(&path);
+        value["content"].as_str().unwrap_or_default().to_string()
+    }
+
+    fn import_twice(name: &str, strategy: MergeStrategy) -> (PathBuf, ImportSummary) {
+        let root = workspace(name);
+        let mut first = ImportSummary::default();
+        import_entries(&root, vec![entry("a", "old")], strategy, &mut first).unwrap();
+        let mut summary = ImportSummary::default();
+        let entries = vec![entry("a", "new"), entry("b", "new")];
+        import_entries(&root, entries, strategy, &mut summary).unwrap();
+        (root, summary)
+    }
+
+    #[test]
+    fn skips_existing_entries() {
+        let (root, summary) = import_twice("skip", MergeStrategy::SkipExisting);
+        assert_eq!(summary.skipped, ["memories/a"]);
+        assert_eq!(summary.added, ["memories/b"]);
+        assert_eq!(content(&root, "a"), "old");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn overwrites_existing_entries() {
+        let (root, summary) = import_twice("overwrite", MergeStrategy::Overwrite);
+        assert_eq!(summary.overwritten, ["memories/a"]);
+        assert_eq!(content(&root, "a"), "new");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn duplicates_existing_entries_under_a_new_id() {
+        let (root, summary) = import_twice("duplicate", MergeStrategy::Duplicate);
+        assert_eq!(
+            summary.renamed,
+            [("memories/a".to_string(), "memories/a-2".to_string())]
+        );
+        assert_eq!(content(&root, "a"), "old");
+        assert_eq!(content(&root, "a-2"), "new");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn merges_memory_json_by_key() {
+        let root = workspace("memory-json");
+        let path = memory::memory_dir(&root).join(MEMORY_FILE);
+        fs::write(&path, r#"{"kept": 1, "shared": "old"}"#).unwrap();
+        let mut summary = ImportSummary::default();
+        let imported = serde_json::json!({ "shared": "new", "added": true });
+        import_memory_json(&root, imported, MergeStrategy::SkipExisting, &mut summary).unwrap();
+
+        assert_eq!(summary.added, ["memory.json#added"]);
+        assert_eq!(summary.skipped, ["memory.json#shared"]);
+        let merged = read_value(&path);
+        assert_eq!(merged["shared"], "old");
+        assert_eq!(merged["kept"], 1);
+        assert_eq!(merged["added"], true);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
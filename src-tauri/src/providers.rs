@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, StatusCode};
+
+use crate::error::NeoError;
+use crate::http;
+
+/// Known model providers and the environment variable holding each one's API key.
+///
+/// Add a row here to support a new provider in `get_api_key`.
+pub const PROVIDERS: &[(&str, &str)] = &[
+    ("gemini", "GEMINI_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("openai", "OPENAI_API_KEY"),
+];
+
+/// Look up the environment variable for a provider name (case-insensitive).
+pub fn env_var(provider: &str) -> Option<&'static str> {
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(provider))
+        .map(|(_, var)| *var)
+}
+
+/// Like `env_var`, but with a readable error for unknown providers.
+pub fn require(provider: &str) -> Result<&'static str, NeoError> {
+    env_var(provider)
+        .ok_or_else(|| NeoError::InvalidInput(format!("Unknown provider '{provider}'")))
+}
+
+/// Prefix every key issued by a provider starts with, where it has one.
+fn key_prefix(provider: &str) -> Option<&'static str> {
+    Some(match provider.to_ascii_lowercase().as_str() {
+        "gemini" => "AIza",
+        "openrouter" => "sk-or-",
+        "anthropic" => "sk-ant-",
+        "openai" => "sk-",
+        _ => return None,
+    })
+}
+
+/// Catch keys that can't be right before they are saved: empty ones, ones with
+/// whitespace inside, and ones missing the provider's prefix. Errors never include
+/// the key.
+pub fn check_key_shape(provider: &str, key: &str) -> Result<(), NeoError> {
+    if key.is_empty() {
+        return Err(NeoError::InvalidInput(
+            "API key must not be empty".to_string(),
+        ));
+    }
+    if key.chars().any(char::is_whitespace) {
+        return Err(NeoError::InvalidInput(
+            "API key must not contain whitespace".to_string(),
+        ));
+    }
+    if let Some(prefix) = key_prefix(provider) {
+        if !key.starts_with(prefix) {
+            return Err(NeoError::InvalidInput(format!(
+                "This doesn't look like a {provider} API key; they start with '{prefix}'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Add a provider's authentication headers to a request. `None` for providers Neo
+/// doesn't know how to authenticate with.
+pub fn authorize(request: RequestBuilder, provider: &str, key: &str) -> Option<RequestBuilder> {
+    Some(match provider.to_ascii_lowercase().as_str() {
+        "gemini" => request.header("x-goog-api-key", key),
+        "openrouter" | "openai" => request.bearer_auth(key),
+        "anthropic" => request
+            .header("x-api-key", key)
+            .header("anthropic-version", "2023-06-01"),
+        _ => return None,
+    })
+}
+
+/// Deadline for a key validation request, so a hung provider doesn't block the UI.
+const VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prefix of the messages of errors caused by the network rather than the key. They
+/// are `NeoError::Network`, so the UI can tell "offline" apart from "rejected".
+pub const NETWORK_ERROR: &str = "Network error";
+
+/// Check an API key with a cheap authenticated request to the provider. The key is
+/// never stored.
+///
+/// Returns `Ok(false)` when the provider rejects the key, `NeoError::Network` when the
+/// provider can't be reached or times out, and other errors for unexpected responses.
+pub async fn validate_key(provider: &str, key: &str) -> Result<bool, NeoError> {
+    require(provider)?;
+    let client = http::client();
+
+    let url = match provider.to_ascii_lowercase().as_str() {
+        "gemini" => "https://generativelanguage.googleapis.com/v1beta/models",
+        "openrouter" => "https://openrouter.ai/api/v1/key",
+        "anthropic" => "https://api.anthropic.com/v1/models",
+        "openai" => "https://api.openai.com/v1/models",
+        _ => {
+            return Err(NeoError::InvalidInput(format!(
+                "Key validation is not supported for provider '{provider}'"
+            )))
+        }
+    };
+    let request = authorize(client.get(url), provider, key).ok_or_else(|| {
+        NeoError::InvalidInput(format!(
+            "Key validation is not supported for provider '{provider}'"
+        ))
+    })?;
+
+    let response = request
+        .timeout(VALIDATION_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            NeoError::Network(if e.is_timeout() {
+                format!("{NETWORK_ERROR}: {provider} did not respond in time")
+            } else {
+                format!("{NETWORK_ERROR}: failed to reach {provider}: {e}")
+            })
+        })?;
+
+    match response.status() {
+        status if status.is_success() => Ok(true),
+        // Gemini reports malformed or unknown keys as 400 API_KEY_INVALID.
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(false),
+        status => Err(NeoError::Failed(format!(
+            "Unexpected response from {provider}: {status}"
+        ))),
+    }
+}
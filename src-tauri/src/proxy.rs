@@ -0,0 +1,109 @@
+//! Provider API requests made on the renderer's behalf, so it can call model APIs
+//! without ever holding the keys.
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::Manager;
+use url::Url;
+
+use crate::dotenv::WorkspaceEnv;
+use crate::error::NeoError;
+use crate::http::{self, RetryPolicy};
+use crate::providers::{self, NETWORK_ERROR};
+
+/// Endpoints `proxy_ai_request` may reach, as a provider and the `https` URL prefix
+/// its requests must start with. Prefixes end at a path segment boundary.
+const ALLOWED_ENDPOINTS: &[(&str, &str)] = &[
+    (
+        "gemini",
+        "https://generativelanguage.googleapis.com/v1beta/",
+    ),
+    ("openrouter", "https://openrouter.ai/api/v1/"),
+    ("anthropic", "https://api.anthropic.com/v1/"),
+    ("openai", "https://api.openai.com/v1/"),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResponse {
+    pub status: u16,
+    /// The response body, parsed as JSON, or as a string if it isn't JSON.
+    pub body: Value,
+}
+
+/// Parse `endpoint` and check it against `provider`'s allowed prefixes. URLs are
+/// compared after parsing, so `..` segments, userinfo and explicit ports can't be
+/// used to slip past a prefix.
+fn allowed_endpoint(provider: &str, endpoint: &str) -> Result<Url, NeoError> {
+    let url = Url::parse(endpoint)
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid endpoint '{endpoint}': {e}")))?;
+    let allowed = url.username().is_empty()
+        && url.password().is_none()
+        && ALLOWED_ENDPOINTS
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(provider))
+            .filter_map(|(_, prefix)| Url::parse(prefix).ok())
+            .any(|prefix| {
+                url.scheme() == prefix.scheme()
+                    && url.host_str() == prefix.host_str()
+                    && url.port_or_known_default() == prefix.port_or_known_default()
+                    && url.path().starts_with(prefix.path())
+            });
+    if !allowed {
+        return Err(NeoError::PermissionDenied(format!(
+            "Endpoint is not an allowed {provider} API: {endpoint}"
+        )));
+    }
+    Ok(url)
+}
+
+/// POST a JSON body to a provider's API with the saved API key and return the
+/// provider's response.
+///
+/// Rate limits, server errors and failures to connect are retried with backoff as
+/// `retry` allows, honoring `Retry-After`. If they persist, the command fails with
+/// the final status and the number of attempts; any other status is returned as the
+/// response.
+///
+/// The key is read the same way as `get_api_key` and sent in the header the provider
+/// expects. `endpoint` must be a full URL under one of the provider's known API
+/// prefixes, so the command can't be used to send keys, or anything else, elsewhere.
+#[tauri::command]
+pub async fn proxy_ai_request(
+    app: tauri::AppHandle,
+    provider: String,
+    endpoint: String,
+    body: Value,
+    retry: Option<RetryPolicy>,
+) -> Result<ProxyResponse, NeoError> {
+    providers::require(&provider)?;
+    let url = allowed_endpoint(&provider, &endpoint)?;
+    let key = crate::read_provider_key(&app.state::<WorkspaceEnv>(), &provider)?;
+    let request =
+        providers::authorize(http::client().post(url), &provider, &key).ok_or_else(|| {
+            NeoError::Failed(format!(
+                "Proxying is not supported for provider '{provider}'"
+            ))
+        })?;
+
+    let retry = retry.unwrap_or_default();
+    let sent = http::send_with_retry(request.json(&body), &retry, &provider).await?;
+    let status = sent.response.status();
+    let text = sent.response.text().await.map_err(|e| {
+        NeoError::Network(format!(
+            "{NETWORK_ERROR}: failed to read {provider}'s response: {e}"
+        ))
+    })?;
+    if http::is_retryable(status) {
+        return Err(NeoError::Network(format!(
+            "{NETWORK_ERROR}: {provider} returned {status} after {}: {}",
+            http::attempts(sent.attempts),
+            text.trim()
+        )));
+    }
+    let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+    Ok(ProxyResponse {
+        status: status.as_u16(),
+        body,
+    })
+}
@@ -0,0 +1,368 @@
+//! A per-workspace cap on the size of `.neomemory/`, enforced when memory is written.
+//!
+//! A write that would go over the quota fails with `quotaExceeded`, unless the
+//! workspace allows eviction: then deleted conversations in the trash go first,
+//! followed by the workspace's log, its rotated copy first, and the least recently
+//! updated conversations. The manifest, settings, `memory.json`,
+//! memory entries and pinned or starred conversations are never evicted. Backups are
+//! bounded by their own rotation and don't count towards the quota.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::conversations;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::logging;
+use crate::memory::{self, BACKUPS_DIR, CONVERSATIONS_DIR, MEMORY_DIR, TRASH_DIR};
+use crate::search;
+use crate::workspaces::WorkspaceGrants;
+
+/// Prefix of the error returned when a write doesn't fit in the quota.
+pub const QUOTA_EXCEEDED_ERROR: &str = "Memory quota exceeded";
+/// Key of the quota settings in `.neomemory/config.json`.
+const CONFIG_KEY: &str = "memoryQuota";
+const DEFAULT_MAX_BYTES: u64 = 500 * 1024 * 1024;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemoryQuota {
+    /// Largest `.neomemory/` allowed, in bytes.
+    pub max_bytes: u64,
+    /// Free up room by evicting old logs and conversations instead of failing writes.
+    pub evict: bool,
+}
+
+impl Default for MemoryQuota {
+    fn default() -> Self {
+        MemoryQuota {
+            max_bytes: DEFAULT_MAX_BYTES,
+            evict: false,
+        }
+    }
+}
+
+/// A file removed to make room for a write.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictedFile {
+    /// Path relative to `.neomemory/`.
+    pub path: String,
+    pub bytes: u64,
+    /// `trash`, `log` or `conversation`.
+    pub kind: &'static str,
+}
+
+/// A file that may be evicted, with the time it is ordered by.
+struct Candidate {
+    path: PathBuf,
+    bytes: u64,
+    kind: &'static str,
+    age: u64,
+    /// Id to drop from the conversation index once the file is gone.
+    conversation_id: Option<String>,
+}
+
+/// The workspace's quota from `config.json`, or the default.
+pub fn load(workspace: &Path) -> MemoryQuota {
+    memory::read_config(workspace)
+        .remove(CONFIG_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Bytes counted towards the quota: every file in `.neomemory/` except backups.
+pub fn usage(workspace: &Path) -> u64 {
+    fn walk(dir: &Path) -> u64 {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| match entry.file_type() {
+                Ok(t) if t.is_dir() => walk(&entry.path()),
+                Ok(t) if t.is_file() => entry.metadata().map_or(0, |m| m.len()),
+                _ => 0,
+            })
+            .sum()
+    }
+    let memory = memory::memory_dir(workspace);
+    walk(&memory).saturating_sub(walk(&memory.join(BACKUPS_DIR)))
+}
+
+/// Whether a conversation is marked to be kept. Unreadable files count as pinned,
+/// since there's no telling.
+pub fn is_pinned(path: &Path) -> bool {
+    let Ok(content) = encryption::read_to_string(path) else {
+        return true;
+    };
+    let Ok(value) = serde_json::from_str::<Value>(&content) else {
+        return false;
+    };
+    ["pinned", "starred"]
+        .iter()
+        .any(|key| value.get(key).and_then(Value::as_bool) == Some(true))
+}
+
+/// Evictable files in the order they are evicted: the trash, logs, then
+/// conversations, each oldest first. `writing` is never a candidate.
+fn candidates(workspace: &Path, writing: &Path) -> Vec<Candidate> {
+    let memory = memory::memory_dir(workspace);
+    let files = |dir: &Path| -> Vec<(PathBuf, u64)> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .filter(|entry| entry.path() != writing)
+            .map(|entry| (entry.path(), entry.metadata().map_or(0, |m| m.len())))
+            .collect()
+    };
+
+    let mut trash: Vec<Candidate> = files(&memory.join(TRASH_DIR))
+        .into_iter()
+        .map(|(path, bytes)| Candidate {
+            age: conversations::modified_millis(&path),
+            path,
+            bytes,
+            kind: "trash",
+            conversation_id: None,
+        })
+        .collect();
+    trash.sort_by_key(|c| c.age);
+
+    let mut logs: Vec<Candidate> = files(&memory)
+        .into_iter()
+        .filter(|(path, _)| {
+            path.file_name()
+                .is_some_and(|name| logging::is_log_file(&name.to_string_lossy()))
+        })
+        .map(|(path, bytes)| Candidate {
+            age: conversations::modified_millis(&path),
+            path,
+            bytes,
+            kind: "log",
+            conversation_id: None,
+        })
+        .collect();
+    logs.sort_by_key(|c| c.age);
+
+    let mut conversations: Vec<Candidate> = files(&memory.join(CONVERSATIONS_DIR))
+        .into_iter()
+        .filter(|(path, _)| conversations::is_conversation_file(path) && !is_pinned(path))
+        .filter_map(|(path, bytes)| {
+            let meta = conversations::read_meta(&path)?;
+            Some(Candidate {
+                path,
+                bytes,
+                kind: "conversation",
+                age: meta.updated_at,
+                conversation_id: Some(meta.id),
+            })
+        })
+        .collect();
+    conversations.sort_by_key(|c| c.age);
+
+    trash.into_iter().chain(logs).chain(conversations).collect()
+}
+
+/// Make sure writing `new_bytes` to `path` keeps the workspace within its quota,
+/// evicting files first if the quota allows it. Returns the files evicted.
+///
+/// Nothing is evicted unless doing so frees enough room; otherwise the write is
+/// refused with `QUOTA_EXCEEDED_ERROR`.
+pub fn make_room(
+    workspace: &Path,
+    path: &Path,
+    new_bytes: u64,
+) -> Result<Vec<EvictedFile>, NeoError> {
+    let quota = load(workspace);
+    let replaced = fs::metadata(path).map_or(0, |m| m.len());
+    let needed = usage(workspace)
+        .saturating_sub(replaced)
+        .saturating_add(new_bytes);
+    if needed <= quota.max_bytes {
+        return Ok(Vec::new());
+    }
+    let exceeded = || {
+        NeoError::QuotaExceeded(format!(
+            "{QUOTA_EXCEEDED_ERROR}: the write needs {needed} bytes of the {} allowed",
+            quota.max_bytes
+        ))
+    };
+    if !quota.evict {
+        return Err(exceeded());
+    }
+
+    let mut excess = needed - quota.max_bytes;
+    let mut plan = Vec::new();
+    for candidate in candidates(workspace, path) {
+        if excess == 0 {
+            break;
+        }
+        excess = excess.saturating_sub(candidate.bytes);
+        plan.push(candidate);
+    }
+    if excess > 0 {
+        return Err(exceeded());
+    }
+
+    let memory = memory::memory_dir(workspace);
+    let mut evicted = Vec::new();
+    let mut conversation_ids = Vec::new();
+    let mut failed = None;
+    for candidate in plan {
+        if let Err(e) = fs::remove_file(&candidate.path) {
+            failed = Some(NeoError::Io(format!(
+                "Failed to evict {}: {e}",
+                candidate.path.display()
+            )));
+            break;
+        }
+        search::refresh(workspace, &candidate.path);
+        conversation_ids.extend(candidate.conversation_id);
+        let relative = candidate
+            .path
+            .strip_prefix(&memory)
+            .unwrap_or(&candidate.path);
+        evicted.push(EvictedFile {
+            path: relative.to_string_lossy().into_owned(),
+            bytes: candidate.bytes,
+            kind: candidate.kind,
+        });
+    }
+    if !conversation_ids.is_empty() {
+        conversations::remove_from_index(workspace, &conversation_ids)?;
+    }
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    tracing::info!(files = evicted.len(), "evicted memory to stay within quota");
+    Ok(evicted)
+}
+
+/// Get a workspace's memory quota.
+#[tauri::command]
+pub fn get_memory_quota(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+) -> Result<MemoryQuota, NeoError> {
+    Ok(load(&grants.require(&workspace)?))
+}
+
+/// Set a workspace's memory quota, saved in `.neomemory/config.json`. Lowering it
+/// doesn't evict anything until the next write.
+#[tauri::command]
+pub fn set_memory_quota(
+    grants: State<'_, WorkspaceGrants>,
+    workspace: String,
+    quota: MemoryQuota,
+) -> Result<(), NeoError> {
+    let root = grants.require(&workspace)?;
+    if !memory::memory_dir(&root).is_dir() {
+        return Err(NeoError::Failed(format!("Workspace has no {MEMORY_DIR}")));
+    }
+    let value = serde_json::to_value(quota)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize memory quota: {e}")))?;
+    let mut config = memory::read_config(&root);
+    config.insert(CONFIG_KEY.to_string(), value);
+    memory::write_config(&root, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    use crate::memory::MEMORY_FILE;
+
+    fn workspace(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("neo-quota-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in [CONVERSATIONS_DIR, TRASH_DIR] {
+            fs::create_dir_all(memory::memory_dir(&root).join(dir)).unwrap();
+        }
+        root
+    }
+
+    fn write(root: &Path, relative: &str, content: &str) -> PathBuf {
+        let path = memory::memory_dir(root).join(relative);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn conversation(root: &Path, id: &str, updated_at: u64, marks: &str) -> PathBuf {
+        let content =
+            format!(r#"{{"id": "{id}", "updatedAt": {updated_at}{marks}, "messages": []}}"#);
+        write(root, &format!("{CONVERSATIONS_DIR}/{id}.json"), &content)
+    }
+
+    fn names(candidates: &[Candidate]) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|c| c.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn never_offers_pinned_or_starred_conversations() {
+        let root = workspace("pinned");
+        conversation(&root, "pinned", 1, r#", "pinned": true"#);
+        conversation(&root, "starred", 2, r#", "starred": true"#);
+        conversation(&root, "newer", 4, r#", "pinned": false"#);
+        conversation(&root, "older", 3, "");
+        write(&root, &format!("{CONVERSATIONS_DIR}/broken.json"), "{");
+
+        let found = candidates(&root, Path::new(""));
+        assert_eq!(names(&found), ["older.json", "newer.json"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn offers_trash_then_logs_then_conversations() {
+        let root = workspace("order");
+        let writing = conversation(&root, "writing", 0, "");
+        conversation(&root, "old", 1, "");
+        let rotated = write(&root, logging::ROTATED_LOG_FILE, "log");
+        let rotated_at = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(rotated)
+            .unwrap()
+            .set_modified(rotated_at)
+            .unwrap();
+        write(&root, logging::LOG_FILE, "log");
+        write(&root, &format!("{TRASH_DIR}/gone.json"), "{}");
+        write(&root, MEMORY_FILE, "{}");
+
+        let found = candidates(&root, &writing);
+        let kinds: Vec<_> = found.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, ["trash", "log", "log", "conversation"]);
+        assert_eq!(names(&found)[1..], ["neo.log.1", "neo.log", "old.json"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn refuses_writes_that_only_pinned_conversations_could_make_room_for() {
+        let root = workspace("refuse");
+        let pinned = conversation(&root, "pinned", 1, r#", "pinned": true"#);
+        let quota = MemoryQuota {
+            max_bytes: usage(&root) + 10,
+            evict: true,
+        };
+        let mut config = memory::read_config(&root);
+        config.insert(CONFIG_KEY.to_string(), serde_json::to_value(quota).unwrap());
+        memory::write_config(&root, &config).unwrap();
+
+        let path = memory::memory_dir(&root).join(MEMORY_FILE);
+        let Err(error) = make_room(&root, &path, 1024) else {
+            panic!("the write should have been refused");
+        };
+        assert!(matches!(error, NeoError::QuotaExceeded(_)));
+        assert!(pinned.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+//! The directories workspaces may be granted under without `allowExternal`.
+//!
+//! The list is kept in `allowed-roots.json` under the app config dir and starts out
+//! as just the home directory, so nothing is loosened until the user adds a root,
+//! such as an external drive.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+
+use crate::error::NeoError;
+use crate::memory;
+use crate::paths;
+
+/// File under the app config dir that holds the allowed roots.
+const ROOTS_FILE: &str = "allowed-roots.json";
+
+fn store_path(app: &tauri::AppHandle) -> Result<PathBuf, NeoError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| NeoError::Failed(format!("Failed to resolve app config dir: {e}")))?;
+    Ok(dir.join(ROOTS_FILE))
+}
+
+fn home() -> Result<PathBuf, NeoError> {
+    paths::home_dir()
+        .ok_or_else(|| NeoError::Failed("Could not determine your home directory".to_string()))
+}
+
+/// The allowed roots, canonicalized where they still exist. A missing or unreadable
+/// file means only the home directory.
+pub fn load(app: &tauri::AppHandle) -> Result<Vec<PathBuf>, NeoError> {
+    let stored = store_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Vec<PathBuf>>(&content).ok());
+    let Some(stored) = stored else {
+        return Ok(vec![home()?]);
+    };
+    Ok(stored
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect())
+}
+
+fn save(app: &tauri::AppHandle, roots: &[PathBuf]) -> Result<(), NeoError> {
+    let path = store_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| NeoError::Io(format!("Failed to create app config dir: {e}")))?;
+    }
+    let content = serde_json::to_string_pretty(roots)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize allowed roots: {e}")))?;
+    memory::write_atomic(&path, content.as_bytes())
+}
+
+/// The allowed root `canonical` lies under, if any.
+pub fn containing(roots: &[PathBuf], canonical: &Path) -> Option<PathBuf> {
+    roots
+        .iter()
+        .find(|root| paths::is_within(canonical, root))
+        .cloned()
+}
+
+/// Check a folder the user wants to add as a root. The same locations that can never
+/// be granted can't be roots, and neither can a folder above the home directory,
+/// which would let other users' folders through.
+fn check_root(path: &str) -> Result<PathBuf, NeoError> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
+    if !canonical.is_dir() {
+        return Err(NeoError::InvalidInput(
+            "Selected path is not a directory".to_string(),
+        ));
+    }
+    let home = home()?;
+    if canonical != home && paths::is_within(&home, &canonical) {
+        return Err(NeoError::PermissionDenied(
+            "An allowed root can't contain your home directory".to_string(),
+        ));
+    }
+    if paths::is_system_path(&canonical) {
+        return Err(NeoError::PermissionDenied(
+            "A system location can't be an allowed root".to_string(),
+        ));
+    }
+    Ok(canonical)
+}
+
+/// List the directories workspaces may be granted under.
+#[tauri::command]
+pub fn get_allowed_roots(app: tauri::AppHandle) -> Result<Vec<PathBuf>, NeoError> {
+    load(&app)
+}
+
+/// Let workspaces be granted anywhere under a directory, such as an external drive,
+/// without `allowExternal`. Returns the updated list.
+#[tauri::command]
+pub fn add_allowed_root(app: tauri::AppHandle, path: String) -> Result<Vec<PathBuf>, NeoError> {
+    let canonical = check_root(&path)?;
+    let mut roots = load(&app)?;
+    if !roots.contains(&canonical) {
+        roots.push(canonical);
+        save(&app, &roots)?;
+    }
+    Ok(roots)
+}
+
+/// Stop allowing new grants under a directory. Workspaces already granted there stay
+/// granted. The last root can't be removed. Returns the updated list.
+#[tauri::command]
+pub fn remove_allowed_root(app: tauri::AppHandle, path: String) -> Result<Vec<PathBuf>, NeoError> {
+    let target = Path::new(&path);
+    let target = target
+        .canonicalize()
+        .unwrap_or_else(|_| target.to_path_buf());
+    let mut roots = load(&app)?;
+    let before = roots.len();
+    roots.retain(|root| root != &target);
+    if roots.len() == before {
+        return Err(NeoError::NotFound(format!(
+            "Not an allowed root: {}",
+            target.display()
+        )));
+    }
+    if roots.is_empty() {
+        return Err(NeoError::InvalidInput(
+            "At least one allowed root must remain".to_string(),
+        ));
+    }
+    save(&app, &roots)?;
+    Ok(roots)
+}
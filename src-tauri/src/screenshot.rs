@@ -0,0 +1,87 @@
+//! Screen capture for vision prompts.
+
+use std::io::Cursor;
+
+use base64::Engine;
+use image::{ImageFormat, RgbaImage};
+use screenshots::Screen;
+
+use crate::error::NeoError;
+
+/// Ask for the Screen Recording permission, showing the system prompt the first time.
+/// Without it macOS returns images of the desktop background only.
+#[cfg(target_os = "macos")]
+fn ensure_permission() -> Result<(), NeoError> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+    // SAFETY: both functions take no arguments and only query or prompt for TCC access.
+    let granted = unsafe { CGPreflightScreenCaptureAccess() || CGRequestScreenCaptureAccess() };
+    if granted {
+        Ok(())
+    } else {
+        Err(NeoError::PermissionDenied(
+            "Screen recording permission is required; allow Neo in System Settings > \
+             Privacy & Security > Screen Recording"
+                .to_string(),
+        ))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ensure_permission() -> Result<(), NeoError> {
+    Ok(())
+}
+
+fn primary_screen() -> Result<Screen, NeoError> {
+    let screens =
+        Screen::all().map_err(|e| NeoError::Io(format!("Failed to list screens: {e}")))?;
+    let primary = screens
+        .iter()
+        .position(|s| s.display_info.is_primary)
+        .unwrap_or(0);
+    screens
+        .into_iter()
+        .nth(primary)
+        .ok_or_else(|| NeoError::NotFound("No screen found".to_string()))
+}
+
+fn capture(x: i32, y: i32, width: u32, height: u32) -> Result<String, NeoError> {
+    ensure_permission()?;
+    let captured = if width == 0 && height == 0 {
+        primary_screen()?
+            .capture()
+            .map_err(|e| NeoError::Failed(format!("Failed to capture screen: {e}")))?
+    } else {
+        let screen = Screen::from_point(x, y)
+            .map_err(|e| NeoError::Failed(format!("No screen at ({x}, {y}): {e}")))?;
+        let info = screen.display_info;
+        screen
+            .capture_area(x - info.x, y - info.y, width, height)
+            .map_err(|e| NeoError::Failed(format!("Failed to capture screen area: {e}")))?
+    };
+
+    let (width, height) = captured.dimensions();
+    let image = RgbaImage::from_raw(width, height, captured.into_raw())
+        .ok_or_else(|| NeoError::Failed("Captured image has an unexpected size".to_string()))?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| NeoError::Failed(format!("Failed to encode PNG: {e}")))?;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&png);
+    Ok(format!("data:image/png;base64,{b64}"))
+}
+
+/// Capture a region of the screen, in global screen coordinates, as a PNG data URL.
+/// A zero width and height captures the whole primary screen.
+///
+/// On macOS the Screen Recording permission is requested first, and an error is
+/// returned if it is denied instead of a blank image.
+#[tauri::command]
+pub async fn take_screenshot(x: i32, y: i32, width: u32, height: u32) -> Result<String, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || capture(x, y, width, height))
+        .await
+        .map_err(|e| NeoError::Failed(format!("Failed to capture screen: {e}")))?
+}
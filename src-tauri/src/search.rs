@@ -0,0 +1,586 @@
+//! Full-text search over a workspace's memory entries and conversations, backed by an
+//! inverted index persisted in `.neomemory/index/`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::conversations;
+use crate::encryption;
+use crate::error::NeoError;
+use crate::memories::Memory;
+use crate::memory::{self, CONVERSATIONS_DIR, MEMORIES_DIR, MEMORY_DIR, MEMORY_FILE};
+use crate::workspaces::WorkspaceGrants;
+
+pub const INDEX_DIR: &str = "index";
+const SEARCH_INDEX_FILE: &str = "search.json";
+/// Current version of the search index format; older indexes are rebuilt.
+const SEARCH_INDEX_VERSION: u32 = 1;
+/// Hits returned by `search_memory` unless the caller sets a limit.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+/// Characters of context kept on each side of the first match in a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// Serializes read-modify-write cycles on index files within this instance.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocumentKind {
+    Memory,
+    Conversation,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    id: String,
+    kind: DocumentKind,
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Number of tokens in `text`, to favour short documents with the same matches.
+    length: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchIndex {
+    version: u32,
+    /// Indexed documents keyed by their source path, relative to the workspace root.
+    documents: BTreeMap<String, Document>,
+    /// For each term, the sources containing it and how often.
+    postings: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        SearchIndex {
+            version: SEARCH_INDEX_VERSION,
+            documents: BTreeMap::new(),
+            postings: BTreeMap::new(),
+        }
+    }
+}
+
+impl SearchIndex {
+    fn insert(&mut self, source: String, document: Document) {
+        self.remove(&source);
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        for token in tokens(&document.text).chain(document.tags.iter().flat_map(|t| tokens(t))) {
+            *counts.entry(token.term).or_default() += 1;
+        }
+        for (term, count) in counts {
+            self.postings
+                .entry(term)
+                .or_default()
+                .insert(source.clone(), count);
+        }
+        self.documents.insert(source, document);
+    }
+
+    fn remove(&mut self, source: &str) {
+        let Some(document) = self.documents.remove(source) else {
+            return;
+        };
+        let terms: HashSet<String> = tokens(&document.text)
+            .chain(document.tags.iter().flat_map(|t| tokens(t)))
+            .map(|token| token.term)
+            .collect();
+        for term in terms {
+            if let Some(sources) = self.postings.get_mut(&term) {
+                sources.remove(source);
+                if sources.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+}
+
+/// A word of a text with its byte range, lowercased for matching.
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+/// Split text into runs of letters and digits.
+fn tokens(text: &str) -> impl Iterator<Item = Token> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.peek().is_some_and(|(_, c)| !c.is_alphanumeric()) {
+            chars.next();
+        }
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.peek().copied().filter(|(_, c)| c.is_alphanumeric()) {
+            end = i + c.len_utf8();
+            chars.next();
+        }
+        Some(Token {
+            term: text[start..end].to_lowercase(),
+            start,
+            end,
+        })
+    })
+}
+
+/// Append every string inside a JSON value to `out`, one per line.
+fn collect_strings(value: &Value, out: &mut String) {
+    match value {
+        Value::String(s) => {
+            out.push_str(s);
+            out.push('\n');
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&encryption::read_to_string(path).ok()?).ok()
+}
+
+/// Build the search document for a file inside `.neomemory/`, or `None` if it isn't
+/// one that is indexed or can't be parsed.
+fn document(memory: &Path, path: &Path) -> Option<Document> {
+    let parent = path.parent()?;
+    let stem = path.file_stem()?.to_string_lossy().into_owned();
+    let is_json = path.extension().is_some_and(|e| e == "json");
+
+    let (id, kind, text, tags) = if is_json && parent == memory.join(MEMORIES_DIR) {
+        let entry: Memory = serde_json::from_str(&encryption::read_to_string(path).ok()?).ok()?;
+        (entry.id, DocumentKind::Memory, entry.content, entry.tags)
+    } else if is_json && parent == memory.join(CONVERSATIONS_DIR) {
+        let conversation = read_json(path)?;
+        let mut text = String::new();
+        if let Some(title) = conversation.get("title") {
+            collect_strings(title, &mut text);
+        }
+        for message in conversation.get("messages")?.as_array()? {
+            if let Some(content) = message.get("content") {
+                collect_strings(content, &mut text);
+            }
+        }
+        let id = conversation
+            .get("id")
+            .and_then(Value::as_str)
+            .map_or(stem, str::to_string);
+        (id, DocumentKind::Conversation, text, Vec::new())
+    } else if path == memory.join(MEMORY_FILE) {
+        let mut text = String::new();
+        collect_strings(&read_json(path)?, &mut text);
+        (stem, DocumentKind::Memory, text, Vec::new())
+    } else {
+        return None;
+    };
+
+    Some(Document {
+        length: tokens(&text).count(),
+        id,
+        kind,
+        text,
+        tags,
+    })
+}
+
+/// Every file the index covers: memory entries, conversations and `memory.json`.
+fn indexed_files(memory: &Path) -> Vec<PathBuf> {
+    let mut files = vec![memory.join(MEMORY_FILE)];
+    for dir in [MEMORIES_DIR, CONVERSATIONS_DIR] {
+        let entries = fs::read_dir(memory.join(dir))
+            .into_iter()
+            .flatten()
+            .flatten();
+        files.extend(entries.map(|e| e.path()));
+    }
+    files
+}
+
+fn source_key(workspace: &Path, path: &Path) -> String {
+    path.strip_prefix(workspace)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn index_path(workspace: &Path) -> PathBuf {
+    memory::memory_dir(workspace)
+        .join(INDEX_DIR)
+        .join(SEARCH_INDEX_FILE)
+}
+
+fn load(workspace: &Path) -> Option<SearchIndex> {
+    let content = encryption::read_to_string(&index_path(workspace)).ok()?;
+    serde_json::from_str::<SearchIndex>(&content)
+        .ok()
+        .filter(|index| index.version == SEARCH_INDEX_VERSION)
+}
+
+fn save(workspace: &Path, index: &SearchIndex) -> Result<(), NeoError> {
+    let path = index_path(workspace);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| NeoError::Io(format!("Failed to create {MEMORY_DIR}/{INDEX_DIR}: {e}")))?;
+    }
+    let content = serde_json::to_string(index)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize search index: {e}")))?;
+    encryption::write(workspace, &path, content.as_bytes())
+}
+
+fn build(workspace: &Path) -> SearchIndex {
+    let memory = memory::memory_dir(workspace);
+    let mut index = SearchIndex::default();
+    for path in indexed_files(&memory) {
+        if let Some(document) = document(&memory, &path) {
+            index.insert(source_key(workspace, &path), document);
+        }
+    }
+    index
+}
+
+/// Update the index entry for a file that was just written or deleted. Files the
+/// index doesn't cover are ignored, and so is a workspace that was never indexed.
+///
+/// Best-effort: a failure leaves the index stale until the next `index_memory`.
+pub fn refresh(workspace: &Path, path: &Path) {
+    let Ok(_guard) = INDEX_LOCK.lock() else {
+        return;
+    };
+    let Some(mut index) = load(workspace) else {
+        return;
+    };
+    let memory = memory::memory_dir(workspace);
+    let source = source_key(workspace, path);
+    match document(&memory, path) {
+        Some(document) => index.insert(source, document),
+        None if index.documents.contains_key(&source) => index.remove(&source),
+        None => return,
+    }
+    let _ = save(workspace, &index);
+}
+
+/// A parsed `search_memory` query.
+#[derive(Default)]
+struct Query {
+    terms: Vec<String>,
+    phrases: Vec<Vec<String>>,
+    kind: Option<DocumentKind>,
+    tags: Vec<String>,
+}
+
+impl Query {
+    fn all_terms(&self) -> impl Iterator<Item = &String> {
+        self.terms.iter().chain(self.phrases.iter().flatten())
+    }
+}
+
+fn apply_word(query: &mut Query, word: &str) -> Result<(), NeoError> {
+    match word.split_once(':') {
+        Some(("kind" | "type", value)) => {
+            query.kind = Some(match value.to_lowercase().as_str() {
+                "memory" => DocumentKind::Memory,
+                "conversation" => DocumentKind::Conversation,
+                _ => {
+                    return Err(NeoError::InvalidInput(format!(
+                        "Invalid search filter: {word}"
+                    )))
+                }
+            });
+        }
+        Some(("tag", value)) => query.tags.push(value.to_lowercase()),
+        _ => query.terms.extend(tokens(word).map(|t| t.term)),
+    }
+    Ok(())
+}
+
+/// Parse words, `"quoted phrases"` and `kind:`/`type:` (`memory` or `conversation`)
+/// and `tag:` filters.
+fn parse_query(input: &str) -> Result<Query, NeoError> {
+    let mut query = Query::default();
+    for (i, part) in input.split('"').enumerate() {
+        if i % 2 == 1 {
+            let phrase: Vec<String> = tokens(part).map(|t| t.term).collect();
+            match phrase.len() {
+                0 => {}
+                1 => query.terms.extend(phrase),
+                _ => query.phrases.push(phrase),
+            }
+        } else {
+            for word in part.split_whitespace() {
+                apply_word(&mut query, word)?;
+            }
+        }
+    }
+    Ok(query)
+}
+
+fn contains_phrase(terms: &[String], phrase: &[String]) -> bool {
+    terms.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Up to `SNIPPET_CONTEXT` characters around the first matching token, with every
+/// matching token wrapped in `**`.
+fn snippet(text: &str, matching: &HashSet<&str>) -> String {
+    let spans: Vec<Token> = tokens(text)
+        .filter(|t| matching.contains(t.term.as_str()))
+        .collect();
+    let at = spans.first().map_or(0, |t| t.start);
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let end = text[at..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _)| at + i);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = start;
+    for span in spans.iter().filter(|t| t.start >= start && t.end <= end) {
+        snippet.push_str(&text[cursor..span.start]);
+        snippet.push_str("**");
+        snippet.push_str(&text[span.start..span.end]);
+        snippet.push_str("**");
+        cursor = span.end;
+    }
+    snippet.push_str(&text[cursor..end]);
+    if end < text.len() {
+        snippet.push('…');
+    }
+    snippet.trim().replace('\n', " ")
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Memory id, conversation id, or `memory` for `memory.json`.
+    pub id: String,
+    pub kind: DocumentKind,
+    /// File the hit came from, relative to the workspace root.
+    pub source: String,
+    /// Text around the first match, with matches wrapped in `**`.
+    pub snippet: String,
+    pub score: f64,
+}
+
+fn search(workspace: &Path, index: &SearchIndex, query: &Query, limit: usize) -> Vec<SearchHit> {
+    let total = index.documents.len() as f64;
+    let mut scores: BTreeMap<&str, f64> = BTreeMap::new();
+    let unique: HashSet<&str> = query.all_terms().map(String::as_str).collect();
+    for term in &unique {
+        let Some(sources) = index.postings.get(*term) else {
+            continue;
+        };
+        let idf = (1.0 + total / sources.len() as f64).ln();
+        for (source, count) in sources {
+            *scores.entry(source.as_str()).or_default() += f64::from(*count) * idf;
+        }
+    }
+
+    let mut hits: Vec<SearchHit> = scores
+        .into_iter()
+        .filter_map(|(source, score)| {
+            let document = index.documents.get(source)?;
+            if query.kind.is_some_and(|kind| kind != document.kind) {
+                return None;
+            }
+            let tags: Vec<String> = document.tags.iter().map(|t| t.to_lowercase()).collect();
+            if !query.tags.iter().all(|tag| tags.contains(tag)) {
+                return None;
+            }
+            if !query.phrases.is_empty() {
+                let terms: Vec<String> = tokens(&document.text).map(|t| t.term).collect();
+                if !query.phrases.iter().all(|p| contains_phrase(&terms, p)) {
+                    return None;
+                }
+            }
+            // The index can lag behind files deleted outside Neo.
+            if !workspace.join(source).exists() {
+                return None;
+            }
+            Some(SearchHit {
+                id: document.id.clone(),
+                kind: document.kind,
+                source: source.to_string(),
+                snippet: snippet(&document.text, &unique),
+                score: score / (document.length.max(1) as f64).sqrt(),
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// Rebuild the workspace's search index from scratch, for the first search or after
+/// the index got out of sync. Returns the number of documents indexed.
+#[tauri::command]
+pub async fn index_memory(app: tauri::AppHandle, workspace: String) -> Result<usize, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace)?;
+        let _guard = INDEX_LOCK
+            .lock()
+            .map_err(|_| NeoError::Failed("Search index is unavailable".to_string()))?;
+        let index = build(&root);
+        save(&root, &index)?;
+        Ok(index.documents.len())
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to index memory: {e}")))?
+}
+
+/// Search the workspace's memory entries, `memory.json` and conversations.
+///
+/// Hits match any of the query's words and every `"quoted phrase"`, ranked by how
+/// often and how rare the matched words are. `kind:memory` or `kind:conversation`
+/// restricts the source, and `tag:name` requires a memory tag. At most `limit` hits
+/// (20 by default) are returned. The index is built on first use; entries written
+/// through Neo's commands keep it current. The search, and building the index, run on
+/// a blocking thread.
+#[tauri::command]
+pub async fn search_memory(
+    app: tauri::AppHandle,
+    workspace: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace)?;
+        let query = parse_query(&query)?;
+        let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        if query.all_terms().next().is_none() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let index = match load(&root) {
+            Some(index) => index,
+            None => {
+                let _guard = INDEX_LOCK
+                    .lock()
+                    .map_err(|_| NeoError::Failed("Search index is unavailable".to_string()))?;
+                let index = build(&root);
+                if memory::memory_dir(&root).is_dir() {
+                    save(&root, &index)?;
+                }
+                index
+            }
+        };
+        Ok(search(&root, &index, &query, limit))
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to search memory: {e}")))?
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// Conversation file the line is in, relative to the workspace root.
+    pub relative_path: String,
+    /// 1-based.
+    pub line_number: u32,
+    /// The matching line, trimmed, and cut down to the text around the match if it's
+    /// long.
+    pub snippet: String,
+}
+
+/// The part of `line` around the first match of `needle`, which is lowercase.
+fn line_snippet(line: &str, needle: &str) -> String {
+    let line = line.trim();
+    let lowercase = line.to_lowercase();
+    let chars = line.chars().count();
+    let needle_chars = needle.chars().count();
+    if chars <= needle_chars + 2 * SNIPPET_CONTEXT {
+        return line.to_string();
+    }
+    // Lowercasing can change the length of a few characters, so this is approximate.
+    let at = lowercase
+        .find(needle)
+        .map_or(0, |i| lowercase[..i].chars().count())
+        .min(chars);
+    let start = at.saturating_sub(SNIPPET_CONTEXT);
+    let end = (at + needle_chars + SNIPPET_CONTEXT).min(chars);
+    let mut snippet: String = line.chars().skip(start).take(end - start).collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn scan_conversations(
+    workspace: &Path,
+    needle: &str,
+    max_results: usize,
+) -> Result<Vec<SearchResult>, NeoError> {
+    let dir = memory::memory_dir(workspace).join(CONVERSATIONS_DIR);
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| conversations::is_conversation_file(path) && path.is_file())
+        .collect();
+    files.sort();
+
+    let mut results = Vec::new();
+    for path in files {
+        let content = match encryption::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e @ NeoError::Locked(_)) => return Err(e),
+            // One unreadable file shouldn't hide the matches in the others.
+            Err(_) => continue,
+        };
+        for (number, line) in content.lines().enumerate() {
+            if !line.to_lowercase().contains(needle) {
+                continue;
+            }
+            results.push(SearchResult {
+                relative_path: source_key(workspace, &path),
+                line_number: u32::try_from(number + 1).unwrap_or(u32::MAX),
+                snippet: line_snippet(line, needle),
+            });
+            if results.len() >= max_results {
+                return Ok(results);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Find the lines of a workspace's conversation files that contain `query`,
+/// ignoring case, in file name order, stopping after `max_results`.
+///
+/// Unlike `search_memory` this scans the files directly, so it needs no index and
+/// matches any text, including inside words, but it reads every conversation.
+#[tauri::command]
+pub async fn search_workspace_files(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    query: String,
+    max_results: usize,
+) -> Result<Vec<SearchResult>, NeoError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = app.state::<WorkspaceGrants>().require(&workspace_path)?;
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() || max_results == 0 {
+            return Ok(Vec::new());
+        }
+        scan_conversations(&root, &needle, max_results)
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to search workspace files: {e}")))?
+}
@@ -0,0 +1,221 @@
+//! Provider API keys in the OS keychain.
+//!
+//! On Linux the keychain is the Secret Service, which isn't running on every
+//! desktop. When it can't be reached, keys go to an encrypted file in the app config
+//! directory instead, and a warning is logged the first time that happens.
+
+use crate::error::NeoError;
+
+/// Keychain service holding every provider key; the provider name is the account.
+const KEYCHAIN_SERVICE: &str = "dev.neo.apikeys";
+
+fn entry(provider: &str) -> Result<keyring::Entry, NeoError> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &provider.to_ascii_lowercase())
+        .map_err(|e| NeoError::PermissionDenied(format!("Keychain access denied: {e}")))
+}
+
+/// Read a provider's API key from the OS keychain.
+///
+/// Returns `Ok(None)` when no key has been saved, and `Err` when the keychain itself
+/// could not be accessed (locked, permission denied, backend unavailable). On Linux
+/// an unavailable Secret Service falls back to the encrypted key file instead.
+pub fn get(provider: &str) -> Result<Option<String>, NeoError> {
+    match entry(provider)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        // A key saved while the Secret Service was down is still in the file.
+        #[cfg(target_os = "linux")]
+        Err(keyring::Error::NoEntry) => fallback::get(provider),
+        #[cfg(target_os = "linux")]
+        Err(keyring::Error::PlatformFailure(_)) => fallback::get(provider),
+        #[cfg(not(target_os = "linux"))]
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(NeoError::PermissionDenied(format!(
+            "Keychain access denied: {e}"
+        ))),
+    }
+}
+
+/// Save a provider's API key to the OS keychain, replacing any existing value.
+pub fn set(provider: &str, key: &str) -> Result<(), NeoError> {
+    match entry(provider)?.set_password(key) {
+        Ok(()) => Ok(()),
+        #[cfg(target_os = "linux")]
+        Err(keyring::Error::PlatformFailure(_)) => fallback::set(provider, Some(key)),
+        Err(e) => Err(NeoError::Io(format!(
+            "Failed to save API key to keychain: {e}"
+        ))),
+    }
+}
+
+/// Remove a provider's API key from the OS keychain. Removing a missing key is a no-op.
+pub fn clear(provider: &str) -> Result<(), NeoError> {
+    let cleared = match entry(provider)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        #[cfg(target_os = "linux")]
+        Err(keyring::Error::PlatformFailure(_)) => Ok(()),
+        Err(e) => Err(NeoError::Io(format!(
+            "Failed to remove API key from keychain: {e}"
+        ))),
+    };
+    #[cfg(target_os = "linux")]
+    fallback::set(provider, None)?;
+    cleared
+}
+
+/// Remember where the fallback key file lives. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    #[cfg(target_os = "linux")]
+    {
+        use tauri::Manager;
+        if let Ok(dir) = app.path().app_config_dir() {
+            let _ = fallback::FILE.set(dir.join(fallback::FILE_NAME));
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = app;
+}
+
+/// Keys kept in `api-keys.enc` when the Secret Service can't be reached.
+///
+/// The file is a JSON map of provider to key, sealed with XChaCha20-Poly1305 under a
+/// key derived from the machine id, and only the current user may read it (mode
+/// 0600 from the moment it is created).
+///
+/// The machine id is readable by every user of the machine, so the encryption is
+/// obfuscation only: it keeps keys out of plaintext searches and out of backups of
+/// the config dir restored elsewhere, but anyone who can read both the file and the
+/// machine id can decrypt it. The file mode is what keeps other users out, and
+/// unlike the keychain nothing stops other programs running as the same user.
+#[cfg(target_os = "linux")]
+mod fallback {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::{Mutex, Once, OnceLock};
+
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+    use crate::error::NeoError;
+    use crate::memory;
+
+    pub const FILE_NAME: &str = "api-keys.enc";
+    pub static FILE: OnceLock<PathBuf> = OnceLock::new();
+    const MACHINE_ID_FILES: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+    const KEY_CONTEXT: &str = "neo api key fallback file v1";
+    /// Authenticated with the contents, so a file sealed for another purpose is refused.
+    const AAD: &[u8] = b"neo-api-keys";
+    const NONCE_LEN: usize = 24;
+
+    /// Serializes read-modify-write cycles on the file.
+    static WRITING: Mutex<()> = Mutex::new(());
+    static WARNED: Once = Once::new();
+
+    fn path() -> Result<&'static PathBuf, NeoError> {
+        FILE.get().ok_or_else(|| {
+            NeoError::Failed("Keychain is unavailable and no fallback key file is set".to_string())
+        })
+    }
+
+    fn cipher() -> Result<XChaCha20Poly1305, NeoError> {
+        let machine_id = MACHINE_ID_FILES
+            .iter()
+            .find_map(|file| fs::read_to_string(file).ok())
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| {
+                NeoError::Failed(
+                    "Keychain is unavailable and there is no machine id to protect keys with"
+                        .to_string(),
+                )
+            })?;
+        let key = blake3::derive_key(KEY_CONTEXT, machine_id.as_bytes());
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+
+    fn read() -> Result<BTreeMap<String, String>, NeoError> {
+        let path = path()?;
+        let sealed = match fs::read(path) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(NeoError::Io(format!("Failed to read {FILE_NAME}: {e}"))),
+        };
+        if sealed.len() < NONCE_LEN {
+            return Err(NeoError::Failed(format!("{FILE_NAME} is corrupt")));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = cipher()?
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: AAD,
+                },
+            )
+            .map_err(|_| {
+                NeoError::Failed(format!(
+                    "{FILE_NAME} is corrupt or was made on another machine"
+                ))
+            })?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|_| NeoError::Failed(format!("{FILE_NAME} is corrupt")))
+    }
+
+    pub fn get(provider: &str) -> Result<Option<String>, NeoError> {
+        if FILE.get().is_none_or(|path| !path.exists()) {
+            return Ok(None);
+        }
+        Ok(read()?.remove(&provider.to_ascii_lowercase()))
+    }
+
+    /// Save `key` for `provider`, or remove it when `key` is `None`.
+    pub fn set(provider: &str, key: Option<&str>) -> Result<(), NeoError> {
+        let provider = provider.to_ascii_lowercase();
+        if key.is_none() && FILE.get().is_none_or(|path| !path.exists()) {
+            return Ok(());
+        }
+        let _writing = WRITING.lock().unwrap_or_else(|p| p.into_inner());
+        let mut keys = read()?;
+        let path = path()?;
+        match key {
+            Some(key) => {
+                WARNED.call_once(|| {
+                    tracing::warn!(
+                        path = %path.display(),
+                        "Secret Service is unavailable; storing API keys in an encrypted file"
+                    );
+                });
+                keys.insert(provider, key.to_string());
+            }
+            None => {
+                if keys.remove(&provider).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        if keys.is_empty() {
+            return fs::remove_file(path)
+                .map_err(|e| NeoError::Io(format!("Failed to remove {FILE_NAME}: {e}")));
+        }
+
+        let plaintext = serde_json::to_vec(&keys)
+            .map_err(|e| NeoError::Failed(format!("Failed to serialize keys: {e}")))?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher()?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: AAD,
+                },
+            )
+            .map_err(|_| NeoError::Failed("Failed to encrypt API keys".to_string()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| NeoError::Io(format!("Failed to create {}: {e}", dir.display())))?;
+        }
+        memory::write_atomic_private(path, &sealed)
+    }
+}
@@ -0,0 +1,352 @@
+//! Running a small set of read-mostly commands inside a granted workspace, for
+//! shell snippets suggested in conversations.
+
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::error::NeoError;
+use crate::workspaces::WorkspaceGrants;
+
+/// Programs `execute_shell_command` may run. Matched against the program name
+/// exactly; paths are rejected so a workspace can't shadow them.
+const ALLOWED_COMMANDS: &[&str] = &["git", "ls", "find", "rg"];
+
+/// Read-only git subcommands. The subcommand must come first, so no global options
+/// can be passed, and `config` is left out because settings like `core.fsmonitor` or
+/// `alias.*` persist in `.git/config` and run commands on a later call.
+///
+/// `status` and `diff` are left out too: they read working tree files through any
+/// `filter.<driver>.clean` the repository binds in its attributes, and the
+/// `--attr-source` option that would stop that needs git 2.40, newer than the git
+/// shipped with macOS and Debian 12.
+const GIT_SUBCOMMANDS: &[&str] = &["log", "show", "ls-files", "rev-parse"];
+
+/// Subcommands that can print patches, and the options passed right after them so
+/// the `diff.<driver>.command` and `diff.<driver>.textconv` programs a repository
+/// configures aren't run.
+const GIT_PATCH_SUBCOMMANDS: &[&str] = &["log", "show"];
+const GIT_PATCH_OVERRIDES: &[&str] = &["--no-ext-diff", "--no-textconv"];
+
+/// Settings passed ahead of every git subcommand, so git doesn't start the programs
+/// a repository's config can name.
+const GIT_OVERRIDES: &[&str] = &[
+    "--no-pager",
+    "-c",
+    "core.fsmonitor=false",
+    "-c",
+    "core.hooksPath=/dev/null",
+    "-c",
+    "diff.external=",
+];
+
+/// Arguments that would let an allowed program run arbitrary commands, or write or
+/// delete files.
+const BLOCKED_ARGS: &[(&str, &[&str])] = &[
+    (
+        "git",
+        &[
+            "-c",
+            "-C",
+            "--config-env",
+            "--exec-path",
+            "--upload-pack",
+            "--receive-pack",
+            "--output",
+            "--git-dir",
+            "--work-tree",
+            "--ext-diff",
+            "--textconv",
+        ],
+    ),
+    (
+        "find",
+        &[
+            "-exec", "-execdir", "-ok", "-okdir", "-delete", "-fls", "-fprint", "-fprint0",
+            "-fprintf",
+        ],
+    ),
+    ("rg", &["--pre"]),
+];
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+/// Output kept per stream; anything beyond is dropped.
+const MAX_OUTPUT_BYTES: u64 = 1024 * 1024;
+/// How often a running command is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` if the process was ended by a signal.
+    pub exit_code: Option<i32>,
+}
+
+/// Read a child's pipe on its own thread so a chatty process can't block on a full
+/// pipe while we wait for it.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = (&mut pipe).take(MAX_OUTPUT_BYTES).read_to_end(&mut buf);
+            // Keep reading so the process doesn't stall once it outgrows the limit.
+            let _ = io::copy(&mut pipe, &mut io::sink());
+        }
+        buf
+    })
+}
+
+/// Wait for a child to exit, killing it once `timeout` has passed.
+pub fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<ExitStatus, NeoError> {
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(NeoError::Subprocess(format!(
+                    "Command timed out after {} seconds",
+                    timeout.as_secs()
+                )));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                return Err(NeoError::Subprocess(format!(
+                    "Failed to wait for command: {e}"
+                )))
+            }
+        }
+    }
+}
+
+/// Run a command to completion and collect its output, killing it once `timeout` has
+/// passed. Every external program Neo runs goes through this, so a hung tool can't
+/// hang the command that called it.
+pub fn run_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output, NeoError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| NeoError::Subprocess(format!("Failed to run {program}: {e}")))?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let status = wait_with_timeout(&mut child, timeout)?;
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn check_allowed(command: &str, args: &[String]) -> Result<(), NeoError> {
+    if !ALLOWED_COMMANDS.contains(&command) {
+        return Err(NeoError::InvalidInput(format!(
+            "Command not allowed: {command} (allowed: {})",
+            ALLOWED_COMMANDS.join(", ")
+        )));
+    }
+    if command == "git"
+        && !args
+            .first()
+            .is_some_and(|sub| GIT_SUBCOMMANDS.contains(&sub.as_str()))
+    {
+        return Err(NeoError::InvalidInput(format!(
+            "git must be run with one of: {}",
+            GIT_SUBCOMMANDS.join(", ")
+        )));
+    }
+    let blocked = BLOCKED_ARGS
+        .iter()
+        .find(|(program, _)| *program == command)
+        .map_or(&[][..], |(_, blocked)| *blocked);
+    for arg in args {
+        let flag = arg.split('=').next().unwrap_or(arg);
+        // Single-letter flags also take their value attached, as in `-cfoo`.
+        let denied = blocked
+            .iter()
+            .any(|b| flag == *b || (b.len() == 2 && flag.starts_with(b)));
+        if denied {
+            return Err(NeoError::InvalidInput(format!(
+                "Argument not allowed for {command}: {arg}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The process for an allowed command, with git's config overrides in place.
+fn build(command: &str, args: &[String], root: &Path) -> Command {
+    let mut process = Command::new(command);
+    match args.split_first() {
+        Some((subcommand, rest)) if command == "git" => {
+            process
+                .args(GIT_OVERRIDES)
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                .arg(subcommand);
+            if GIT_PATCH_SUBCOMMANDS.contains(&subcommand.as_str()) {
+                process.args(GIT_PATCH_OVERRIDES);
+            }
+            process.args(rest);
+        }
+        _ => {
+            process.args(args);
+        }
+    }
+    process.current_dir(root);
+    process
+}
+
+/// Run an allowlisted command (`git`, `ls`, `find` or `rg`) with the workspace root as
+/// its working directory. git is limited to read-only subcommands.
+///
+/// The program is run directly, not through a shell, and arguments that would let it
+/// run other programs are rejected. Commands are killed after 30 seconds.
+#[tauri::command]
+pub async fn execute_shell_command(
+    app: tauri::AppHandle,
+    workspace_path: String,
+    command: String,
+    args: Option<Vec<String>>,
+) -> Result<CommandOutput, NeoError> {
+    let args = args.unwrap_or_default();
+    check_allowed(&command, &args)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let grants: State<'_, WorkspaceGrants> = app.state();
+        let root = grants.require(&workspace_path)?;
+
+        let output = run_with_timeout(&mut build(&command, &args, &root), COMMAND_TIMEOUT)?;
+
+        Ok(CommandOutput {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            exit_code: output.status.code(),
+        })
+    })
+    .await
+    .map_err(|e| NeoError::Subprocess(format!("Failed to run command: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(command: &str, args: &[&str]) -> Result<(), NeoError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        check_allowed(command, &args)
+    }
+
+    #[test]
+    fn allows_read_only_commands() {
+        assert!(check("git", &["log", "--oneline", "-n", "5"]).is_ok());
+        assert!(check("git", &["show", "HEAD"]).is_ok());
+        assert!(check("ls", &["-la"]).is_ok());
+        assert!(check("find", &[".", "-name", "*.rs"]).is_ok());
+        assert!(check("rg", &["TODO"]).is_ok());
+    }
+
+    #[test]
+    fn rejects_other_programs_and_paths() {
+        assert!(check("sh", &["-c", "true"]).is_err());
+        assert!(check("/usr/bin/git", &["status"]).is_err());
+        assert!(check("./git", &["status"]).is_err());
+    }
+
+    #[test]
+    fn limits_git_to_read_only_subcommands() {
+        assert!(check("git", &[]).is_err());
+        assert!(check("git", &["config", "core.fsmonitor", "touch pwned"]).is_err());
+        assert!(check("git", &["commit", "-m", "x"]).is_err());
+        assert!(check("git", &["status"]).is_err());
+        assert!(check("git", &["diff"]).is_err());
+        assert!(check("git", &["-C", "/", "status"]).is_err());
+        assert!(check("git", &["--git-dir=/tmp/x", "status"]).is_err());
+    }
+
+    #[test]
+    fn rejects_arguments_that_run_or_write() {
+        assert!(check("git", &["log", "-p", "--output=/tmp/x"]).is_err());
+        assert!(check("git", &["show", "--ext-diff"]).is_err());
+        assert!(check("git", &["show", "--textconv"]).is_err());
+        assert!(check("git", &["log", "-cfoo=bar"]).is_err());
+        assert!(check("git", &["log", "--work-tree", "/"]).is_err());
+        assert!(check("find", &[".", "-exec", "rm", "{}", ";"]).is_err());
+        assert!(check("find", &[".", "-delete"]).is_err());
+        assert!(check("rg", &["--pre=sh", "x"]).is_err());
+    }
+
+    /// A repository whose one file is bound to a clean filter and a diff driver that
+    /// each create `marker` when run.
+    #[cfg(unix)]
+    fn repo_with_drivers(marker: &Path) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("neo-shell-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=Neo", "-c", "user.email=neo@example.com"])
+                .args(args)
+                .current_dir(&root)
+                .stdout(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?} failed");
+        };
+        let run_marker = format!("touch '{}'; cat", marker.display());
+        git(&["init", "-q"]);
+        git(&["config", "filter.neo.clean", &run_marker]);
+        git(&["config", "diff.neo.textconv", &run_marker]);
+        git(&["config", "diff.neo.command", &run_marker]);
+        std::fs::write(root.join("notes.txt"), "one\n").unwrap();
+        git(&["add", "notes.txt"]);
+        git(&["commit", "-q", "-m", "first"]);
+        std::fs::write(root.join("notes.txt"), "two\n").unwrap();
+        git(&["commit", "-q", "-a", "-m", "second"]);
+        std::fs::write(root.join(".gitattributes"), "*.txt filter=neo diff=neo\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "three\n").unwrap();
+        let _ = std::fs::remove_file(marker);
+        root
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_no_drivers_a_repository_configures() {
+        let marker = std::env::temp_dir().join(format!("neo-shell-ran-{}", std::process::id()));
+        let root = repo_with_drivers(&marker);
+
+        // Without the overrides, showing a patch runs the repository's diff driver.
+        Command::new("git")
+            .args(["--no-pager", "show"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(marker.exists());
+        std::fs::remove_file(&marker).unwrap();
+
+        for args in [
+            &["log", "-p"][..],
+            &["show"],
+            &["show", "HEAD~1", "--stat"],
+            &["ls-files", "-m"],
+        ] {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            check_allowed("git", &args).unwrap();
+            let output =
+                run_with_timeout(&mut build("git", &args, &root), COMMAND_TIMEOUT).unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+            assert!(!marker.exists(), "git {args:?} ran a driver");
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
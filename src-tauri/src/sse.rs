@@ -0,0 +1,205 @@
+//! Parsing of Server-Sent Events, the format OpenRouter and Gemini stream responses in.
+
+use serde::Serialize;
+
+use crate::error::NeoError;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseEvent {
+    /// The `event:` field; `None` for the default `message` type.
+    pub event: Option<String>,
+    /// `data:` lines joined with newlines.
+    pub data: String,
+    /// The last `id:` seen in the chunk up to this event.
+    pub id: Option<String>,
+}
+
+/// Parse events per the WHATWG event stream rules: lines end in CRLF, LF or CR,
+/// repeated `data:` lines are joined, `:` lines are comments, a single space after
+/// the colon is dropped, and an event is dispatched at each blank line unless it has
+/// no data. `retry:` and unknown fields are ignored.
+pub fn parse(raw: &str) -> Vec<SseEvent> {
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    let mut events = Vec::new();
+    let mut event_type = String::new();
+    let mut data = String::new();
+    let mut last_id: Option<String> = None;
+
+    let normalized = raw.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines = normalized.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        // Text after the last line break is an incomplete line, not an empty one.
+        if lines.peek().is_none() {
+            break;
+        }
+        if line.is_empty() {
+            if !data.is_empty() {
+                data.pop();
+                events.push(SseEvent {
+                    event: (!event_type.is_empty()).then(|| event_type.clone()),
+                    data: std::mem::take(&mut data),
+                    id: last_id.clone(),
+                });
+            }
+            event_type.clear();
+            continue;
+        }
+        if line.starts_with(':') {
+            continue;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => event_type = value.to_string(),
+            "data" => {
+                data.push_str(value);
+                data.push('\n');
+            }
+            "id" if !value.contains('\0') => last_id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Incremental parser for a stream arriving in arbitrary byte chunks.
+#[derive(Default)]
+pub struct SseDecoder {
+    /// Bytes after the last complete event.
+    pending: Vec<u8>,
+}
+
+/// The end of the last blank line in `bytes`, which start at the beginning of a line.
+/// Line endings may be mixed. A CR at the very end counts as a whole line ending; if
+/// an LF follows in the next chunk, it reads as one more blank line, which dispatches
+/// nothing.
+fn last_blank_line_end(bytes: &[u8]) -> Option<usize> {
+    let mut end = None;
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => 2,
+            b'\r' | b'\n' => 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if i == line_start {
+            end = Some(i + len);
+        }
+        i += len;
+        line_start = i;
+    }
+    end
+}
+
+impl SseDecoder {
+    /// Add a chunk and return the events it completes. A chunk may end in the middle
+    /// of a line or a UTF-8 character; the rest is kept for the next one.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.pending.extend_from_slice(chunk);
+        let Some(end) = last_blank_line_end(&self.pending) else {
+            return Vec::new();
+        };
+        let complete: Vec<u8> = self.pending.drain(..end).collect();
+        parse(&String::from_utf8_lossy(&complete))
+    }
+}
+
+/// Parse a chunk of a Server-Sent Events stream into its events.
+///
+/// Only complete events, ended by a blank line, are returned; keep any text after the
+/// last blank line and prepend it to the next chunk.
+#[tauri::command]
+pub fn parse_sse_chunk(raw: String) -> Result<Vec<SseEvent>, NeoError> {
+    Ok(parse(&raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(events: &[SseEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.data.as_str()).collect()
+    }
+
+    #[test]
+    fn joins_data_lines_and_drops_one_space() {
+        let events = parse("data: a\ndata:  b\ndata\n\n");
+        assert_eq!(data(&events), ["a\n b\n"]);
+    }
+
+    #[test]
+    fn accepts_every_line_ending() {
+        let events = parse("\u{feff}data: crlf\r\n\r\ndata: cr\r\rdata: lf\n\n");
+        assert_eq!(data(&events), ["crlf", "cr", "lf"]);
+    }
+
+    #[test]
+    fn skips_comments_and_events_without_data() {
+        let events = parse(": keep-alive\n\nevent: ping\n\nretry: 10\nfoo: bar\ndata: x\n\n");
+        assert_eq!(data(&events), ["x"]);
+        assert_eq!(events[0].event, None);
+    }
+
+    #[test]
+    fn keeps_event_type_per_event_and_id_across_events() {
+        let events = parse("event: delta\nid: 7\ndata: a\n\ndata: b\n\nid: bad\0\ndata: c\n\n");
+        assert_eq!(events[0].event.as_deref(), Some("delta"));
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].id.as_deref(), Some("7"));
+        assert_eq!(events[2].id.as_deref(), Some("7"));
+    }
+
+    fn decode(chunks: &[&[u8]]) -> Vec<String> {
+        let mut decoder = SseDecoder::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| decoder.push(chunk))
+            .map(|event| event.data)
+            .collect()
+    }
+
+    #[test]
+    fn decodes_events_split_anywhere() {
+        let stream = "data: héllo\r\n\r\ndata: wörld\n\n".as_bytes();
+        let whole = decode(&[stream]);
+        assert_eq!(whole, ["héllo", "wörld"]);
+        for at in 1..stream.len() {
+            assert_eq!(
+                decode(&[&stream[..at], &stream[at..]]),
+                whole,
+                "split at {at}"
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_mixed_line_endings() {
+        assert_eq!(decode(&[b"data: a\n\r\ndata: b\r\n\n"]), ["a", "b"]);
+        assert_eq!(
+            decode(&[b"data: a\r", b"\rdata: b\r\n", b"\r\n"]),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn keeps_an_incomplete_event_pending() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: a\n").is_empty());
+        assert!(decoder.push(b"data: b").is_empty());
+        assert_eq!(data(&decoder.push(b"\n\n")), ["a\nb"]);
+    }
+
+    #[test]
+    fn leaves_an_unterminated_event_out() {
+        assert_eq!(data(&parse("data: done\n\ndata: partial\n")), ["done"]);
+        assert!(parse("data: partial").is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Size and file-count reporting for a workspace and its `.neomemory/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::State;
+use tauri_plugin_fs::FsExt;
+
+use crate::error::NeoError;
+use crate::memory::{self, CONVERSATIONS_DIR, MEMORY_DIR};
+use crate::quota;
+use crate::workspaces::WorkspaceGrants;
+
+/// Directories skipped when counting workspace files.
+const SKIP_DIRS: &[&str] = &[".git", "node_modules"];
+/// Number of largest `.neomemory/` files reported.
+const LARGEST_FILES: usize = 10;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryFile {
+    /// Path relative to `.neomemory/`.
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStats {
+    /// Files in the workspace, excluding `.neomemory/` and skipped directories.
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub memory_bytes: u64,
+    /// Bytes of `.neomemory/` counted towards the memory quota.
+    pub quota_used_bytes: u64,
+    /// The workspace's memory quota in bytes.
+    pub quota_bytes: u64,
+    pub conversation_count: u64,
+    pub largest_memory_files: Vec<MemoryFile>,
+    /// The walk stopped at the depth or entry limit, so the counts are lower bounds.
+    pub truncated: bool,
+}
+
+struct Walk<'a> {
+    scope: &'a tauri::fs::Scope,
+    max_depth: Option<usize>,
+    max_entries: Option<usize>,
+    entries: usize,
+    truncated: bool,
+}
+
+impl Walk<'_> {
+    /// Visit every file under `root`, calling `visit` with its path and size.
+    /// Symlinks and forbidden paths are skipped.
+    fn files(&mut self, root: &Path, skip: &[&str], mut visit: impl FnMut(&Path, u64)) {
+        let mut pending: Vec<(PathBuf, usize)> = vec![(root.to_path_buf(), 0)];
+
+        while let Some((dir, depth)) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if self.max_entries.is_some_and(|max| self.entries >= max) {
+                    self.truncated = true;
+                    return;
+                }
+                self.entries += 1;
+
+                let path = entry.path();
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if self.scope.is_forbidden(&path) {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    let name = entry.file_name();
+                    if skip.iter().any(|s| name == *s) {
+                        continue;
+                    }
+                    if self.max_depth.is_some_and(|max| depth + 1 > max) {
+                        self.truncated = true;
+                        continue;
+                    }
+                    pending.push((path, depth + 1));
+                } else if file_type.is_file() {
+                    let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    visit(&path, bytes);
+                }
+            }
+        }
+    }
+}
+
+fn collect(
+    app: &tauri::AppHandle,
+    root: &Path,
+    max_depth: Option<usize>,
+    max_entries: Option<usize>,
+) -> WorkspaceStats {
+    let scope = app.fs_scope();
+    let mut walk = Walk {
+        scope: &scope,
+        max_depth,
+        max_entries,
+        entries: 0,
+        truncated: false,
+    };
+    let mut stats = WorkspaceStats::default();
+
+    let mut skip = SKIP_DIRS.to_vec();
+    skip.push(MEMORY_DIR);
+    walk.files(root, &skip, |_, bytes| {
+        stats.file_count += 1;
+        stats.total_bytes += bytes;
+    });
+
+    let memory = memory::memory_dir(root);
+    let conversations = memory.join(CONVERSATIONS_DIR);
+    let mut memory_files = Vec::new();
+    walk.files(&memory, &[], |path, bytes| {
+        stats.memory_bytes += bytes;
+        if path.starts_with(&conversations) {
+            stats.conversation_count += 1;
+        }
+        let relative = path.strip_prefix(&memory).unwrap_or(path);
+        memory_files.push(MemoryFile {
+            path: relative.to_string_lossy().into_owned(),
+            bytes,
+        });
+    });
+
+    stats.quota_used_bytes = quota::usage(root);
+    stats.quota_bytes = quota::load(root).max_bytes;
+
+    memory_files.sort_by_key(|f| std::cmp::Reverse(f.bytes));
+    memory_files.truncate(LARGEST_FILES);
+    stats.largest_memory_files = memory_files;
+    stats.truncated = walk.truncated;
+    stats
+}
+
+/// Report file counts and sizes for a granted workspace and its `.neomemory/`.
+///
+/// `.git` and `node_modules` are skipped. `maxDepth` and `maxEntries` bound the walk
+/// on huge repositories; hitting either sets `truncated`. The walk runs on a blocking
+/// thread so the UI stays responsive. The memory quota and its usage are reported
+/// alongside, for a storage meter.
+#[tauri::command]
+pub async fn workspace_stats(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    path: String,
+    max_depth: Option<usize>,
+    max_entries: Option<usize>,
+) -> Result<WorkspaceStats, NeoError> {
+    let root = grants.require(&path)?;
+    tauri::async_runtime::spawn_blocking(move || collect(&app, &root, max_depth, max_entries))
+        .await
+        .map_err(|e| NeoError::Failed(format!("Failed to collect workspace stats: {e}")))
+}
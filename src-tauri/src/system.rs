@@ -0,0 +1,79 @@
+//! Facts about the user's machine, for prompt context and bug reports.
+
+use std::env::consts;
+
+use serde::Serialize;
+use sysinfo::{CpuRefreshKind, System};
+
+use crate::error::NeoError;
+
+const UNKNOWN: &str = "unknown";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub os: String,
+    pub os_version: String,
+    pub arch: String,
+    pub cpu_count: usize,
+    pub total_memory_mb: u64,
+    pub hostname: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+    pub identifier: String,
+    pub tauri_version: String,
+    pub os: String,
+    pub os_version: String,
+    pub arch: String,
+    /// Whether this is a debug build, as opposed to a release build.
+    pub debug: bool,
+}
+
+fn or_unknown(value: Option<String>) -> String {
+    value
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
+
+/// Describe the OS, architecture, CPUs and memory of this machine.
+///
+/// Never fails: fields that can't be determined are `"unknown"`, or 0 for numbers.
+#[tauri::command]
+pub fn get_system_info() -> Result<SystemInfo, NeoError> {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.refresh_cpu_list(CpuRefreshKind::nothing());
+
+    Ok(SystemInfo {
+        os: or_unknown(System::name()),
+        os_version: or_unknown(System::long_os_version().or_else(System::os_version)),
+        arch: or_unknown(Some(System::cpu_arch())),
+        cpu_count: system.cpus().len(),
+        total_memory_mb: system.total_memory() / (1024 * 1024),
+        hostname: or_unknown(System::host_name()),
+    })
+}
+
+/// Describe this build of Neo and the OS it runs on, for an About panel or a bug
+/// report. Contains no settings or secrets.
+#[tauri::command]
+pub fn get_app_info(app: tauri::AppHandle) -> Result<AppInfo, NeoError> {
+    let package = app.package_info();
+    let config = app.config();
+    let name = config.product_name.as_ref().unwrap_or(&package.name);
+    Ok(AppInfo {
+        name: name.clone(),
+        version: package.version.to_string(),
+        identifier: config.identifier.clone(),
+        tauri_version: tauri::VERSION.to_string(),
+        os: consts::OS.to_string(),
+        os_version: or_unknown(System::long_os_version().or_else(System::os_version)),
+        arch: consts::ARCH.to_string(),
+        debug: cfg!(debug_assertions),
+    })
+}
@@ -0,0 +1,45 @@
+//! Token count estimates, so the UI can warn before a request hits a context limit.
+
+use tiktoken_rs::tokenizer::{self, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use crate::error::NeoError;
+
+/// Average characters per token for English prose on most model families.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// The OpenAI tokenizer for a model id, with or without OpenRouter's `openai/` vendor
+/// prefix and `:variant` suffix. `None` for every other vendor.
+fn openai_tokenizer(model: &str) -> Option<&'static CoreBPE> {
+    let model = model.split(':').next().unwrap_or(model);
+    let name = match model.split_once('/') {
+        Some((vendor, name)) if vendor.eq_ignore_ascii_case("openai") => name,
+        Some(_) => return None,
+        None => model,
+    };
+    let tokenizer = tokenizer::get_tokenizer(&name.to_ascii_lowercase())?;
+    Some(match tokenizer {
+        Tokenizer::O200kHarmony => tiktoken_rs::o200k_harmony_singleton(),
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        Tokenizer::P50kBase => tiktoken_rs::p50k_base_singleton(),
+        Tokenizer::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    })
+}
+
+/// Estimate how many tokens `text` takes up for `model`, using the same model ids as
+/// the rest of Neo (such as `openai/gpt-4o` or `google/gemini-2.5-flash`).
+///
+/// OpenAI models are counted with their real tokenizer, which is exact for the text
+/// itself but ignores the few tokens of per-message overhead. Every other model is
+/// estimated at one token per four characters, which is usually within 10% for
+/// English prose and less accurate for code or other languages.
+#[tauri::command]
+pub fn count_tokens_estimate(text: String, model: String) -> Result<u32, NeoError> {
+    let count = match openai_tokenizer(&model) {
+        Some(bpe) => bpe.encode_ordinary(&text).len(),
+        None => text.chars().count().div_ceil(CHARS_PER_TOKEN),
+    };
+    Ok(u32::try_from(count).unwrap_or(u32::MAX))
+}
@@ -0,0 +1,304 @@
+//! File watching for granted workspaces, so the frontend can tell when its memory
+//! of a project has gone stale.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use glob::Pattern;
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{Emitter, State};
+
+use crate::denylist;
+use crate::error::NeoError;
+use crate::lock;
+use crate::logging;
+use crate::memory::MEMORY_DIR;
+use crate::workspaces::{self, WorkspaceGrants};
+
+/// Event emitted with a batch of changes to a workspace's files.
+pub const FILE_CHANGED_EVENT: &str = "workspace://file-changed";
+/// Event emitted once per changed file of a batch, with its latest change.
+pub const CHANGED_EVENT: &str = "workspace://changed";
+/// Event emitted once per changed path inside `.neomemory/`.
+pub const MEMORY_CHANGED_EVENT: &str = "workspace-changed";
+
+/// A batch is flushed once no new change has arrived for this long...
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// ...or once it has been collecting for this long, whichever comes first.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// One of `created`, `modified`, `removed` or `renamed`.
+    pub kind: &'static str,
+    /// Unix timestamp in milliseconds.
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChangeBatch {
+    pub workspace: String,
+    pub changes: Vec<FileChange>,
+}
+
+/// Payload of `CHANGED_EVENT`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceChange {
+    pub workspace: String,
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// One of `created`, `modified` or `removed`.
+    pub kind: &'static str,
+}
+
+/// A change inside `.neomemory/`, for example from `git pull` or another Neo window.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryChange {
+    pub workspace: String,
+    /// One of `created`, `modified` or `deleted`.
+    pub kind: &'static str,
+    /// Path relative to the workspace root.
+    pub path: String,
+}
+
+/// Active watchers keyed by canonical workspace path. Dropping a watcher stops it
+/// and ends its debounce thread.
+#[derive(Default)]
+pub struct WorkspaceWatchers(Mutex<HashMap<PathBuf, RecommendedWatcher>>);
+
+impl WorkspaceWatchers {
+    /// Stop every watcher.
+    pub fn clear(&self) {
+        if let Ok(mut watchers) = self.0.lock() {
+            watchers.clear();
+        }
+    }
+}
+
+fn change_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(ModifyKind::Name(_)) => Some("renamed"),
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        _ => None,
+    }
+}
+
+/// Kind reported for a `.neomemory/` change. Renames are reported as the path
+/// appearing or disappearing.
+fn memory_change_kind(kind: &EventKind, path: &Path) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(ModifyKind::Name(_)) if path.exists() => Some("created"),
+        EventKind::Modify(ModifyKind::Name(_)) | EventKind::Remove(_) => Some("deleted"),
+        EventKind::Modify(_) => Some("modified"),
+        _ => None,
+    }
+}
+
+/// Changes collected since the last flush.
+#[derive(Default)]
+struct Pending {
+    /// The workspace's deny-list; changes to matching files aren't reported.
+    denied: Vec<Pattern>,
+    files: Vec<FileChange>,
+    /// Latest kind per path, in first-seen order.
+    memory: Vec<(String, &'static str)>,
+}
+
+impl Pending {
+    fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.memory.is_empty()
+    }
+
+    fn add(&mut self, root: &Path, event: &notify::Event) {
+        let timestamp = workspaces::now_millis();
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative_str = relative.to_string_lossy().into_owned();
+
+            if relative.starts_with(MEMORY_DIR) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if lock::is_lock_file(&name) || logging::is_log_file(&name) {
+                    continue;
+                }
+                let Some(kind) = memory_change_kind(&event.kind, path) else {
+                    continue;
+                };
+                match self.memory.iter_mut().find(|(p, _)| *p == relative_str) {
+                    Some(entry) => entry.1 = kind,
+                    None => self.memory.push((relative_str, kind)),
+                }
+            } else if denylist::is_denied(&self.denied, relative) {
+                continue;
+            } else if let Some(kind) = change_kind(&event.kind) {
+                self.files.push(FileChange {
+                    path: relative_str,
+                    kind,
+                    timestamp,
+                });
+            }
+        }
+    }
+
+    /// The latest change per file, in first-seen order. Renames are reported as the
+    /// path appearing or disappearing.
+    fn latest(&self, root: &Path) -> Vec<(String, &'static str)> {
+        let mut latest: Vec<(String, &'static str)> = Vec::new();
+        for change in &self.files {
+            let kind = match change.kind {
+                "renamed" if root.join(&change.path).exists() => "created",
+                "renamed" => "removed",
+                kind => kind,
+            };
+            match latest.iter_mut().find(|(p, _)| *p == change.path) {
+                Some(entry) => entry.1 = kind,
+                None => latest.push((change.path.clone(), kind)),
+            }
+        }
+        latest
+    }
+
+    fn flush(&mut self, app: &tauri::AppHandle, root: &Path, workspace: &str) {
+        if !self.files.is_empty() {
+            for (path, kind) in self.latest(root) {
+                let change = WorkspaceChange {
+                    workspace: workspace.to_string(),
+                    path,
+                    kind,
+                };
+                let _ = app.emit(CHANGED_EVENT, change);
+            }
+            let batch = FileChangeBatch {
+                workspace: workspace.to_string(),
+                changes: std::mem::take(&mut self.files),
+            };
+            let _ = app.emit(FILE_CHANGED_EVENT, batch);
+        }
+        for (path, kind) in std::mem::take(&mut self.memory) {
+            let change = MemoryChange {
+                workspace: workspace.to_string(),
+                kind,
+                path,
+            };
+            let _ = app.emit(MEMORY_CHANGED_EVENT, change);
+        }
+    }
+}
+
+/// Collect changes from the watcher and emit them in debounced batches. Returns when
+/// the watcher is dropped.
+fn debounce(
+    app: tauri::AppHandle,
+    root: PathBuf,
+    denied: Vec<Pattern>,
+    events: mpsc::Receiver<notify::Event>,
+) {
+    let workspace = root.to_string_lossy().into_owned();
+    let mut pending = Pending {
+        denied,
+        ..Pending::default()
+    };
+    let mut batch_started = Instant::now();
+
+    loop {
+        let timeout = if pending.is_empty() {
+            Duration::from_secs(3600)
+        } else {
+            DEBOUNCE.min(MAX_BATCH_DELAY.saturating_sub(batch_started.elapsed()))
+        };
+
+        let disconnected = match events.recv_timeout(timeout) {
+            Ok(event) => {
+                if pending.is_empty() {
+                    batch_started = Instant::now();
+                }
+                pending.add(&root, &event);
+                if batch_started.elapsed() < MAX_BATCH_DELAY {
+                    continue;
+                }
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+
+        pending.flush(&app, &root, &workspace);
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// Start watching a granted workspace.
+///
+/// Changes to the workspace's files are emitted in batches as
+/// `workspace://file-changed`, and once per changed file as `workspace://changed`.
+/// Files on the workspace's deny-list, as it was when watching started, are left
+/// out. Changes inside `.neomemory/` are emitted separately, one `workspace-changed`
+/// event per path, so listeners that reload memory don't react to every file in the
+/// project. Watching an already watched workspace is a no-op.
+#[tauri::command]
+pub fn watch_workspace(
+    app: tauri::AppHandle,
+    grants: State<'_, WorkspaceGrants>,
+    watchers: State<'_, WorkspaceWatchers>,
+    path: String,
+) -> Result<(), NeoError> {
+    let root = grants.require(&path)?;
+    let denied = denylist::compile(&workspaces::denylist_for(&app, &root))?;
+
+    let mut active = watchers
+        .0
+        .lock()
+        .map_err(|_| NeoError::Failed("Watcher state is unavailable".to_string()))?;
+    if active.contains_key(&root) {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            let _ = tx.send(event);
+        }
+    })
+    .map_err(|e| NeoError::Failed(format!("Failed to start watcher: {e}")))?;
+    let mode = if grants.covers_subfolders(&root) {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&root, mode)
+        .map_err(|e| NeoError::Io(format!("Failed to watch workspace: {e}")))?;
+
+    let thread_root = root.clone();
+    thread::spawn(move || debounce(app, thread_root, denied, rx));
+    active.insert(root, watcher);
+    Ok(())
+}
+
+/// Stop watching a workspace. Unwatching a workspace that isn't watched is a no-op.
+#[tauri::command]
+pub fn unwatch_workspace(watchers: State<'_, WorkspaceWatchers>, path: String) {
+    let raw = PathBuf::from(path);
+    let root = raw.canonicalize().unwrap_or(raw);
+    if let Ok(mut active) = watchers.0.lock() {
+        active.remove(&root);
+    }
+}
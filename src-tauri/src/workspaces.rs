@@ -0,0 +1,788 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use tauri_plugin_fs::FsExt;
+
+use crate::denylist;
+use crate::error::NeoError;
+use crate::memory::{self, ProviderConfig};
+use crate::paths;
+
+/// File under the app data dir that holds the workspace registry.
+const WORKSPACES_FILE: &str = "workspaces.json";
+
+/// Serializes read-modify-write cycles on the registry, so concurrent commands don't
+/// drop each other's changes.
+static UPDATING: Mutex<()> = Mutex::new(());
+
+/// A registered workspace, persisted across launches.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEntry {
+    /// Canonical path of the workspace root.
+    pub path: PathBuf,
+    pub name: String,
+    /// Unix timestamp in milliseconds.
+    pub last_opened: u64,
+    /// Glob patterns, relative to the workspace root, that stay forbidden.
+    #[serde(default = "denylist::default_patterns")]
+    pub denylist: Vec<String>,
+    /// Whether subfolders are granted too. Registries written before this option
+    /// only held recursive grants.
+    #[serde(default = "default_recursive")]
+    pub recursive: bool,
+    /// ID from the workspace's manifest, once `.neomemory/` has been initialized.
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Imported from another machine and not yet granted here; it isn't restored on
+    /// launch until the user grants the folder.
+    #[serde(default)]
+    pub pending: bool,
+    /// Provider settings from an import, written to `config.json` once the folder is
+    /// granted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_config: Option<ProviderConfig>,
+    /// The directory no longer exists. Recomputed on every load.
+    #[serde(default, skip_deserializing)]
+    pub stale: bool,
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+fn store_path(app: &tauri::AppHandle) -> Result<PathBuf, NeoError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| NeoError::Failed(format!("Failed to resolve app data dir: {e}")))?;
+    Ok(dir.join(WORKSPACES_FILE))
+}
+
+/// Default display name for a workspace: its folder name.
+fn default_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+/// Load the registry, most recently opened first. A missing or unreadable file is
+/// treated as empty.
+///
+/// Entries whose directory has been deleted are kept but marked stale, and entries
+/// that now resolve to the same canonical path are merged, keeping the most recent.
+pub fn load(app: &tauri::AppHandle) -> Vec<WorkspaceEntry> {
+    let Ok(path) = store_path(app) else {
+        return Vec::new();
+    };
+    let mut entries = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Vec<WorkspaceEntry>>(&content).ok())
+        .unwrap_or_default();
+
+    for entry in &mut entries {
+        match entry.path.canonicalize() {
+            Ok(canonical) if canonical.is_dir() => {
+                entry.path = canonical;
+                entry.stale = false;
+            }
+            _ => entry.stale = true,
+        }
+    }
+
+    entries.sort_by_key(|e| Reverse(e.last_opened));
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(entry.path.clone()));
+    entries
+}
+
+/// Overwrite the persisted registry, keeping it sorted by recency. The file is
+/// replaced atomically, so a crash never leaves it truncated.
+fn save(app: &tauri::AppHandle, entries: &[WorkspaceEntry]) -> Result<(), NeoError> {
+    let path = store_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| NeoError::Io(format!("Failed to create app data dir: {e}")))?;
+    }
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|e| Reverse(e.last_opened));
+    let content = serde_json::to_string_pretty(&entries)
+        .map_err(|e| NeoError::Failed(format!("Failed to serialize workspaces: {e}")))?;
+    memory::write_atomic(&path, content.as_bytes())
+}
+
+/// Load the registry, change it and save it, holding `UPDATING` throughout. Every
+/// write to the registry goes through here. Nothing is saved if `change` fails.
+pub fn update<T>(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&mut Vec<WorkspaceEntry>) -> Result<T, NeoError>,
+) -> Result<T, NeoError> {
+    let _updating = UPDATING.lock().unwrap_or_else(|p| p.into_inner());
+    let mut entries = load(app);
+    let changed = change(&mut entries)?;
+    save(app, &entries)?;
+    Ok(changed)
+}
+
+/// Add or update a registry entry for a canonical path and mark it as just opened.
+///
+/// `name` replaces the existing display name when given; new entries default to the
+/// folder name. `recursive` records how the folder was granted. A pending imported
+/// entry is confirmed, and its imported provider settings applied.
+pub fn upsert(
+    app: &tauri::AppHandle,
+    canonical: &Path,
+    name: Option<String>,
+    recursive: bool,
+) -> Result<WorkspaceEntry, NeoError> {
+    update(app, |entries| {
+        let entry = match entries.iter_mut().find(|e| e.path == canonical) {
+            Some(entry) => {
+                if let Some(name) = name {
+                    entry.name = name;
+                }
+                entry.recursive = recursive;
+                entry.last_opened = now_millis();
+                entry.pending = false;
+                if let Some(config) = entry.pending_config.take() {
+                    memory::save_provider_config(canonical, &config)?;
+                }
+                entry.clone()
+            }
+            None => {
+                let entry = WorkspaceEntry {
+                    path: canonical.to_path_buf(),
+                    name: name.unwrap_or_else(|| default_name(canonical)),
+                    last_opened: now_millis(),
+                    denylist: denylist::default_patterns(),
+                    recursive,
+                    workspace_id: None,
+                    pending: false,
+                    pending_config: None,
+                    stale: false,
+                };
+                entries.push(entry.clone());
+                entry
+            }
+        };
+        Ok(entry)
+    })
+}
+
+/// Record a canonical workspace path so it is re-granted on the next launch.
+pub fn remember(app: &tauri::AppHandle, canonical: &Path, recursive: bool) -> Result<(), NeoError> {
+    upsert(app, canonical, None, recursive).map(|_| ())
+}
+
+/// Drop a workspace path from the registry.
+///
+/// Returns `false` if the path was not registered.
+pub fn forget(app: &tauri::AppHandle, path: &Path) -> Result<bool, NeoError> {
+    update(app, |entries| {
+        let before = entries.len();
+        entries.retain(|e| e.path != path);
+        Ok(entries.len() != before)
+    })
+}
+
+/// Store the manifest's workspace ID on a registered workspace. Unregistered paths
+/// are ignored.
+pub fn set_workspace_id(
+    app: &tauri::AppHandle,
+    canonical: &Path,
+    id: &str,
+) -> Result<(), NeoError> {
+    update(app, |entries| {
+        if let Some(entry) = entries.iter_mut().find(|e| e.path == canonical) {
+            entry.workspace_id = Some(id.to_string());
+        }
+        Ok(())
+    })
+}
+
+/// Point a registry entry at the folder it was moved to, keeping its name,
+/// deny-list and grant options. An entry already registered for `new` is replaced.
+pub fn relink(app: &tauri::AppHandle, old: &Path, new: &Path) -> Result<WorkspaceEntry, NeoError> {
+    update(app, |entries| {
+        entries.retain(|e| e.path != new);
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.path == old)
+            .ok_or_else(|| NeoError::NotFound("Workspace is not registered".to_string()))?;
+        entry.path = new.to_path_buf();
+        entry.stale = false;
+        entry.last_opened = now_millis();
+        Ok(entry.clone())
+    })
+}
+
+/// Deny-list for a workspace: its saved list, or the defaults if it isn't registered.
+pub fn denylist_for(app: &tauri::AppHandle, canonical: &Path) -> Vec<String> {
+    load(app)
+        .into_iter()
+        .find(|e| e.path == canonical)
+        .map(|e| e.denylist)
+        .unwrap_or_else(denylist::default_patterns)
+}
+
+/// Canonicalize a user-supplied path, falling back to the raw path so stale
+/// (deleted) workspaces can still be addressed.
+pub fn resolve(path: String) -> PathBuf {
+    let raw = PathBuf::from(path);
+    raw.canonicalize().unwrap_or(raw)
+}
+
+/// Event emitted by `allow_workspace_dir` once a folder has been added to the scope.
+pub const WORKSPACE_ALLOWED_EVENT: &str = "workspace-allowed";
+
+/// Payload of `WORKSPACE_ALLOWED_EVENT`.
+#[derive(Clone, Serialize)]
+pub struct WorkspaceAllowed {
+    /// Canonical path of the granted folder.
+    pub path: String,
+}
+
+/// How a grant request related to the grants already in place.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GrantStatus {
+    /// The folder was added to the scope.
+    Granted,
+    /// The folder or one of its ancestors was already granted; nothing was added.
+    AlreadyCovered,
+    /// The folder was added and replaces grants for folders inside it.
+    Merged,
+}
+
+/// Result of granting a workspace: the path as picked and the target actually granted.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantedWorkspace {
+    pub path: String,
+    pub canonical_path: PathBuf,
+    pub status: GrantStatus,
+    /// The existing grant that already covers the folder, for `AlreadyCovered`.
+    pub covered_by: Option<PathBuf>,
+    /// Grants for subfolders absorbed into this one, for `Merged`.
+    pub absorbed: Vec<PathBuf>,
+    /// Whether the grant that gives access to the folder includes its subfolders.
+    pub recursive: bool,
+    /// Paths added to the FS scope: the canonical target, then the path as picked if
+    /// it reaches the target through a symlink. Empty for `AlreadyCovered`.
+    pub granted_paths: Vec<PathBuf>,
+    /// Sync service managing the folder (`icloud`, `dropbox`, ...), whose files may
+    /// need `materialize_file` before they can be read.
+    pub cloud_provider: Option<&'static str>,
+}
+
+/// Outcome of one folder in `allow_workspace_dirs`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathResult {
+    pub path: String,
+    /// The grant, on success.
+    pub granted: Option<GrantedWorkspace>,
+    /// Why the folder wasn't granted; `None` on success.
+    pub error: Option<String>,
+}
+
+/// A directory added to the FS scope during this session.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceGrant {
+    pub path: String,
+    pub recursive: bool,
+    /// Unix timestamp in milliseconds.
+    pub granted_at: u64,
+    /// Symlinked paths to the folder that were granted along with it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+}
+
+/// Bookkeeping of every scope grant, since the plugin scope itself only exposes
+/// raw glob patterns.
+#[derive(Default)]
+pub struct WorkspaceGrants(Mutex<Vec<WorkspaceGrant>>);
+
+impl WorkspaceGrants {
+    pub fn record(&self, canonical: &Path, recursive: bool) {
+        let Ok(mut grants) = self.0.lock() else {
+            return;
+        };
+        let path = canonical.to_string_lossy().into_owned();
+        let mut links = Vec::new();
+        grants.retain(|g| {
+            if g.path != path {
+                return true;
+            }
+            links.clone_from(&g.links);
+            false
+        });
+        grants.push(WorkspaceGrant {
+            path,
+            recursive,
+            granted_at: now_millis(),
+            links,
+        });
+    }
+
+    /// Note that `link`, a symlinked path to the granted folder `canonical`, was
+    /// granted too, so revoking the folder revokes it as well.
+    pub fn add_link(&self, canonical: &Path, link: &Path) {
+        let Ok(mut grants) = self.0.lock() else {
+            return;
+        };
+        let path = canonical.to_string_lossy();
+        let link = link.to_string_lossy().into_owned();
+        if let Some(grant) = grants.iter_mut().find(|g| g.path == path) {
+            if !grant.links.contains(&link) {
+                grant.links.push(link);
+            }
+        }
+    }
+
+    /// Symlinked paths granted along with `canonical`.
+    pub fn links(&self, canonical: &Path) -> Vec<PathBuf> {
+        let path = canonical.to_string_lossy();
+        self.0
+            .lock()
+            .ok()
+            .and_then(|grants| {
+                let grant = grants.iter().find(|g| g.path == path)?;
+                Some(grant.links.iter().map(PathBuf::from).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, canonical: &Path) {
+        if let Ok(mut grants) = self.0.lock() {
+            let path = canonical.to_string_lossy();
+            grants.retain(|g| g.path != path);
+        }
+    }
+
+    pub fn contains(&self, canonical: &Path) -> bool {
+        let path = canonical.to_string_lossy();
+        self.0
+            .lock()
+            .map(|grants| grants.iter().any(|g| g.path == path))
+            .unwrap_or(false)
+    }
+
+    /// Whether `canonical` itself was granted with its subfolders, or `None` if it
+    /// wasn't granted directly.
+    pub fn is_recursive(&self, canonical: &Path) -> Option<bool> {
+        let path = canonical.to_string_lossy();
+        let grants = self.0.lock().ok()?;
+        grants.iter().find(|g| g.path == path).map(|g| g.recursive)
+    }
+
+    /// Whether a recursive grant on `canonical` or one of its ancestors makes the
+    /// folders inside it accessible.
+    pub fn covers_subfolders(&self, canonical: &Path) -> bool {
+        self.0
+            .lock()
+            .map(|grants| {
+                grants
+                    .iter()
+                    .any(|g| g.recursive && paths::is_within(canonical, Path::new(&g.path)))
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn list(&self) -> Vec<WorkspaceGrant> {
+        self.0.lock().map(|g| g.clone()).unwrap_or_default()
+    }
+
+    /// The granted folder that covers `canonical`: the folder itself, or a recursively
+    /// granted ancestor.
+    pub fn covering(&self, canonical: &Path) -> Option<PathBuf> {
+        let grants = self.0.lock().ok()?;
+        grants
+            .iter()
+            .filter(|g| g.recursive || Path::new(&g.path) == canonical)
+            .map(|g| PathBuf::from(&g.path))
+            .filter(|granted| paths::is_within(canonical, granted))
+            // The closest ancestor is the most specific match.
+            .max_by_key(|granted| granted.as_os_str().len())
+    }
+
+    /// Drop grants for folders strictly inside `canonical` and return them, so a
+    /// recursive grant on `canonical` can take their place.
+    pub fn absorb_descendants(&self, canonical: &Path) -> Vec<PathBuf> {
+        let Ok(mut grants) = self.0.lock() else {
+            return Vec::new();
+        };
+        let mut absorbed = Vec::new();
+        grants.retain(|g| {
+            let path = Path::new(&g.path);
+            let inside = path != canonical && paths::is_within(path, canonical);
+            if inside {
+                absorbed.push(path.to_path_buf());
+            }
+            !inside
+        });
+        absorbed
+    }
+
+    /// Canonicalize a workspace path and check it is covered by a grant made this
+    /// session.
+    pub fn require(&self, path: &str) -> Result<PathBuf, NeoError> {
+        let canonical = Path::new(path)
+            .canonicalize()
+            .map_err(|e| NeoError::InvalidInput(format!("Invalid path: {e}")))?;
+        if self.covering(&canonical).is_none() {
+            return Err(NeoError::PermissionDenied(
+                "Workspace has not been granted".to_string(),
+            ));
+        }
+        Ok(canonical)
+    }
+}
+
+/// Undo `glob::Pattern::escape`, which wraps `?`, `*`, `[` and `]` in brackets.
+fn unescape_pattern(pattern: &str) -> String {
+    pattern
+        .replace("[?]", "?")
+        .replace("[*]", "*")
+        .replace("[[]", "[")
+        .replace("[]]", "]")
+}
+
+/// A directory granted in the live FS scope.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeDirectory {
+    pub path: PathBuf,
+    /// Granted with `<dir>/**` rather than only `<dir>/*`.
+    pub recursive: bool,
+}
+
+/// Directories explicitly granted in the FS scope, derived from its glob patterns.
+///
+/// `allow_directory` inserts both `<dir>` and `<dir>/**` (or `<dir>/*`), so a pattern
+/// counts as a directory grant only when that pair is present. Static capability
+/// globs and forbidden directories are excluded.
+pub fn scope_directories(app: &tauri::AppHandle) -> Vec<ScopeDirectory> {
+    let scope = app.fs_scope();
+    let patterns: HashSet<String> = scope
+        .allowed_patterns()
+        .iter()
+        .map(|p| p.as_str().to_string())
+        .collect();
+
+    let mut dirs: HashMap<PathBuf, bool> = HashMap::new();
+    for pattern in &patterns {
+        let (base, recursive) = match pattern.strip_suffix("**") {
+            Some(base) => (base, true),
+            None => match pattern.strip_suffix('*') {
+                Some(base) => (base, false),
+                None => continue,
+            },
+        };
+        let Some(base) = base.strip_suffix(MAIN_SEPARATOR) else {
+            continue;
+        };
+        if !patterns.contains(base) {
+            continue;
+        }
+        let dir = PathBuf::from(unescape_pattern(base));
+        if !scope.is_forbidden(&dir) {
+            *dirs.entry(dir).or_default() |= recursive;
+        }
+    }
+
+    let mut dirs: Vec<ScopeDirectory> = dirs
+        .into_iter()
+        .map(|(path, recursive)| ScopeDirectory { path, recursive })
+        .collect();
+    dirs.sort_by(|a, b| a.path.cmp(&b.path));
+    dirs
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Year, month (1 to 12) and day (1 to 31) of a Unix timestamp in milliseconds, in
+/// UTC.
+pub fn utc_date(millis: u64) -> (i64, i64, i64) {
+    // Howard Hinnant's days-to-civil algorithm.
+    let days = (millis / (24 * 60 * 60 * 1000)) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Re-apply the FS scope for every registered workspace.
+///
+/// Stale entries are skipped silently instead of failing startup; they stay in the
+/// registry so the user can see and remove them. Pending imported entries wait for
+/// the user to grant them on this machine. Workspaces inside another registered
+/// workspace are covered by its grant and aren't granted separately, but their own
+/// deny-lists still apply.
+pub fn restore(app: &tauri::AppHandle) {
+    let scope = app.fs_scope();
+    let entries = load(app)
+        .into_iter()
+        .filter(|e| !e.stale && !e.pending)
+        .collect();
+    restore_entries(
+        &app.state::<WorkspaceGrants>(),
+        entries,
+        |entry| scope.allow_directory(&entry.path, entry.recursive).is_ok(),
+        |entry| {
+            let _ = denylist::apply(app, &entry.path, &entry.denylist);
+        },
+    );
+}
+
+/// Grant each entry with `allow` unless an earlier grant covers it, then apply its
+/// deny-list with `deny`. Entries that fail to be granted are skipped.
+fn restore_entries(
+    grants: &WorkspaceGrants,
+    mut entries: Vec<WorkspaceEntry>,
+    mut allow: impl FnMut(&WorkspaceEntry) -> bool,
+    mut deny: impl FnMut(&WorkspaceEntry),
+) {
+    // Parents first, so their grants cover the workspaces nested inside them.
+    entries.sort_by_key(|e| e.path.components().count());
+
+    for entry in &entries {
+        if grants.covering(&entry.path).is_none() {
+            if !allow(entry) {
+                continue;
+            }
+            grants.record(&entry.path, entry.recursive);
+        }
+        deny(entry);
+    }
+}
+
+/// List the workspace directories that are re-granted on startup.
+#[tauri::command]
+pub fn get_persisted_workspaces(app: tauri::AppHandle) -> Vec<String> {
+    load(&app)
+        .into_iter()
+        .filter(|e| !e.stale && !e.pending)
+        .map(|e| e.path.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// List registered workspaces, most recently opened first.
+#[tauri::command]
+pub fn list_workspaces(app: tauri::AppHandle) -> Vec<WorkspaceEntry> {
+    load(&app)
+}
+
+/// Bump a registered workspace's last-opened timestamp.
+#[tauri::command]
+pub fn touch_workspace(app: tauri::AppHandle, path: String) -> Result<(), NeoError> {
+    let path = resolve(path);
+    update(&app, |entries| {
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.path == path)
+            .ok_or_else(|| NeoError::NotFound("Workspace is not registered".to_string()))?;
+        entry.last_opened = now_millis();
+        Ok(())
+    })
+}
+
+/// Remove a workspace from the registry. This does not revoke FS access granted in
+/// the current session; use `revoke_workspace_dir` for that.
+#[tauri::command]
+pub fn remove_workspace(app: tauri::AppHandle, path: String) -> Result<(), NeoError> {
+    if !forget(&app, &resolve(path))? {
+        return Err(NeoError::NotFound(
+            "Workspace is not registered".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the deny-list patterns of a workspace.
+#[tauri::command]
+pub fn get_workspace_denylist(app: tauri::AppHandle, path: String) -> Vec<String> {
+    denylist_for(&app, &resolve(path))
+}
+
+/// Replace the deny-list patterns of a registered workspace and apply them now if it
+/// is granted.
+///
+/// The scope can't lift a forbid, so patterns removed from the list stay in effect
+/// until the next launch.
+#[tauri::command]
+pub async fn set_workspace_denylist(
+    app: tauri::AppHandle,
+    path: String,
+    patterns: Vec<String>,
+) -> Result<(), NeoError> {
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    denylist::compile(&patterns)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = resolve(path);
+        update(&app, |entries| {
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.path == path)
+                .ok_or_else(|| NeoError::NotFound("Workspace is not registered".to_string()))?;
+            entry.denylist = patterns.clone();
+            Ok(())
+        })?;
+
+        if app.state::<WorkspaceGrants>().contains(&path) {
+            denylist::apply(&app, &path, &patterns)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| NeoError::Failed(format!("Failed to set deny-list: {e}")))?
+}
+
+/// List every directory Neo has been granted FS access to in this session.
+#[tauri::command]
+pub fn list_allowed_workspaces(grants: State<'_, WorkspaceGrants>) -> Vec<WorkspaceGrant> {
+    grants.list()
+}
+
+/// List the directories currently granted in the live FS scope.
+#[tauri::command]
+pub fn list_allowed_workspace_dirs(app: tauri::AppHandle) -> Vec<String> {
+    scope_directories(&app)
+        .into_iter()
+        .map(|d| d.path.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// List the directories granted in the live FS scope and whether each covers its
+/// subfolders, for debugging permission errors from the fs plugin.
+#[tauri::command]
+pub fn get_allowed_directories(app: tauri::AppHandle) -> Result<Vec<ScopeDirectory>, NeoError> {
+    Ok(scope_directories(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grants(recorded: &[(&str, bool)]) -> WorkspaceGrants {
+        let grants = WorkspaceGrants::default();
+        for (path, recursive) in recorded {
+            grants.record(Path::new(path), *recursive);
+        }
+        grants
+    }
+
+    #[test]
+    fn finds_the_closest_covering_grant() {
+        let grants = grants(&[("/w", true), ("/w/a", true), ("/w/b", false)]);
+        let covering = |path: &str| grants.covering(Path::new(path));
+        assert_eq!(covering("/w/a/x"), Some(PathBuf::from("/w/a")));
+        assert_eq!(covering("/w/b/x"), Some(PathBuf::from("/w")));
+        assert_eq!(covering("/w/b"), Some(PathBuf::from("/w/b")));
+        assert_eq!(covering("/wx"), None);
+        assert!(grants.covers_subfolders(Path::new("/w/b")));
+    }
+
+    #[test]
+    fn leaves_subfolders_of_shallow_grants_uncovered() {
+        let grants = grants(&[("/w", false)]);
+        assert_eq!(grants.covering(Path::new("/w/a")), None);
+        assert!(!grants.covers_subfolders(Path::new("/w")));
+        assert_eq!(grants.is_recursive(Path::new("/w")), Some(false));
+    }
+
+    #[test]
+    fn absorbs_only_strict_descendants() {
+        let grants = grants(&[
+            ("/w", false),
+            ("/w/a", false),
+            ("/w/a/b", true),
+            ("/wx", true),
+        ]);
+        let mut absorbed = grants.absorb_descendants(Path::new("/w"));
+        absorbed.sort();
+        assert_eq!(absorbed, [PathBuf::from("/w/a"), PathBuf::from("/w/a/b")]);
+        let left: Vec<String> = grants.list().into_iter().map(|g| g.path).collect();
+        assert_eq!(left, ["/w", "/wx"]);
+    }
+
+    fn entry(path: &str, denylist: &[&str]) -> WorkspaceEntry {
+        WorkspaceEntry {
+            path: PathBuf::from(path),
+            name: default_name(Path::new(path)),
+            last_opened: 0,
+            denylist: denylist.iter().map(|p| p.to_string()).collect(),
+            recursive: true,
+            workspace_id: None,
+            pending: false,
+            pending_config: None,
+            stale: false,
+        }
+    }
+
+    #[test]
+    fn applies_the_deny_list_of_a_covered_nested_workspace() {
+        let grants = WorkspaceGrants::default();
+        let entries = vec![entry("/w/a", &["secret/**"]), entry("/w", &["**/.env*"])];
+        let mut allowed = Vec::new();
+        let mut denied = Vec::new();
+        restore_entries(
+            &grants,
+            entries,
+            |entry| {
+                allowed.push(entry.path.clone());
+                true
+            },
+            |entry| denied.push((entry.path.clone(), entry.denylist.clone())),
+        );
+        assert_eq!(allowed, [PathBuf::from("/w")]);
+        assert_eq!(
+            denied,
+            [
+                (PathBuf::from("/w"), vec!["**/.env*".to_string()]),
+                (PathBuf::from("/w/a"), vec!["secret/**".to_string()]),
+            ]
+        );
+        assert!(!grants.contains(Path::new("/w/a")));
+    }
+
+    #[test]
+    fn keeps_links_until_the_grant_is_removed() {
+        let grants = grants(&[("/real", true)]);
+        let real = Path::new("/real");
+        grants.add_link(real, Path::new("/link"));
+        grants.add_link(real, Path::new("/link"));
+        grants.add_link(Path::new("/other"), Path::new("/stray"));
+        grants.record(real, false);
+        assert_eq!(grants.links(real), [PathBuf::from("/link")]);
+        assert!(grants.links(Path::new("/other")).is_empty());
+        grants.remove(real);
+        assert!(grants.links(real).is_empty());
+    }
+}